@@ -1,7 +1,17 @@
 use std::time::Instant;
 
 #[derive(Debug)]
-enum Error {}
+enum Error {
+    #[allow(dead_code)]
+    InvalidInstruction(String),
+    // Asked for the position after more instructions than there are. Contains how many there are.
+    #[allow(dead_code)]
+    NotEnoughInstructions(usize),
+}
+
+// The letters that turn the dial up or down, respectively.
+const INCREASE: &[char] = &['R'];
+const DECREASE: &[char] = &['L'];
 
 fn split_instruction(s: &str) -> Option<(char, i32)> {
     let mut chars = s.chars();
@@ -18,24 +28,81 @@ fn split_instruction(s: &str) -> Option<(char, i32)> {
     Some((letter, number))
 }
 
-fn part1(input: &str) -> Result<(), Error> {
-    let mut number = 50;
-    let mut zeroes = 0;
+// Maps the letter of an instruction to the direction the dial is turned in: 1 to increase, -1 to
+// decrease.
+fn direction(letter: char, increase: &[char], decrease: &[char]) -> Option<i32> {
+    if increase.contains(&letter) {
+        return Some(1);
+    }
+    if decrease.contains(&letter) {
+        return Some(-1);
+    }
+    return None;
+}
 
-    for line in input.lines() {
-        match split_instruction(line) {
-            Some(instruction) => match instruction.0 {
-                'L' => number = (number - instruction.1).rem_euclid(100),
-                'R' => number = (number + instruction.1).rem_euclid(100),
-                _ => panic!("Invalid instruction '{}'", line),
-            },
-            None => panic!("Invalid instruction '{}'", line),
-        }
+// Runs the instructions on a dial with the given number of positions. The letters in `increase` and
+// `decrease` are the aliases for turning the dial up or down. Returns the final position and how
+// often the dial ended up at 0.
+fn simulate_with_aliases(
+    input: &str,
+    start: i32,
+    modulus: i32,
+    increase: &[char],
+    decrease: &[char],
+) -> Result<(i32, u32), Error> {
+    let (number, landings) = simulate_landings(input, start, modulus, increase, decrease)?;
+    return Ok((number, landings.len() as u32));
+}
+
+// The indices of the instructions after which the dial was at 0.
+#[allow(dead_code)]
+fn landings_on_zero(input: &str, start: i32, modulus: i32) -> Result<Vec<usize>, Error> {
+    let (_, landings) = simulate_landings(input, start, modulus, INCREASE, DECREASE)?;
+    return Ok(landings);
+}
+
+// The position of the dial after running the first `k` instructions.
+#[allow(dead_code)]
+fn position_at(input: &str, start: i32, modulus: i32, k: usize) -> Result<i32, Error> {
+    let lines: Vec<&str> = input.lines().take(k).collect();
+    if lines.len() < k {
+        return Err(Error::NotEnoughInstructions(lines.len()));
+    }
+    let (number, _) = simulate_landings(&lines.join("\n"), start, modulus, INCREASE, DECREASE)?;
+    return Ok(number);
+}
+
+// Like `simulate_with_aliases`, but returns the indices of the instructions that ended up at 0.
+fn simulate_landings(
+    input: &str,
+    start: i32,
+    modulus: i32,
+    increase: &[char],
+    decrease: &[char],
+) -> Result<(i32, Vec<usize>), Error> {
+    let mut number = start;
+    let mut landings = Vec::new();
+
+    for (index, line) in input.lines().enumerate() {
+        let Some((letter, distance)) = split_instruction(line) else {
+            return Err(Error::InvalidInstruction(line.to_string()));
+        };
+        let Some(sign) = direction(letter, increase, decrease) else {
+            return Err(Error::InvalidInstruction(line.to_string()));
+        };
+
+        number = (number + sign * distance).rem_euclid(modulus);
         if number == 0 {
-            zeroes += 1;
+            landings.push(index);
         }
     }
 
+    return Ok((number, landings));
+}
+
+fn part1(input: &str) -> Result<(), Error> {
+    let (_, zeroes) = simulate_with_aliases(input, 50, 100, INCREASE, DECREASE)?;
+
     println!("Part 1: {}", zeroes);
     return Ok(());
 }
@@ -46,8 +113,8 @@ fn part2(input: &str) -> Result<(), Error> {
 
     for line in input.lines() {
         match split_instruction(line) {
-            Some(instruction) => match instruction.0 {
-                'L' => {
+            Some(instruction) => match direction(instruction.0, INCREASE, DECREASE) {
+                Some(-1) => {
                     let intermediate = number - instruction.1;
                     zeroes += (intermediate / 100).abs();
                     // I'm sure there's a more elegant way to solve this. Account for some special cases:
@@ -59,16 +126,16 @@ fn part2(input: &str) -> Result<(), Error> {
 
                     number = intermediate.rem_euclid(100);
                 }
-                'R' => {
+                Some(_) => {
                     let intermediate = number + instruction.1;
                     // Easy: just divide by 100 to get how many times we've crossed 0.
                     // Also handles when the dial lands exactly on 0 again.
                     zeroes += intermediate / 100;
                     number = intermediate.rem_euclid(100);
                 }
-                _ => panic!("Invalid instruction '{}'", line),
+                None => return Err(Error::InvalidInstruction(line.to_string())),
             },
-            None => panic!("Invalid instruction '{}'", line),
+            None => return Err(Error::InvalidInstruction(line.to_string())),
         }
     }
 
@@ -89,3 +156,58 @@ fn main() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulate_with_aliases() {
+        let input = include_str!("../rsc/sample1.txt");
+        let result = simulate_with_aliases(input, 50, 100, &['R'], &['L']).unwrap();
+        assert_eq!(result, (32, 3));
+
+        let aliased = input.replace('R', "U").replace('L', "D");
+        assert_eq!(
+            simulate_with_aliases(&aliased, 50, 100, &['U', 'R'], &['D', 'L']).unwrap(),
+            result
+        );
+        assert!(matches!(
+            simulate_with_aliases(&aliased, 50, 100, &['R'], &['L']),
+            Err(Error::InvalidInstruction(_))
+        ));
+    }
+
+    #[test]
+    fn test_landings_on_zero() {
+        let input = include_str!("../rsc/sample1.txt");
+        let landings = landings_on_zero(input, 50, 100).unwrap();
+        assert_eq!(landings, vec![2, 5, 7]);
+        assert_eq!(
+            landings.len() as u32,
+            simulate_with_aliases(input, 50, 100, INCREASE, DECREASE)
+                .unwrap()
+                .1
+        );
+
+        assert_eq!(
+            landings_on_zero("R5\nL10\nR5\nR10", 0, 10).unwrap(),
+            vec![2, 3]
+        );
+    }
+
+    #[test]
+    fn test_position_at() {
+        let input = include_str!("../rsc/sample1.txt");
+        let positions: Vec<i32> = (0..=10)
+            .map(|k| position_at(input, 50, 100, k).unwrap())
+            .collect();
+        assert_eq!(positions, vec![50, 82, 52, 0, 95, 55, 0, 99, 0, 14, 32]);
+        assert!(matches!(
+            position_at(input, 50, 100, 11),
+            Err(Error::NotEnoughInstructions(10))
+        ));
+
+        assert_eq!(position_at("R5\nL10\nR5\nR10", 0, 10, 2).unwrap(), 5);
+    }
+}
@@ -0,0 +1,26 @@
+use aoc::{Machine, gen_input};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+// Run with `cargo bench --features bench`. That both find the same number of presses is checked
+// by `test_light_up_gray_code`. The Gray code enumeration doubles with each button, the BFS only
+// depends on the number of lights.
+fn bench_gray_code(c: &mut Criterion) {
+    let mut group = c.benchmark_group("gray_code");
+    group.sample_size(10);
+    for buttons in [20, 24] {
+        let machines = Machine::from_input(&gen_input(1, 10, buttons, 42).unwrap()).unwrap();
+        let machine = &machines[0];
+        group.bench_with_input(BenchmarkId::new("bfs", buttons), machine, |b, machine| {
+            b.iter(|| machine.light_up_bfs().unwrap())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("gray code", buttons),
+            machine,
+            |b, machine| b.iter(|| machine.light_up_gray_code().unwrap()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_gray_code);
+criterion_main!(benches);
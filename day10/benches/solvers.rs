@@ -0,0 +1,32 @@
+use aoc::{LightSolver, Machine, Solver, best_joltage_solutions, gen_input, light_up_all};
+use criterion::{Criterion, criterion_group, criterion_main};
+
+// Run with `cargo bench`. That the solvers agree is checked by the tests of each solver.
+fn bench_light_up(c: &mut Criterion) {
+    let machines = Machine::from_input(&gen_input(1, 10, 20, 42).unwrap()).unwrap();
+    let mut group = c.benchmark_group("light_up");
+    group.sample_size(10);
+    group.bench_function("bfs", |b| {
+        b.iter(|| light_up_all(&machines, LightSolver::Bfs, false).unwrap())
+    });
+    group.bench_function("meet in the middle", |b| {
+        b.iter(|| light_up_all(&machines, LightSolver::MeetInTheMiddle, false).unwrap())
+    });
+    group.finish();
+}
+
+fn bench_best_joltage(c: &mut Criterion) {
+    let machines = Machine::from_input(&gen_input(100, 10, 13, 42).unwrap()).unwrap();
+    let mut group = c.benchmark_group("best_joltage");
+    group.sample_size(10);
+    group.bench_function("native", |b| {
+        b.iter(|| best_joltage_solutions(&machines, Solver::Native).unwrap())
+    });
+    group.bench_function("auto", |b| {
+        b.iter(|| best_joltage_solutions(&machines, Solver::Auto).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_light_up, bench_best_joltage);
+criterion_main!(benches);
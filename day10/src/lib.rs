@@ -0,0 +1,2176 @@
+use rng::next_random;
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap};
+use std::ops::BitXor;
+#[cfg(feature = "z3")]
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub enum Error {
+    // A line doesn't follow the grammar of a machine. Lines and columns are counted from 1.
+    #[allow(dead_code)]
+    Syntax {
+        line_no: usize,
+        column: usize,
+        message: String,
+    },
+    #[allow(dead_code)]
+    InvalidArgument(String),
+    NoSolution,
+    // The lights of a machine cannot be reached with its buttons. Machines are counted from 1.
+    #[allow(dead_code)]
+    Unsolvable {
+        machine_no: usize,
+    },
+    // z3 proved that a machine's joltages cannot be reached. Machines are counted from 1.
+    #[cfg(feature = "z3")]
+    #[allow(dead_code)]
+    Unsatisfiable {
+        machine_no: usize,
+    },
+    // z3 hit its timeout (or gave up for another reason) without finding the minimum.
+    #[cfg(feature = "z3")]
+    #[allow(dead_code)]
+    SolverGaveUp {
+        machine_no: usize,
+        elapsed: Duration,
+    },
+    // A button refers to a light that doesn't exist, indices must be less than `max`. Machines
+    // and buttons are counted from 1.
+    #[allow(dead_code)]
+    IndexOutOfRange {
+        machine_no: usize,
+        button_no: usize,
+        index: usize,
+        max: usize,
+    },
+    // A button lists the same light twice. Pressing it would toggle the light twice, which is
+    // most likely a typo.
+    #[allow(dead_code)]
+    DuplicateIndex {
+        machine_no: usize,
+        button_no: usize,
+        index: usize,
+    },
+    // A machine has more lights than a `LightState` can hold.
+    #[allow(dead_code)]
+    TooManyLights {
+        machine_no: usize,
+        lights: usize,
+        max: usize,
+    },
+    // Each light needs exactly one joltage.
+    #[allow(dead_code)]
+    JoltageCountMismatch {
+        machine_no: usize,
+        lights: usize,
+        joltages: usize,
+    },
+}
+
+// How to solve part 2. `Native` is the Gauss-Jordan elimination, `Auto` picks between that and
+// the DP for each machine. z3 is only available when built with the `z3` feature. It can be given
+// a timeout in milliseconds, if it runs out the native solver takes over.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Solver {
+    Auto,
+    Native,
+    Dp,
+    #[cfg(feature = "z3")]
+    Z3 {
+        timeout: Option<u32>,
+    },
+}
+
+// How to solve part 1. `Auto` picks based on the number of lights and buttons of each machine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightSolver {
+    Auto,
+    Bfs,
+    MeetInTheMiddle,
+}
+
+pub type Button = Vec<usize>;
+
+// The memo of `Machine::dp_cost`: for each button index and residual joltages, the cheapest cost
+// and how often that button is pressed for it. `None` if the residual cannot be reached.
+type DpMemo = HashMap<(usize, Vec<usize>), Option<(u64, u64)>>;
+
+// The number of 64 bit words in a `LightState`, and thus how many lights a machine can have.
+const LIGHT_WORDS: usize = 2;
+const MAX_LIGHTS: usize = 64 * LIGHT_WORDS;
+
+// Which lights are on, as a bitmask: light `i` is bit `i % 64` of word `i / 64`. Unlike a
+// `Vec<bool>`, this is `Copy` and pressing a button is a couple of xors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+struct LightState([u64; LIGHT_WORDS]);
+
+// Equal machines have the same solutions, see `Machine::normalized`.
+#[derive(PartialEq, Eq, Hash)]
+pub struct Machine {
+    lights: LightState,
+    light_count: usize,
+    buttons: Vec<Button>,
+    // What each press of a button costs. That's 1 unless given in the input, like `(0,2)*5`.
+    costs: Vec<u64>,
+    joltage: Vec<usize>,
+}
+
+// Which buttons to press to light up a machine, pressing each at most once.
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub presses: Vec<bool>,
+    pub count: usize,
+    pub cost: u64,
+}
+
+// How often to press each button to reach the joltages.
+#[derive(Debug, Clone)]
+pub struct Solution2 {
+    pub presses: Vec<u64>,
+    pub total: u64,
+    pub cost: u64,
+}
+
+// The equations of part 2 after Gauss-Jordan elimination. Row `i` reads
+// `pivot * presses[pivots[i]] + sum(coefficient * presses[free button]) = value`, the columns of
+// the other pivot buttons are 0.
+struct ReducedSystem {
+    // One row per pivot: the coefficients of all buttons followed by the value.
+    rows: Vec<Vec<i64>>,
+    // The button whose presses each row determines.
+    pivots: Vec<usize>,
+    // The buttons that can be pressed freely, and how often at most.
+    free: Vec<usize>,
+    bounds: Vec<i64>,
+    // The cost of a press of each button.
+    costs: Vec<i64>,
+    // The number of buttons.
+    columns: usize,
+}
+
+impl LightState {
+    // The state with the given lights toggled, starting with all lights off. Lights must be less
+    // than `MAX_LIGHTS`.
+    fn from_lights(lights: &[usize]) -> LightState {
+        let mut state = LightState::default();
+        for light in lights {
+            state.toggle(*light);
+        }
+        return state;
+    }
+
+    fn toggle(&mut self, light: usize) {
+        self.0[light / 64] ^= 1 << (light % 64);
+    }
+
+    #[allow(dead_code)]
+    fn is_on(&self, light: usize) -> bool {
+        return self.0[light / 64] & 1 << (light % 64) != 0;
+    }
+}
+
+impl BitXor for LightState {
+    type Output = LightState;
+
+    fn bitxor(self, other: LightState) -> LightState {
+        let mut words = self.0;
+        for (word, other) in words.iter_mut().zip(other.0) {
+            *word ^= other;
+        }
+        return LightState(words);
+    }
+}
+
+// A recursive-descent parser for a single machine like `[.##.] (3) (1,3) (2) {3,5,4,7}`: the
+// lights, space separated buttons and the joltages.
+struct MachineParser {
+    chars: Vec<char>,
+    position: usize,
+    line_no: usize,
+}
+
+impl MachineParser {
+    fn new(line: &str, line_no: usize) -> MachineParser {
+        return MachineParser {
+            chars: line.chars().collect(),
+            position: 0,
+            line_no,
+        };
+    }
+
+    fn error(&self, message: String) -> Error {
+        return Error::Syntax {
+            line_no: self.line_no,
+            column: self.position + 1,
+            message,
+        };
+    }
+
+    fn peek(&self) -> Option<char> {
+        return self.chars.get(self.position).copied();
+    }
+
+    // The next character, for error messages.
+    fn found(&self) -> String {
+        match self.peek() {
+            Some(c) => return format!("'{}'", c),
+            None => return "end of line".to_string(),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        if self.peek() != Some(expected) {
+            return Err(self.error(format!("expected '{}', found {}", expected, self.found())));
+        }
+        self.position += 1;
+        return Ok(());
+    }
+
+    // Returns the number of skipped characters.
+    fn skip_spaces(&mut self) -> usize {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.position += 1;
+        }
+        return self.position - start;
+    }
+
+    fn number(&mut self, what: &str) -> Result<usize, Error> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+        if start == self.position {
+            return Err(self.error(format!(
+                "expected a number in the {}, found {}",
+                what,
+                self.found()
+            )));
+        }
+        let digits: String = self.chars[start..self.position].iter().collect();
+        match digits.parse() {
+            Ok(number) => return Ok(number),
+            Err(_) => {
+                self.position = start;
+                return Err(self.error(format!("number too large: {}", digits)));
+            }
+        }
+    }
+
+    // At least one number, separated by commas and enclosed by `open` and `close`.
+    fn list(&mut self, open: char, close: char, what: &str) -> Result<Vec<usize>, Error> {
+        self.expect(open)?;
+        let mut numbers = vec![self.number(what)?];
+        loop {
+            match self.peek() {
+                Some(',') => {
+                    self.position += 1;
+                    numbers.push(self.number(what)?);
+                }
+                Some(c) if c == close => {
+                    self.position += 1;
+                    return Ok(numbers);
+                }
+                _ => {
+                    return Err(self.error(format!(
+                        "expected ',' or '{}' in the {}, found {}",
+                        close,
+                        what,
+                        self.found()
+                    )));
+                }
+            }
+        }
+    }
+
+    // `[.##.]`: the number of lights and the ones that are on.
+    fn lights(&mut self) -> Result<(usize, Vec<usize>), Error> {
+        self.skip_spaces();
+        self.expect('[')?;
+        let mut count = 0;
+        let mut on = Vec::new();
+        loop {
+            match self.peek() {
+                Some('.') => {}
+                Some('#') => on.push(count),
+                Some(']') => break,
+                _ => {
+                    return Err(self.error(format!(
+                        "expected '.', '#' or ']' in the lights, found {}",
+                        self.found()
+                    )));
+                }
+            }
+            count += 1;
+            self.position += 1;
+        }
+        self.position += 1;
+        return Ok((count, on));
+    }
+
+    // `(3) (1,3)*2 (2)`, at least one button, and the cost of each. Stops in front of the
+    // joltages.
+    fn buttons(&mut self) -> Result<(Vec<Button>, Vec<u64>), Error> {
+        let mut buttons = Vec::new();
+        let mut costs = Vec::new();
+        loop {
+            let spaces = self.skip_spaces();
+            match self.peek() {
+                Some('(') if spaces > 0 => {
+                    buttons.push(self.list('(', ')', "button")?);
+                    costs.push(self.cost()?);
+                }
+                Some('{') if spaces > 0 && !buttons.is_empty() => return Ok((buttons, costs)),
+                _ if spaces == 0 => {
+                    return Err(self.error(format!("expected a space, found {}", self.found())));
+                }
+                _ if buttons.is_empty() => {
+                    return Err(self.error(format!("expected a button, found {}", self.found())));
+                }
+                _ => {
+                    return Err(self.error(format!(
+                        "expected a button or the joltages, found {}",
+                        self.found()
+                    )));
+                }
+            }
+        }
+    }
+
+    // The optional `*5` after a button.
+    fn cost(&mut self) -> Result<u64, Error> {
+        if self.peek() != Some('*') {
+            return Ok(1);
+        }
+        self.position += 1;
+        let start = self.position;
+        let cost = self.number("cost")?;
+        if cost == 0 {
+            self.position = start;
+            return Err(self.error("a button's cost must be at least 1".to_string()));
+        }
+        return Ok(cost as u64);
+    }
+
+    // `{3,5,4,7}`, which must end the line.
+    fn joltages(&mut self) -> Result<Vec<usize>, Error> {
+        let joltages = self.list('{', '}', "joltages")?;
+        self.skip_spaces();
+        if self.peek().is_some() {
+            return Err(self.error(format!(
+                "expected the end of the line after the joltages, found {}",
+                self.found()
+            )));
+        }
+        return Ok(joltages);
+    }
+}
+
+// Solutions list something for each button of a machine, so they need to follow when the
+// buttons are reordered.
+trait Reorder {
+    // `order` has the new index of each button, like returned by `Machine::normalized`.
+    fn reordered(&self, order: &[usize]) -> Self;
+}
+
+fn reorder_presses<T: Copy + Default>(presses: &[T], order: &[usize]) -> Vec<T> {
+    let mut reordered = vec![T::default(); presses.len()];
+    for (press, index) in presses.iter().zip(order) {
+        reordered[*index] = *press;
+    }
+    return reordered;
+}
+
+impl Reorder for Solution {
+    fn reordered(&self, order: &[usize]) -> Solution {
+        return Solution {
+            presses: reorder_presses(&self.presses, order),
+            count: self.count,
+            cost: self.cost,
+        };
+    }
+}
+
+impl Reorder for Solution2 {
+    fn reordered(&self, order: &[usize]) -> Solution2 {
+        return Solution2 {
+            presses: reorder_presses(&self.presses, order),
+            total: self.total,
+            cost: self.cost,
+        };
+    }
+}
+
+impl<T: Reorder> Reorder for Option<T> {
+    fn reordered(&self, order: &[usize]) -> Option<T> {
+        return self.as_ref().map(|solution| solution.reordered(order));
+    }
+}
+
+// The most states `Solver::Auto` lets the DP handle.
+const MAX_DP_STATES: u64 = 1_000_000;
+
+// Meet in the middle enumerates the subsets of each half of the buttons as bitmasks.
+const MAX_MITM_BUTTONS: usize = 64;
+
+// The Gray code enumeration tries all 2^buttons subsets, beyond this it would take forever.
+#[cfg(any(test, feature = "bench"))]
+const MAX_GRAY_CODE_BUTTONS: usize = 32;
+
+// Generated machines press each button less often than this.
+const MAX_GEN_PRESSES: u64 = 10;
+
+// The lights toggled by pressing the buttons whose bits are set in `subset`.
+fn subset_state(buttons: &[LightState], subset: u64) -> LightState {
+    return buttons
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| subset & 1 << index != 0)
+        .fold(LightState::default(), |state, (_, button)| state ^ *button);
+}
+
+// The cost of pressing the buttons whose bits are set in `subset`.
+fn subset_cost(costs: &[u64], subset: u64) -> u64 {
+    return costs
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| subset & 1 << index != 0)
+        .map(|(_, cost)| cost)
+        .sum();
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        return a.abs();
+    }
+    return gcd(b, a % b);
+}
+
+impl Machine {
+    pub fn from_input(input: &str) -> Result<Vec<Machine>, Error> {
+        // Every line needs to be a machine, otherwise it would silently be missing from the sums.
+        let mut machines = Vec::new();
+        for (index, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut parser = MachineParser::new(line, index + 1);
+            let (light_count, on) = parser.lights()?;
+            let (buttons, costs) = parser.buttons()?;
+            let joltage = parser.joltages()?;
+
+            let machine_no = machines.len() + 1;
+            if light_count > MAX_LIGHTS {
+                return Err(Error::TooManyLights {
+                    machine_no,
+                    lights: light_count,
+                    max: MAX_LIGHTS,
+                });
+            }
+
+            if light_count != joltage.len() {
+                return Err(Error::JoltageCountMismatch {
+                    machine_no,
+                    lights: light_count,
+                    joltages: joltage.len(),
+                });
+            }
+            for (button_index, button) in buttons.iter().enumerate() {
+                for (position, index) in button.iter().enumerate() {
+                    if *index >= light_count {
+                        return Err(Error::IndexOutOfRange {
+                            machine_no,
+                            button_no: button_index + 1,
+                            index: *index,
+                            max: light_count,
+                        });
+                    }
+                    if button[..position].contains(index) {
+                        return Err(Error::DuplicateIndex {
+                            machine_no,
+                            button_no: button_index + 1,
+                            index: *index,
+                        });
+                    }
+                }
+            }
+
+            machines.push(Machine {
+                lights: LightState::from_lights(&on),
+                light_count,
+                buttons,
+                costs,
+                joltage,
+            });
+        }
+
+        return Ok(machines);
+    }
+
+    // A copy with the lights of each button and the buttons themselves sorted, so machines that
+    // only differ in the order of their buttons are equal. Also returns the index of each of the
+    // copy's buttons in this machine.
+    fn normalized(&self) -> (Machine, Vec<usize>) {
+        let buttons: Vec<Button> = self
+            .buttons
+            .iter()
+            .map(|button| {
+                let mut button = button.clone();
+                button.sort();
+                button
+            })
+            .collect();
+        let mut order: Vec<usize> = (0..buttons.len()).collect();
+        order.sort_by_key(|index| (&buttons[*index], self.costs[*index]));
+        let machine = Machine {
+            lights: self.lights,
+            light_count: self.light_count,
+            buttons: order.iter().map(|index| buttons[*index].clone()).collect(),
+            costs: order.iter().map(|index| self.costs[*index]).collect(),
+            joltage: self.joltage.clone(),
+        };
+        return (machine, order);
+    }
+
+    pub fn buttons(&self) -> &[Button] {
+        return &self.buttons;
+    }
+
+    fn light_up(&self, solver: LightSolver) -> Result<Solution, Error> {
+        match solver {
+            LightSolver::Auto if self.prefers_meet_in_the_middle() => return self.light_up_mitm(),
+            LightSolver::Auto | LightSolver::Bfs if self.is_weighted() => {
+                return self.light_up_dijkstra();
+            }
+            LightSolver::Auto | LightSolver::Bfs => return self.light_up_bfs(),
+            LightSolver::MeetInTheMiddle => return self.light_up_mitm(),
+        }
+    }
+
+    // Whether any button costs more than one press.
+    fn is_weighted(&self) -> bool {
+        return self.costs.iter().any(|cost| *cost != 1);
+    }
+
+    // The BFS visits at most 2^min(lights, buttons) states, usually fewer since it stops at the
+    // solution. Meet in the middle always enumerates 2^(buttons / 2) subsets twice. So only
+    // switch if the BFS might be a lot worse.
+    fn prefers_meet_in_the_middle(&self) -> bool {
+        let buttons = self.buttons.len();
+        return buttons <= MAX_MITM_BUTTONS
+            && self.light_count.min(buttons) > buttons.div_ceil(2) + 4;
+    }
+
+    // The lights that are on after pressing the given buttons.
+    #[allow(dead_code)]
+    fn apply_presses(&self, presses: &[bool]) -> LightState {
+        let mut lights = LightState::default();
+        for (button, pressed) in self.buttons.iter().zip(presses) {
+            if *pressed {
+                lights = lights ^ LightState::from_lights(button);
+            }
+        }
+        return lights;
+    }
+
+    // The joltages after pressing each button the given number of times.
+    #[allow(dead_code)]
+    fn apply_joltage_presses(&self, presses: &[u64]) -> Vec<usize> {
+        let mut joltage = vec![0; self.joltage.len()];
+        for (button, count) in self.buttons.iter().zip(presses) {
+            for index in button {
+                joltage[*index] += *count as usize;
+            }
+        }
+        return joltage;
+    }
+
+    // Breadth-first search over the light states, starting with all lights off. Each level adds
+    // one press, so the first level containing the target is the minimum. Since every state is
+    // only visited once, there are at most 2^lights states to check no matter how many buttons
+    // there are. Remembering where each state was reached from gives the buttons to press.
+    pub fn light_up_bfs(&self) -> Result<Solution, Error> {
+        let target = self.lights;
+        let buttons: Vec<LightState> = self
+            .buttons
+            .iter()
+            .map(|button| LightState::from_lights(button))
+            .collect();
+
+        // The previous state and the button pressed to get from there.
+        let mut predecessors: HashMap<LightState, (LightState, usize)> = HashMap::new();
+        let all_off = LightState::default();
+        let mut level = vec![all_off];
+        while !level.is_empty() && !level.contains(&target) {
+            let mut next = Vec::new();
+            for state in level {
+                for (index, button) in buttons.iter().enumerate() {
+                    let pressed = state ^ *button;
+                    if pressed != all_off && !predecessors.contains_key(&pressed) {
+                        predecessors.insert(pressed, (state, index));
+                        next.push(pressed);
+                    }
+                }
+            }
+            level = next;
+        }
+        if level.is_empty() {
+            return Err(Error::NoSolution);
+        }
+
+        let mut presses = vec![false; buttons.len()];
+        let mut count = 0;
+        let mut state = target;
+        while let Some((previous, button)) = predecessors.get(&state) {
+            // A shortest path never presses a button twice, that would cancel out.
+            presses[*button] = true;
+            count += 1;
+            state = *previous;
+        }
+        return Ok(Solution {
+            presses,
+            count,
+            cost: count as u64,
+        });
+    }
+
+    // Like the BFS, but for buttons with different costs the fewest presses aren't necessarily
+    // the cheapest. So always continue with the cheapest state that hasn't been finished yet.
+    fn light_up_dijkstra(&self) -> Result<Solution, Error> {
+        let buttons: Vec<LightState> = self
+            .buttons
+            .iter()
+            .map(|button| LightState::from_lights(button))
+            .collect();
+
+        let all_off = LightState::default();
+        let mut costs: HashMap<LightState, u64> = HashMap::from([(all_off, 0)]);
+        let mut predecessors: HashMap<LightState, (LightState, usize)> = HashMap::new();
+        let mut queue = BinaryHeap::from([Reverse((0, all_off))]);
+        while let Some(Reverse((cost, state))) = queue.pop() {
+            if state == self.lights {
+                break;
+            }
+            if costs.get(&state).is_some_and(|best| cost > *best) {
+                // Already reached more cheaply.
+                continue;
+            }
+            for (index, button) in buttons.iter().enumerate() {
+                let pressed = state ^ *button;
+                let pressed_cost = cost + self.costs[index];
+                if costs.get(&pressed).is_none_or(|best| pressed_cost < *best) {
+                    costs.insert(pressed, pressed_cost);
+                    predecessors.insert(pressed, (state, index));
+                    queue.push(Reverse((pressed_cost, pressed)));
+                }
+            }
+        }
+        let cost = *costs.get(&self.lights).ok_or(Error::NoSolution)?;
+
+        // With positive costs, the cheapest path never presses a button twice either.
+        let mut presses = vec![false; buttons.len()];
+        let mut state = self.lights;
+        while let Some((previous, button)) = predecessors.get(&state) {
+            presses[*button] = true;
+            state = *previous;
+        }
+        let count = presses.iter().filter(|pressed| **pressed).count();
+        return Ok(Solution {
+            presses,
+            count,
+            cost,
+        });
+    }
+
+    // Meet in the middle: every subset of the first half of the buttons is stored by the state it
+    // results in, keeping the cheapest one. Then for every subset of the second half, the first
+    // half needs to provide the remaining lights of the target.
+    fn light_up_mitm(&self) -> Result<Solution, Error> {
+        if self.buttons.len() > MAX_MITM_BUTTONS {
+            return Err(Error::InvalidArgument(format!(
+                "Too many buttons for meet in the middle: {}",
+                self.buttons.len()
+            )));
+        }
+        let buttons: Vec<LightState> = self
+            .buttons
+            .iter()
+            .map(|button| LightState::from_lights(button))
+            .collect();
+        let (first, second) = buttons.split_at(buttons.len() / 2);
+        let (first_costs, second_costs) = self.costs.split_at(first.len());
+
+        // The cheapest subset for each state, and its cost.
+        let mut first_subsets: HashMap<LightState, (u64, u64)> = HashMap::new();
+        for subset in 0..1u64 << first.len() {
+            let state = subset_state(first, subset);
+            let cost = subset_cost(first_costs, subset);
+            let best = first_subsets.entry(state).or_insert((subset, cost));
+            if cost < best.1 {
+                *best = (subset, cost);
+            }
+        }
+
+        let mut best: Option<(u64, u64, u64)> = None;
+        for subset in 0..1u64 << second.len() {
+            let missing = self.lights ^ subset_state(second, subset);
+            if let Some((first_subset, first_cost)) = first_subsets.get(&missing) {
+                let cost = first_cost + subset_cost(second_costs, subset);
+                if best.is_none_or(|(_, _, best_cost)| cost < best_cost) {
+                    best = Some((*first_subset, subset, cost));
+                }
+            }
+        }
+
+        let (first_subset, second_subset, cost) = best.ok_or(Error::NoSolution)?;
+        let presses: Vec<bool> = (0..buttons.len())
+            .map(|index| {
+                if index < first.len() {
+                    first_subset & 1 << index != 0
+                } else {
+                    second_subset & 1 << (index - first.len()) != 0
+                }
+            })
+            .collect();
+        let count = presses.iter().filter(|pressed| **pressed).count();
+        return Ok(Solution {
+            presses,
+            count,
+            cost,
+        });
+    }
+
+    // Tries every subset of the buttons, in Gray code order: the next subset differs in a single
+    // button, the lowest bit set in the subset's index. So each step is a single xor on the
+    // light state and a single addition or subtraction of a cost, without any allocations.
+    // Only feasible for few buttons, but simple enough to cross-check the other solvers.
+    #[cfg(any(test, feature = "bench"))]
+    pub fn light_up_gray_code(&self) -> Result<Solution, Error> {
+        if self.buttons.len() > MAX_GRAY_CODE_BUTTONS {
+            return Err(Error::InvalidArgument(format!(
+                "Too many buttons for the Gray code enumeration: {}",
+                self.buttons.len()
+            )));
+        }
+        let buttons: Vec<LightState> = self
+            .buttons
+            .iter()
+            .map(|button| LightState::from_lights(button))
+            .collect();
+
+        let mut state = LightState::default();
+        let mut subset = 0u64;
+        let mut cost = 0;
+        // The cheapest subset, its cost and how many buttons it presses.
+        let mut best: Option<(u64, u64, u32)> = None;
+        if state == self.lights {
+            best = Some((0, 0, 0));
+        }
+        for index in 1..1u64 << buttons.len() {
+            let button = index.trailing_zeros() as usize;
+            subset ^= 1 << button;
+            state = state ^ buttons[button];
+            if subset & 1 << button != 0 {
+                cost += self.costs[button];
+            } else {
+                cost -= self.costs[button];
+            }
+
+            if state == self.lights {
+                let count = subset.count_ones();
+                let better = best.is_none_or(|(_, best_cost, best_count)| {
+                    (cost, count) < (best_cost, best_count)
+                });
+                if better {
+                    best = Some((subset, cost, count));
+                }
+            }
+        }
+
+        let (subset, cost, count) = best.ok_or(Error::NoSolution)?;
+        return Ok(Solution {
+            presses: (0..buttons.len())
+                .map(|index| subset & 1 << index != 0)
+                .collect(),
+            count: count as usize,
+            cost,
+        });
+    }
+
+    // `machine_no` is only used in z3's errors.
+    #[cfg_attr(not(feature = "z3"), allow(unused_variables))]
+    fn best_joltage(&self, machine_no: usize, solver: Solver) -> Result<Solution2, Error> {
+        match solver {
+            Solver::Auto if self.prefers_dp() => self.best_joltage_dp(),
+            Solver::Auto | Solver::Native => self.best_joltage_native(),
+            Solver::Dp => self.best_joltage_dp(),
+            #[cfg(feature = "z3")]
+            Solver::Z3 { timeout } => match self.best_joltage_z3(machine_no, timeout) {
+                Err(Error::SolverGaveUp {
+                    machine_no,
+                    elapsed,
+                }) => {
+                    eprintln!(
+                        "Machine {}: z3 gave up after {:.2?}, using the native solver",
+                        machine_no, elapsed
+                    );
+                    self.best_joltage_native()
+                }
+                result => result,
+            },
+        }
+    }
+
+    // The DP has at most one state per residual joltage vector and button, which is only
+    // manageable for small joltages.
+    fn prefers_dp(&self) -> bool {
+        let states = self
+            .joltage
+            .iter()
+            .fold(self.buttons.len() as u64, |states, value| {
+                states.saturating_mul(*value as u64 + 1)
+            });
+        return states <= MAX_DP_STATES;
+    }
+
+    // Goes through the buttons one by one and tries every useful number of presses for each:
+    // at most the smallest residual joltage of its lights. The cheapest way to finish is
+    // memoized per button and residual joltages. Simple and exact, but only fast for small
+    // joltages, which makes it a good cross-check for the other solvers.
+    fn best_joltage_dp(&self) -> Result<Solution2, Error> {
+        // After the last button affecting a light, its residual joltage has to be 0.
+        let mut last_buttons = vec![None; self.joltage.len()];
+        for (index, button) in self.buttons.iter().enumerate() {
+            for light in button {
+                last_buttons[*light] = Some(index);
+            }
+        }
+
+        let mut memo = HashMap::new();
+        let cost = self
+            .dp_cost(0, self.joltage.clone(), &last_buttons, &mut memo)
+            .ok_or(Error::NoSolution)?;
+
+        // Follow the memoized choices to get the presses.
+        let mut presses = Vec::with_capacity(self.buttons.len());
+        let mut residual = self.joltage.clone();
+        for (index, button) in self.buttons.iter().enumerate() {
+            let (_, count) = memo[&(index, residual.clone())].ok_or(Error::NoSolution)?;
+            for light in button {
+                residual[*light] -= count as usize;
+            }
+            presses.push(count);
+        }
+        return Ok(Solution2 {
+            total: presses.iter().sum(),
+            presses,
+            cost,
+        });
+    }
+
+    // The cheapest cost of reaching `residual` with the buttons from `index` on. The memo also
+    // remembers how often button `index` is pressed for it.
+    fn dp_cost(
+        &self,
+        index: usize,
+        residual: Vec<usize>,
+        last_buttons: &[Option<usize>],
+        memo: &mut DpMemo,
+    ) -> Option<u64> {
+        let unreachable = residual
+            .iter()
+            .zip(last_buttons)
+            .any(|(value, last)| *value > 0 && last.is_none_or(|last| last < index));
+        if unreachable {
+            return None;
+        }
+        let Some(button) = self.buttons.get(index) else {
+            return Some(0);
+        };
+        let key = (index, residual);
+        if let Some(best) = memo.get(&key) {
+            return best.map(|(cost, _)| cost);
+        }
+
+        let max_useful = button.iter().map(|light| key.1[*light]).min().unwrap_or(0);
+        let mut best: Option<(u64, u64)> = None;
+        for count in 0..=max_useful {
+            let mut next = key.1.clone();
+            for light in button {
+                next[*light] -= count;
+            }
+            if let Some(rest) = self.dp_cost(index + 1, next, last_buttons, memo) {
+                let cost = rest + count as u64 * self.costs[index];
+                if best.is_none_or(|(best_cost, _)| cost < best_cost) {
+                    best = Some((cost, count as u64));
+                }
+            }
+        }
+        memo.insert(key, best);
+        return best.map(|(cost, _)| cost);
+    }
+
+    // Each joltage gives a linear equation: the presses of the buttons affecting it sum up to the
+    // joltage. Gauss-Jordan elimination expresses the presses of some buttons through the
+    // presses of the remaining "free" buttons, of which there are only a few. Then all
+    // combinations of presses of the free buttons are tried. None of them can be pressed more
+    // often than the smallest joltage it affects.
+    fn best_joltage_native(&self) -> Result<Solution2, Error> {
+        let system = self.reduce()?;
+        let mut presses = Vec::with_capacity(system.free.len());
+        let mut best = None;
+        system.search(&mut presses, 0, &mut best);
+        return best.ok_or(Error::NoSolution);
+    }
+
+    fn reduce(&self) -> Result<ReducedSystem, Error> {
+        let columns = self.buttons.len();
+        let mut rows: Vec<Vec<i64>> = self
+            .joltage
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let mut row: Vec<i64> = self
+                    .buttons
+                    .iter()
+                    .map(|button| button.contains(&index) as i64)
+                    .collect();
+                row.push(*value as i64);
+                row
+            })
+            .collect();
+
+        // Stays on integers by scaling the rows instead of dividing, and keeps the numbers small
+        // by dividing each row by the GCD of its entries.
+        let mut pivots = Vec::new();
+        for column in 0..columns {
+            let rank = pivots.len();
+            let Some(found) = (rank..rows.len()).find(|&row| rows[row][column] != 0) else {
+                continue;
+            };
+            rows.swap(rank, found);
+            let pivot_row = rows[rank].clone();
+            for (index, row) in rows.iter_mut().enumerate() {
+                if index == rank || row[column] == 0 {
+                    continue;
+                }
+                let factor = row[column];
+                for (value, pivot_value) in row.iter_mut().zip(&pivot_row) {
+                    *value = *value * pivot_row[column] - pivot_value * factor;
+                }
+                let divisor = row.iter().fold(0, |divisor, value| gcd(divisor, *value));
+                if divisor > 1 {
+                    row.iter_mut().for_each(|value| *value /= divisor);
+                }
+            }
+            pivots.push(column);
+        }
+
+        // The remaining rows have no buttons left, so their values must be 0 as well.
+        if rows[pivots.len()..].iter().any(|row| row[columns] != 0) {
+            return Err(Error::NoSolution);
+        }
+        rows.truncate(pivots.len());
+
+        let free: Vec<usize> = (0..columns).filter(|c| !pivots.contains(c)).collect();
+        let bounds = free
+            .iter()
+            .map(|button| {
+                self.buttons[*button]
+                    .iter()
+                    .filter_map(|index| self.joltage.get(*index))
+                    .min()
+                    .map_or(0, |value| *value as i64)
+            })
+            .collect();
+
+        return Ok(ReducedSystem {
+            rows,
+            pivots,
+            free,
+            bounds,
+            costs: self.costs.iter().map(|cost| *cost as i64).collect(),
+            columns,
+        });
+    }
+
+    #[cfg(feature = "z3")]
+    fn best_joltage_z3(&self, machine_no: usize, timeout: Option<u32>) -> Result<Solution2, Error> {
+        let button_consts: Vec<_> = (0..self.buttons.len())
+            .into_iter()
+            .map(|index| format!("button_{}", index))
+            .map(|name| z3::ast::Int::new_const(name))
+            .collect();
+        let result_const = z3::ast::Int::new_const("result");
+
+        let optimizer = z3::Optimize::new();
+        if let Some(timeout) = timeout {
+            let mut params = z3::Params::new();
+            params.set_u32("timeout", timeout);
+            optimizer.set_params(&params);
+        }
+        // Buttons cannot get pressed a negative number of times.
+        for button in button_consts.iter() {
+            optimizer.assert(&z3::ast::Int::ge(button, z3::ast::Int::from_u64(0)));
+        }
+
+        // For each joltage, find the affected buttons. The sum of the button (presses) must match the joltage.
+        for (index, value) in self.joltage.iter().enumerate() {
+            let mut affected = Vec::new();
+            for (button_index, button) in self.buttons.iter().enumerate() {
+                if button.contains(&index) {
+                    affected.push(&button_consts[button_index]);
+                }
+            }
+            let sum = z3::ast::Int::add(&affected);
+            optimizer.assert(&sum.eq(z3::ast::Int::from_u64(*value as u64)));
+        }
+
+        let weighted: Vec<_> = button_consts
+            .iter()
+            .zip(&self.costs)
+            .map(|(button, cost)| {
+                z3::ast::Int::mul(&[button.clone(), z3::ast::Int::from_u64(*cost)])
+            })
+            .collect();
+        optimizer.assert(&z3::ast::Int::add(&weighted).eq(&result_const));
+        optimizer.minimize(&result_const);
+        let start = Instant::now();
+        match optimizer.check(&[]) {
+            z3::SatResult::Unsat => {
+                return Err(Error::Unsatisfiable { machine_no });
+            }
+            z3::SatResult::Unknown => {
+                return Err(Error::SolverGaveUp {
+                    machine_no,
+                    elapsed: start.elapsed(),
+                });
+            }
+            z3::SatResult::Sat => {}
+        }
+
+        let solution = optimizer.get_model().ok_or(Error::NoSolution)?;
+        let value = solution
+            .get_const_interp(&result_const)
+            .map(|v| v.as_u64())
+            .flatten()
+            .ok_or(Error::NoSolution)?;
+        let presses = button_consts
+            .iter()
+            .map(|button| {
+                solution
+                    .get_const_interp(button)
+                    .and_then(|v| v.as_u64())
+                    .ok_or(Error::NoSolution)
+            })
+            .collect::<Result<Vec<u64>, Error>>()?;
+        return Ok(Solution2 {
+            total: presses.iter().sum(),
+            presses,
+            cost: value,
+        });
+    }
+}
+
+impl ReducedSystem {
+    // Tries all presses of the remaining free buttons, given the presses of the first ones,
+    // which cost `cost` so far.
+    fn search(&self, presses: &mut Vec<i64>, cost: i64, best: &mut Option<Solution2>) {
+        if best.as_ref().is_some_and(|best| cost as u64 >= best.cost) {
+            // The pivot buttons can only add more costs.
+            return;
+        }
+
+        let Some(bound) = self.bounds.get(presses.len()) else {
+            // Only build the solution when it's actually better, most combinations aren't.
+            if let Some(total) = self.total_cost(presses)
+                && best.as_ref().is_none_or(|best| total < best.cost)
+            {
+                *best = self.solution(presses);
+            }
+            return;
+        };
+        let button_cost = self.costs[self.free[presses.len()]];
+        for count in 0..=*bound {
+            presses.push(count);
+            self.search(presses, cost + count * button_cost, best);
+            presses.pop();
+        }
+    }
+
+    // The total cost with the free buttons pressed as given, if the pivot buttons end up with
+    // non-negative integer presses.
+    fn total_cost(&self, free_presses: &[i64]) -> Option<u64> {
+        let mut total: i64 = 0;
+        for (button, count) in self.free.iter().zip(free_presses) {
+            total += self.costs[*button] * count;
+        }
+        for (row, pivot) in self.rows.iter().zip(&self.pivots) {
+            total += self.costs[*pivot] * self.pivot_presses(row, *pivot, free_presses)?;
+        }
+        return Some(total as u64);
+    }
+
+    // The presses of the row's pivot button, if they're a non-negative integer.
+    fn pivot_presses(&self, row: &[i64], pivot: usize, free_presses: &[i64]) -> Option<i64> {
+        let mut value = *row.last()?;
+        for (button, count) in self.free.iter().zip(free_presses) {
+            value -= row[*button] * count;
+        }
+        let coefficient = row[pivot];
+        if value % coefficient != 0 || value / coefficient < 0 {
+            return None;
+        }
+        return Some(value / coefficient);
+    }
+
+    // Like `total_cost`, but with the presses of all buttons.
+    fn solution(&self, free_presses: &[i64]) -> Option<Solution2> {
+        let mut presses = vec![0; self.columns];
+        for (button, count) in self.free.iter().zip(free_presses) {
+            presses[*button] = *count as u64;
+        }
+        for (row, pivot) in self.rows.iter().zip(&self.pivots) {
+            presses[*pivot] = self.pivot_presses(row, *pivot, free_presses)? as u64;
+        }
+        let total = presses.iter().sum();
+        let cost = presses
+            .iter()
+            .zip(&self.costs)
+            .map(|(count, cost)| count * *cost as u64)
+            .sum();
+        return Some(Solution2 {
+            presses,
+            total,
+            cost,
+        });
+    }
+}
+
+// Formats a button like in the input.
+pub fn format_button(button: &Button) -> String {
+    let lights: Vec<String> = button.iter().map(|light| light.to_string()).collect();
+    return format!("({})", lights.join(","));
+}
+
+// Generates a random machine whose answers are known to be feasible: it picks the buttons and how
+// often to press each first, then derives the joltages from the presses and the lights from
+// their parity. Returns the machine as an input line, the number of buttons pressed an odd number
+// of times (an upper bound for part 1) and the total number of presses (one for part 2).
+fn gen_machine(lights: usize, buttons: usize, seed: u64) -> Result<(String, usize, u64), Error> {
+    if lights == 0 || lights > MAX_LIGHTS || buttons == 0 {
+        return Err(Error::InvalidArgument(format!(
+            "cannot generate a machine with {} lights and {} buttons",
+            lights, buttons
+        )));
+    }
+
+    let mut state = seed.max(1);
+    let mut joltage = vec![0; lights];
+    let mut formatted_buttons = Vec::with_capacity(buttons);
+    let mut odd_presses = 0;
+    let mut total = 0;
+    for _ in 0..buttons {
+        let mut button: Button = (0..lights)
+            .filter(|_| next_random(&mut state).is_multiple_of(2))
+            .collect();
+        if button.is_empty() {
+            button.push((next_random(&mut state) % lights as u64) as usize);
+        }
+        let presses = next_random(&mut state) % MAX_GEN_PRESSES;
+        for light in &button {
+            joltage[*light] += presses;
+        }
+        if !presses.is_multiple_of(2) {
+            odd_presses += 1;
+        }
+        total += presses;
+        formatted_buttons.push(format_button(&button));
+    }
+
+    let lights: String = joltage
+        .iter()
+        .map(|value| if value.is_multiple_of(2) { '.' } else { '#' })
+        .collect();
+    let joltage: Vec<String> = joltage.iter().map(|value| value.to_string()).collect();
+    let line = format!(
+        "[{}] {} {{{}}}",
+        lights,
+        formatted_buttons.join(" "),
+        joltage.join(",")
+    );
+    return Ok((line, odd_presses, total));
+}
+
+// Several random machines, one per line, see `gen_machine`.
+pub fn gen_input(
+    machines: usize,
+    lights: usize,
+    buttons: usize,
+    seed: u64,
+) -> Result<String, Error> {
+    let mut state = seed.max(1);
+    let mut input = String::new();
+    for _ in 0..machines {
+        let (line, _, _) = gen_machine(lights, buttons, next_random(&mut state))?;
+        input.push_str(&line);
+        input.push('\n');
+    }
+    return Ok(input);
+}
+
+// Solves each distinct machine only once, duplicates (also with their buttons in a different
+// order) are answered from a cache. `solve` gets the index of the machine and its normalized
+// version. Returns the solutions in the order of `machines` and the number of cache hits.
+fn solve_distinct<S, F>(machines: &[Machine], mut solve: F) -> Result<(Vec<S>, usize), Error>
+where
+    S: Reorder,
+    F: FnMut(usize, &Machine) -> Result<S, Error>,
+{
+    let mut cache: HashMap<Machine, S> = HashMap::new();
+    let mut hits = 0;
+    let mut solutions = Vec::with_capacity(machines.len());
+    for (index, machine) in machines.iter().enumerate() {
+        let (normalized, order) = machine.normalized();
+        let solution = match cache.entry(normalized) {
+            Entry::Occupied(entry) => {
+                hits += 1;
+                entry.into_mut()
+            }
+            Entry::Vacant(entry) => {
+                let solution = solve(index, entry.key())?;
+                entry.insert(solution)
+            }
+        };
+        solutions.push(solution.reordered(&order));
+    }
+    return Ok((solutions, hits));
+}
+
+// The solution for each machine, or `None` if its lights cannot be reached. Unless `lenient`,
+// the first such machine is an error instead. Also returns the number of cache hits.
+pub fn light_up_all(
+    machines: &[Machine],
+    solver: LightSolver,
+    lenient: bool,
+) -> Result<(Vec<Option<Solution>>, usize), Error> {
+    return solve_distinct(machines, |index, machine| match machine.light_up(solver) {
+        Ok(solution) => Ok(Some(solution)),
+        Err(Error::NoSolution) if lenient => Ok(None),
+        Err(Error::NoSolution) => Err(Error::Unsolvable {
+            machine_no: index + 1,
+        }),
+        Err(error) => Err(error),
+    });
+}
+
+// The number of presses for `--explain`, and their cost if it differs.
+pub fn format_presses(machine: &Machine, presses: u64, cost: u64) -> String {
+    if machine.is_weighted() {
+        return format!("{} presses, cost {}", presses, cost);
+    }
+    return format!("{} presses", presses);
+}
+
+// Also returns the number of cache hits.
+pub fn best_joltage_solutions(
+    machines: &[Machine],
+    solver: Solver,
+) -> Result<(Vec<Solution2>, usize), Error> {
+    return solve_distinct(machines, |index, machine| {
+        machine.best_joltage(index + 1, solver)
+    });
+}
+
+// Like part 2, but machines whose joltages cannot be reached are skipped instead of failing the
+// whole run. Other errors, like z3 giving up, still fail it. Returns the total cost of the
+// solvable machines and the indices of the solvable and unsolvable ones.
+pub fn solve_all_lenient(
+    machines: &[Machine],
+    solver: Solver,
+) -> Result<(usize, Vec<usize>, Vec<usize>), Error> {
+    let (solutions, _) = solve_distinct(machines, |index, machine| {
+        match machine.best_joltage(index + 1, solver) {
+            Ok(solution) => Ok(Some(solution)),
+            Err(Error::NoSolution | Error::Unsolvable { .. }) => Ok(None),
+            #[cfg(feature = "z3")]
+            Err(Error::Unsatisfiable { .. }) => Ok(None),
+            Err(error) => Err(error),
+        }
+    })?;
+    let mut total = 0;
+    let mut solvable = Vec::new();
+    let mut unsolvable = Vec::new();
+    for (index, solution) in solutions.iter().enumerate() {
+        match solution {
+            Some(solution) => {
+                total += solution.cost as usize;
+                solvable.push(index);
+            }
+            None => unsolvable.push(index),
+        }
+    }
+    return Ok((total, solvable, unsolvable));
+}
+
+// Aligns the cells below the headers, each column is as wide as its widest cell. The last column
+// is left-aligned since its cells can vary a lot in width, the others are right-aligned.
+fn format_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.len()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let format_row = |cells: Vec<&str>| -> String {
+        let last = cells.len() - 1;
+        let formatted: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(index, (cell, width))| {
+                if index == last {
+                    cell.to_string()
+                } else {
+                    format!("{:>width$}", cell, width = width)
+                }
+            })
+            .collect();
+        formatted.join("  ").trim_end().to_string() + "\n"
+    };
+
+    let mut table = format_row(headers.to_vec());
+    let separator: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+    table += &format_row(separator.iter().map(|cell| cell.as_str()).collect());
+    for row in rows {
+        table += &format_row(row.iter().map(|cell| cell.as_str()).collect());
+    }
+    return table;
+}
+
+// One row per machine with the presses of both parts, and how often part 2 presses each button.
+pub fn solution_table(
+    machines: &[Machine],
+    lights: &[Option<Solution>],
+    joltages: &[Solution2],
+) -> String {
+    let rows: Vec<Vec<String>> = machines
+        .iter()
+        .zip(lights)
+        .zip(joltages)
+        .enumerate()
+        .map(|(index, ((machine, light), joltage))| {
+            let presses: Vec<String> = joltage.presses.iter().map(|c| c.to_string()).collect();
+            vec![
+                (index + 1).to_string(),
+                machine.light_count.to_string(),
+                machine.buttons.len().to_string(),
+                light
+                    .as_ref()
+                    .map_or("-".to_string(), |light| light.count.to_string()),
+                joltage.total.to_string(),
+                presses.join(","),
+            ]
+        })
+        .collect();
+    return format_table(
+        &[
+            "Machine",
+            "Lights",
+            "Buttons",
+            "Part 1",
+            "Part 2",
+            "Presses per button",
+        ],
+        &rows,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_light_up() {
+        let machines = Machine::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        let solutions: Vec<Solution> = machines
+            .iter()
+            .map(|m| m.light_up(LightSolver::Auto).unwrap())
+            .collect();
+        let counts: Vec<usize> = solutions.iter().map(|s| s.count).collect();
+        assert_eq!(counts, vec![2, 3, 2]);
+
+        for (machine, solution) in machines.iter().zip(&solutions) {
+            assert_eq!(machine.apply_presses(&solution.presses), machine.lights);
+            let pressed = solution.presses.iter().filter(|p| **p).count();
+            assert_eq!(pressed, solution.count);
+        }
+    }
+
+    // A random machine from `gen_machine`, parsed. Its lights and joltages can be reached by
+    // construction, all buttons cost 1.
+    fn gen_test_machine(lights: usize, buttons: usize, seed: u64) -> Machine {
+        let (line, _, _) = gen_machine(lights, buttons, seed).unwrap();
+        return Machine::from_input(&line).unwrap().remove(0);
+    }
+
+    #[test]
+    fn test_light_up_bfs() {
+        for seed in 1..50 {
+            let machine = gen_test_machine(6, 8, seed);
+            let solution = machine.light_up_bfs().unwrap();
+            assert_eq!(
+                solution.count,
+                machine.light_up_gray_code().unwrap().count,
+                "seed {}",
+                seed
+            );
+            assert_eq!(machine.apply_presses(&solution.presses), machine.lights);
+        }
+
+        let machines = Machine::from_input("[.#] (0) {1,1}").unwrap();
+        assert!(matches!(machines[0].light_up_bfs(), Err(Error::NoSolution)));
+    }
+
+    #[test]
+    fn test_light_up_gray_code() {
+        for seed in 1..50 {
+            let mut machine = gen_test_machine(6, 12, seed);
+            let solution = machine.light_up_gray_code().unwrap();
+            assert_eq!(solution.count, machine.light_up_bfs().unwrap().count);
+            assert_eq!(machine.apply_presses(&solution.presses), machine.lights);
+
+            // Also with costs, against the Dijkstra the BFS turns into.
+            let mut state = seed;
+            machine.costs = (0..12).map(|_| 1 + next_random(&mut state) % 5).collect();
+            let solution = machine.light_up_gray_code().unwrap();
+            assert_eq!(
+                solution.cost,
+                machine.light_up(LightSolver::Bfs).unwrap().cost,
+                "seed {}",
+                seed
+            );
+            assert_eq!(machine.apply_presses(&solution.presses), machine.lights);
+        }
+
+        let machines = Machine::from_input("[.#] (0) {1,1}\n[..] (0) {0,0}").unwrap();
+        assert!(matches!(
+            machines[0].light_up_gray_code(),
+            Err(Error::NoSolution)
+        ));
+        assert_eq!(machines[1].light_up_gray_code().unwrap().count, 0);
+        assert!(gen_test_machine(8, 33, 1).light_up_gray_code().is_err());
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        let input =
+            "[.##.] (3) (1,3) (2) {3,5,4,7}\n\n[...#.] (0,2,3,4) {7,5,12,7\n[.#] (0) (1) {1,1}\n";
+        match Machine::from_input(input) {
+            Err(Error::Syntax {
+                line_no,
+                column,
+                message,
+            }) => {
+                assert_eq!((line_no, column), (3, 28));
+                assert_eq!(
+                    message,
+                    "expected ',' or '}' in the joltages, found end of line"
+                );
+            }
+            _ => panic!("Corrupted line not reported"),
+        }
+
+        // Lines containing something that looks like a machine aren't good enough, the whole
+        // line needs to be one machine.
+        for garbled in [
+            "[.#] (0) (1) {1,1} [.#] (0) (1) {1,1}",
+            "x[.#] (0) (1) {1,1}",
+            "[.#] (0) foo (1) {1,1}",
+            "[.#] (0) (1) {1,1}}",
+        ] {
+            let input = format!("[.#] (0) (1) {{1,1}}\n{}\n", garbled);
+            assert!(
+                matches!(
+                    Machine::from_input(&input),
+                    Err(Error::Syntax { line_no: 2, .. })
+                ),
+                "Garbled line not reported: {}",
+                garbled
+            );
+        }
+
+        assert_eq!(
+            Machine::from_input("[.#] (0)  (1) {1,1}\n").unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        for (line, expected_column, expected_message) in [
+            (".#] (0) {1,1}", 1, "expected '[', found '.'"),
+            (
+                "[.#. (0) {1,1,1}",
+                5,
+                "expected '.', '#' or ']' in the lights, found ' '",
+            ),
+            (
+                "[.#",
+                4,
+                "expected '.', '#' or ']' in the lights, found end of line",
+            ),
+            ("[.#](0) {1,1}", 5, "expected a space, found '('"),
+            ("[.#] {1,1}", 6, "expected a button, found '{'"),
+            (
+                "[.#] () {1,1}",
+                7,
+                "expected a number in the button, found ')'",
+            ),
+            (
+                "[.#] (0,) {1,1}",
+                9,
+                "expected a number in the button, found ')'",
+            ),
+            (
+                "[.#] (0 1) {1,1}",
+                8,
+                "expected ',' or ')' in the button, found ' '",
+            ),
+            (
+                "[.#] (0) (1",
+                12,
+                "expected ',' or ')' in the button, found end of line",
+            ),
+            (
+                "[.#] (0) 1,1",
+                10,
+                "expected a button or the joltages, found '1'",
+            ),
+            (
+                "[.#] (0) {1,1,}",
+                15,
+                "expected a number in the joltages, found '}'",
+            ),
+            (
+                "[.#] (0) {1,1",
+                14,
+                "expected ',' or '}' in the joltages, found end of line",
+            ),
+            (
+                "[.#] (0) {1,1} x",
+                16,
+                "expected the end of the line after the joltages, found 'x'",
+            ),
+            (
+                "[.#] (99999999999999999999999) {1,1}",
+                7,
+                "number too large: 99999999999999999999999",
+            ),
+        ] {
+            match Machine::from_input(line) {
+                Err(Error::Syntax {
+                    line_no,
+                    column,
+                    message,
+                }) => {
+                    assert_eq!(line_no, 1, "{}", line);
+                    assert_eq!(column, expected_column, "{}", line);
+                    assert_eq!(message, expected_message, "{}", line);
+                }
+                _ => panic!("Not reported: {}", line),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_sample() {
+        let machines = Machine::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        assert_eq!(machines.len(), 3);
+
+        let machine = &machines[1];
+        assert_eq!(machine.light_count, 5);
+        assert_eq!(machine.lights, LightState::from_lights(&[3]));
+        assert_eq!(
+            machine.buttons,
+            vec![
+                vec![0, 2, 3, 4],
+                vec![2, 3],
+                vec![0, 4],
+                vec![0, 1, 2],
+                vec![1, 2, 3, 4]
+            ]
+        );
+        assert_eq!(machine.joltage, vec![7, 5, 12, 7, 2]);
+
+        // Surrounding and repeated spaces are fine.
+        let machines = Machine::from_input("  [.#]  (0)\t(0,1)   {1,2}  \n").unwrap();
+        assert_eq!(machines[0].buttons, vec![vec![0], vec![0, 1]]);
+        assert_eq!(machines[0].joltage, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_validate_indices() {
+        let valid = "[.##.] (3) (1,3) (2) {3,5,4,7}\n";
+        assert!(matches!(
+            Machine::from_input(&format!("{}[.#.] (0) (1,3) {{1,2,3}}", valid)),
+            Err(Error::IndexOutOfRange {
+                machine_no: 2,
+                button_no: 2,
+                index: 3,
+                max: 3
+            })
+        ));
+        assert!(matches!(
+            Machine::from_input(&format!("{}[.#.] (0) (1,2) {{1,1}}", valid)),
+            Err(Error::JoltageCountMismatch {
+                machine_no: 2,
+                lights: 3,
+                joltages: 2
+            })
+        ));
+        assert!(matches!(
+            Machine::from_input("[.#.] (0,2,0) (1,2) {1,1,1}"),
+            Err(Error::DuplicateIndex {
+                machine_no: 1,
+                button_no: 1,
+                index: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn test_light_up_all_off() {
+        let machines = Machine::from_input("[....] (0,1) (2) (1,3) {1,2,3,4}").unwrap();
+        let solution = machines[0].light_up(LightSolver::Auto).unwrap();
+        assert_eq!(solution.count, 0);
+        assert_eq!(solution.presses, vec![false; 3]);
+    }
+
+    // Both answers of the sample, with every solver that is built.
+    #[test]
+    fn test_sample_answers() {
+        let machines = Machine::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        for solver in [
+            LightSolver::Auto,
+            LightSolver::Bfs,
+            LightSolver::MeetInTheMiddle,
+        ] {
+            let (solutions, _) = light_up_all(&machines, solver, false).unwrap();
+            let sum: u64 = solutions.iter().flatten().map(|s| s.cost).sum();
+            assert_eq!(sum, 7, "{:?}", solver);
+        }
+
+        #[allow(unused_mut)]
+        let mut solvers = vec![Solver::Auto, Solver::Native, Solver::Dp];
+        #[cfg(feature = "z3")]
+        solvers.push(Solver::Z3 { timeout: None });
+        for solver in solvers {
+            let (solutions, _) = best_joltage_solutions(&machines, solver).unwrap();
+            let sum: u64 = solutions.iter().map(|s| s.cost).sum();
+            assert_eq!(sum, 33, "{:?}", solver);
+        }
+    }
+
+    #[test]
+    fn test_parse_two_buttons() {
+        let machines = Machine::from_input("[#.#] (0,2) (1) {4,1,4}").unwrap();
+        assert_eq!(machines.len(), 1);
+        let machine = &machines[0];
+        assert_eq!(machine.lights, LightState::from_lights(&[0, 2]));
+        assert_eq!(machine.light_count, 3);
+        assert_eq!(machine.buttons(), &[vec![0, 2], vec![1]]);
+        assert_eq!(machine.costs, vec![1, 1]);
+        assert_eq!(machine.joltage, vec![4, 1, 4]);
+    }
+
+    #[test]
+    fn test_light_up_one_press() {
+        let machine = &Machine::from_input("[##] (0) (0,1) (1) {1,1}").unwrap()[0];
+        for solver in [
+            LightSolver::Auto,
+            LightSolver::Bfs,
+            LightSolver::MeetInTheMiddle,
+        ] {
+            let solution = machine.light_up(solver).unwrap();
+            assert_eq!(solution.count, 1, "{:?}", solver);
+            assert_eq!(solution.presses, vec![false, true, false], "{:?}", solver);
+        }
+    }
+
+    // The minimal number of presses for each machine.
+    fn best_joltages(machines: &[Machine], solver: Solver) -> Result<Vec<u64>, Error> {
+        let (solutions, _) = best_joltage_solutions(machines, solver)?;
+        return Ok(solutions.iter().map(|solution| solution.total).collect());
+    }
+
+    #[test]
+    fn test_best_joltages() {
+        let machines = Machine::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        let presses = best_joltages(&machines, Solver::Native).unwrap();
+        assert_eq!(presses, vec![10, 12, 11]);
+        assert_eq!(presses.iter().sum::<u64>(), 33);
+
+        let solutions = best_joltage_solutions(&machines, Solver::Native).unwrap().0;
+        for (machine, solution) in machines.iter().zip(&solutions) {
+            assert_eq!(
+                machine.apply_joltage_presses(&solution.presses),
+                machine.joltage
+            );
+            assert_eq!(solution.presses.iter().sum::<u64>(), solution.total);
+        }
+    }
+
+    #[test]
+    fn test_best_joltage_native() {
+        // Pressing (0,1) once and (0) twice is cheaper than pressing (0) three times and (1) once.
+        let machines = Machine::from_input("[..] (0) (1) (0,1) (0) {3,1}").unwrap();
+        let solution = machines[0].best_joltage_native().unwrap();
+        assert_eq!(solution.total, 3);
+        assert_eq!(solution.presses, vec![2, 0, 1, 0]);
+
+        let machines = Machine::from_input("[..] (0) {3,1}").unwrap();
+        assert!(matches!(
+            machines[0].best_joltage_native(),
+            Err(Error::NoSolution)
+        ));
+
+        // The only solution presses each button half a time.
+        let machines = Machine::from_input("[...] (0,1) (0,2) (1,2) {1,1,1}").unwrap();
+        assert!(matches!(
+            machines[0].best_joltage_native(),
+            Err(Error::NoSolution)
+        ));
+    }
+
+    #[cfg(feature = "z3")]
+    #[test]
+    fn test_solvers_agree() {
+        for input in [
+            include_str!("../rsc/sample1.txt"),
+            include_str!("../rsc/input.txt"),
+        ] {
+            let machines = Machine::from_input(input).unwrap();
+            assert_eq!(
+                best_joltages(&machines, Solver::Native).unwrap(),
+                best_joltages(&machines, Solver::Z3 { timeout: None }).unwrap()
+            );
+
+            // There might be several optimal solutions, but all need to be correct.
+            let solutions = best_joltage_solutions(&machines, Solver::Z3 { timeout: None })
+                .unwrap()
+                .0;
+            for (machine, solution) in machines.iter().zip(&solutions) {
+                assert_eq!(
+                    machine.apply_joltage_presses(&solution.presses),
+                    machine.joltage
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "z3")]
+    #[test]
+    fn test_z3_presses() {
+        let machines = Machine::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        for machine in &machines {
+            let solution = machine.best_joltage_z3(1, None).unwrap();
+            assert_eq!(solution.presses.len(), machine.buttons.len());
+            assert_eq!(solution.total, solution.presses.iter().sum::<u64>());
+
+            // Each joltage is the sum of the presses of the buttons affecting it.
+            for (light, value) in machine.joltage.iter().enumerate() {
+                let sum: u64 = machine
+                    .buttons
+                    .iter()
+                    .zip(&solution.presses)
+                    .filter(|(button, _)| button.contains(&light))
+                    .map(|(_, presses)| *presses)
+                    .sum();
+                assert_eq!(sum, *value as u64, "light {}", light);
+            }
+        }
+    }
+
+    #[test]
+    fn test_light_up_80_lights() {
+        // Ten blocks of eight lights, one button per block. Blocks 0, 3, 8 and 9 need to be on,
+        // which takes four of those buttons. But there are two more buttons toggling blocks 0 and 3
+        // and blocks 8 and 9, the latter straddling the two words of the state. Each button toggles
+        // at most 16 of the 32 lights, so at least two presses are needed, and those two do it.
+        let block = |block: usize| (block * 8..block * 8 + 8).collect::<Vec<usize>>();
+        let mut buttons: Vec<Button> = (0..10).map(block).collect();
+        buttons.push([block(0), block(3)].concat());
+        buttons.push([block(8), block(9)].concat());
+
+        let lights: String = (0..80)
+            .map(|light| {
+                if [0, 3, 8, 9].contains(&(light / 8)) {
+                    '#'
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        let buttons: Vec<String> = buttons.iter().map(format_button).collect();
+        let input = format!(
+            "[{}] {} {{{}}}",
+            lights,
+            buttons.join(" "),
+            vec!["1"; 80].join(",")
+        );
+
+        let machines = Machine::from_input(&input).unwrap();
+        let machine = &machines[0];
+        assert_eq!(machine.light_count, 80);
+        assert!(machine.lights.is_on(79) && !machine.lights.is_on(63));
+
+        let solution = machine.light_up(LightSolver::Auto).unwrap();
+        assert_eq!(solution.count, 2);
+        assert_eq!(solution.presses[10..], [true, true]);
+        assert_eq!(machine.apply_presses(&solution.presses), machine.lights);
+        assert_eq!(machine.light_up_gray_code().unwrap().count, 2);
+
+        let too_many = format!("[{}] (0) {{{}}}", ".".repeat(129), vec!["1"; 129].join(","));
+        assert!(matches!(
+            Machine::from_input(&too_many),
+            Err(Error::TooManyLights {
+                machine_no: 1,
+                lights: 129,
+                max: 128
+            })
+        ));
+    }
+
+    #[cfg(feature = "z3")]
+    #[test]
+    fn test_z3_timeout() {
+        let machine = gen_test_machine(30, 33, 42);
+        assert!(matches!(
+            machine.best_joltage_z3(7, Some(1)),
+            Err(Error::SolverGaveUp { machine_no: 7, .. })
+        ));
+
+        // Falls back to the native solver.
+        let solution = machine
+            .best_joltage(7, Solver::Z3 { timeout: Some(1) })
+            .unwrap();
+        assert_eq!(
+            solution.total,
+            machine.best_joltage(7, Solver::Native).unwrap().total
+        );
+
+        let machines = Machine::from_input("[.#] (0,1) {1,2}").unwrap();
+        assert!(matches!(
+            machines[0].best_joltage(1, Solver::Z3 { timeout: None }),
+            Err(Error::Unsatisfiable { machine_no: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_light_up_all() {
+        // The second machine has one light on, but its only button toggles two.
+        let input = "[.##.] (3) (1,3) (2) {3,5,4,7}\n[#.] (0,1) {1,1}\n[.#] (0) (1) {1,1}\n";
+        let machines = Machine::from_input(input).unwrap();
+        assert!(matches!(
+            light_up_all(&machines, LightSolver::Auto, false),
+            Err(Error::Unsolvable { machine_no: 2 })
+        ));
+
+        let solutions = light_up_all(&machines, LightSolver::Auto, true).unwrap().0;
+        let counts: Vec<Option<usize>> = solutions
+            .iter()
+            .map(|solution| solution.as_ref().map(|solution| solution.count))
+            .collect();
+        assert_eq!(counts, vec![Some(3), None, Some(1)]);
+    }
+
+    #[test]
+    fn test_solve_all_lenient() {
+        // The inserted second machine would have to press each button half a time.
+        let mut input = include_str!("../rsc/sample1.txt").to_string();
+        input.insert_str(
+            input.find('\n').unwrap() + 1,
+            "[...] (0,1) (0,2) (1,2) {1,1,1}\n",
+        );
+        let machines = Machine::from_input(&input).unwrap();
+        assert!(best_joltage_solutions(&machines, Solver::Auto).is_err());
+        for solver in [Solver::Auto, Solver::Native, Solver::Dp] {
+            assert_eq!(
+                solve_all_lenient(&machines, solver).unwrap(),
+                (33, vec![0, 2, 3], vec![1]),
+                "{:?}",
+                solver
+            );
+        }
+        assert_eq!(
+            solve_all_lenient(&[], Solver::Auto).unwrap(),
+            (0, vec![], vec![])
+        );
+    }
+
+    #[test]
+    fn test_light_up_mitm() {
+        for seed in 1..20 {
+            for (lights, buttons) in [(6, 8), (10, 13), (12, 16), (16, 11)] {
+                let machine = gen_test_machine(lights, buttons, seed);
+                let mitm = machine.light_up_mitm().unwrap();
+                let bfs = machine.light_up_bfs().unwrap();
+                assert_eq!(mitm.count, bfs.count, "seed {}", seed);
+                assert_eq!(
+                    mitm.count,
+                    machine.light_up_gray_code().unwrap().count,
+                    "seed {}",
+                    seed
+                );
+                assert_eq!(machine.apply_presses(&mitm.presses), machine.lights);
+            }
+        }
+
+        let machines = Machine::from_input("[#.] (0,1) {1,1}\n[..] (0) {1,1}").unwrap();
+        assert!(matches!(
+            machines[0].light_up(LightSolver::MeetInTheMiddle),
+            Err(Error::NoSolution)
+        ));
+        assert_eq!(
+            machines[1]
+                .light_up(LightSolver::MeetInTheMiddle)
+                .unwrap()
+                .count,
+            0
+        );
+
+        // Many lights but few buttons is a case for the BFS, many of both isn't.
+        assert!(!gen_test_machine(40, 8, 1).prefers_meet_in_the_middle());
+        assert!(gen_test_machine(40, 30, 1).prefers_meet_in_the_middle());
+    }
+
+    #[test]
+    fn test_solve_distinct() {
+        // Three copies of the same machine, the last with its buttons in a different order.
+        let input = "[.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}\n\
+                     [.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}\n\
+                     [.##.] (0,1) (3,1) (2) (2,3) (0,2) (3) {3,5,4,7}\n";
+        let machines = Machine::from_input(input).unwrap();
+
+        let mut calls = 0;
+        let (solutions, hits) = solve_distinct(&machines, |_, machine| {
+            calls += 1;
+            machine.light_up(LightSolver::Bfs)
+        })
+        .unwrap();
+        assert_eq!(calls, 1);
+        assert_eq!(hits, 2);
+        assert_eq!(solutions.iter().map(|s| s.count).sum::<usize>(), 3 * 2);
+        for (machine, solution) in machines.iter().zip(&solutions) {
+            assert_eq!(machine.apply_presses(&solution.presses), machine.lights);
+        }
+
+        let mut calls = 0;
+        let (solutions, hits) = solve_distinct(&machines, |_, machine| {
+            calls += 1;
+            machine.best_joltage_native()
+        })
+        .unwrap();
+        assert_eq!(calls, 1);
+        assert_eq!(hits, 2);
+        assert_eq!(solutions.iter().map(|s| s.total).sum::<u64>(), 3 * 10);
+        for (machine, solution) in machines.iter().zip(&solutions) {
+            assert_eq!(
+                machine.apply_joltage_presses(&solution.presses),
+                machine.joltage
+            );
+        }
+
+        // Different joltages make a different machine.
+        let input = "[.#] (0) (1) {1,1}\n[.#] (0) (1) {1,2}\n";
+        let machines = Machine::from_input(input).unwrap();
+        let (_, hits) = best_joltage_solutions(&machines, Solver::Native).unwrap();
+        assert_eq!(hits, 0);
+    }
+
+    #[test]
+    fn test_button_costs() {
+        // Pressing the expensive button once is the fewest presses, but pressing the two cheap
+        // ones is cheaper.
+        let machines = Machine::from_input("[##] (0,1)*5 (0) (1) {3,3}").unwrap();
+        let machine = &machines[0];
+        assert_eq!(machine.costs, vec![5, 1, 1]);
+        for solver in [LightSolver::Auto, LightSolver::MeetInTheMiddle] {
+            let solution = machine.light_up(solver).unwrap();
+            assert_eq!(solution.presses, vec![false, true, true]);
+            assert_eq!((solution.count, solution.cost), (2, 2));
+        }
+        let solution = machine.best_joltage_native().unwrap();
+        assert_eq!(solution.presses, vec![0, 3, 3]);
+        assert_eq!((solution.total, solution.cost), (6, 6));
+
+        // Unless the cheap ones get more expensive.
+        let machines = Machine::from_input("[##] (0,1)*5 (0)*3 (1)*3 {3,3}").unwrap();
+        let solution = machines[0].light_up(LightSolver::Auto).unwrap();
+        assert_eq!((solution.count, solution.cost), (1, 5));
+        let solution = machines[0].best_joltage_native().unwrap();
+        assert_eq!((solution.total, solution.cost), (3, 15));
+
+        // Without costs, the cost is the number of presses.
+        let machines = Machine::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        for machine in &machines {
+            let solution = machine.light_up(LightSolver::Auto).unwrap();
+            assert_eq!(solution.cost, solution.count as u64);
+            assert_eq!(machine.light_up_dijkstra().unwrap().cost, solution.cost);
+            let solution = machine.best_joltage_native().unwrap();
+            assert_eq!(solution.cost, solution.total);
+        }
+
+        // Dijkstra and meet in the middle agree on random costs.
+        for seed in 1..30 {
+            let mut machine = gen_test_machine(8, 10, seed);
+            let mut state = seed;
+            machine.costs = (0..10).map(|_| 1 + next_random(&mut state) % 5).collect();
+            let dijkstra = machine.light_up_dijkstra().unwrap();
+            let mitm = machine.light_up_mitm().unwrap();
+            assert_eq!(dijkstra.cost, mitm.cost, "seed {}", seed);
+            assert_eq!(machine.apply_presses(&dijkstra.presses), machine.lights);
+        }
+
+        assert!(matches!(
+            Machine::from_input("[#] (0)*0 {1}"),
+            Err(Error::Syntax { column: 9, .. })
+        ));
+        assert!(matches!(
+            Machine::from_input("[#] (0)* {1}"),
+            Err(Error::Syntax { column: 9, .. })
+        ));
+    }
+
+    #[test]
+    fn test_best_joltage_dp() {
+        let machines = Machine::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        assert_eq!(
+            best_joltages(&machines, Solver::Dp).unwrap(),
+            vec![10, 12, 11]
+        );
+        assert!(machines[0].prefers_dp());
+
+        // Small machines of varying sizes, with unit costs and with random ones.
+        let mut machines = Vec::new();
+        for seed in 1..=100 {
+            let (lights, buttons) = (3 + seed as usize % 4, 3 + seed as usize % 5);
+            let mut weighted = gen_test_machine(lights, buttons, seed);
+            let mut state = seed;
+            weighted.costs = (0..buttons)
+                .map(|_| 1 + next_random(&mut state) % 3)
+                .collect();
+            machines.push(gen_test_machine(lights, buttons, seed));
+            machines.push(weighted);
+        }
+        for (index, machine) in machines.iter().enumerate() {
+            let dp = machine.best_joltage_dp().unwrap();
+            let native = machine.best_joltage_native().unwrap();
+            assert_eq!(dp.cost, native.cost, "machine {}", index);
+            assert_eq!(
+                machine.apply_joltage_presses(&dp.presses),
+                machine.joltage,
+                "machine {}",
+                index
+            );
+            #[cfg(feature = "z3")]
+            assert_eq!(
+                dp.cost,
+                machine.best_joltage_z3(index + 1, None).unwrap().cost,
+                "machine {}",
+                index
+            );
+        }
+
+        let machines = Machine::from_input("[.#] (0,1) {1,2}").unwrap();
+        assert!(matches!(
+            machines[0].best_joltage_dp(),
+            Err(Error::NoSolution)
+        ));
+
+        let machines = Machine::from_input("[...] (0) (1) (2) {500,500,500}").unwrap();
+        assert!(!machines[0].prefers_dp());
+    }
+
+    #[test]
+    fn test_solution_table() {
+        let machines = Machine::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        let (lights, _) = light_up_all(&machines, LightSolver::Bfs, false).unwrap();
+        let (joltages, _) = best_joltage_solutions(&machines, Solver::Native).unwrap();
+        assert_eq!(
+            solution_table(&machines, &lights, &joltages),
+            "Machine  Lights  Buttons  Part 1  Part 2  Presses per button\n\
+             -------  ------  -------  ------  ------  ------------------\n      \
+                   1       4        6       2      10  1,5,0,1,3,0\n      \
+                   2       5        5       3      12  2,5,0,5,0\n      \
+                   3       6        4       2      11  5,0,5,1\n"
+        );
+
+        let rows = vec![
+            vec!["1".to_string(), "".to_string(), "a".to_string()],
+            vec!["100".to_string(), "x".to_string(), "".to_string()],
+        ];
+        assert_eq!(
+            format_table(&["A", "Wide", "Last"], &rows),
+            "  A  Wide  Last\n\
+             ---  ----  ----\n  \
+               1        a\n\
+             100     x\n"
+        );
+    }
+
+    #[test]
+    fn test_gen_machine() {
+        // The native solver tries all presses of the free buttons, so keep the shape close to the
+        // real input: not many more buttons than lights.
+        for seed in 1..=60 {
+            let lights = 1 + (seed % 7) as usize;
+            let buttons = 1 + (seed / 7 % 4) as usize + lights / 2;
+            let (line, odd_presses, total) = gen_machine(lights, buttons, seed).unwrap();
+            assert_eq!(gen_machine(lights, buttons, seed).unwrap().0, line);
+            let machines = Machine::from_input(&line).unwrap();
+            let machine = &machines[0];
+            assert_eq!(machine.light_count, lights);
+            assert_eq!(machine.buttons.len(), buttons);
+
+            // Every solver has to do at least as well as the presses the machine was made from.
+            let mut counts = Vec::new();
+            for solver in [LightSolver::Bfs, LightSolver::MeetInTheMiddle] {
+                let solution = machine.light_up(solver).unwrap();
+                assert!(solution.count <= odd_presses, "{}: {:?}", line, solver);
+                assert_eq!(machine.apply_presses(&solution.presses), machine.lights);
+                counts.push(solution.count);
+            }
+            assert_eq!(counts[0], counts[1], "{}", line);
+
+            let mut solvers = vec![Solver::Native];
+            if machine.prefers_dp() {
+                solvers.push(Solver::Dp);
+            }
+            #[cfg(feature = "z3")]
+            solvers.push(Solver::Z3 { timeout: None });
+            let mut totals = Vec::new();
+            for solver in solvers {
+                let solution = machine.best_joltage(1, solver).unwrap();
+                assert!(solution.total <= total, "{}: {:?}", line, solver);
+                assert_eq!(
+                    machine.apply_joltage_presses(&solution.presses),
+                    machine.joltage
+                );
+                totals.push(solution.total);
+            }
+            assert!(totals.iter().all(|t| *t == totals[0]), "{}", line);
+        }
+
+        let input = gen_input(5, 4, 6, 7).unwrap();
+        assert_eq!(input.lines().count(), 5);
+        assert_eq!(Machine::from_input(&input).unwrap().len(), 5);
+        assert!(gen_machine(0, 3, 1).is_err());
+        assert!(gen_machine(3, 0, 1).is_err());
+        assert!(gen_machine(MAX_LIGHTS + 1, 3, 1).is_err());
+    }
+}
@@ -1,188 +1,190 @@
-use regex::Regex;
+use aoc::{
+    Error, LightSolver, Machine, Solution, Solution2, Solver, best_joltage_solutions,
+    format_button, format_presses, gen_input, light_up_all, solution_table, solve_all_lenient,
+};
 use std::time::Instant;
-use z3;
 
-#[derive(Debug)]
-enum Error {
-    #[allow(dead_code)]
-    InvalidInput(String),
-    NoSolution,
+struct Options {
+    solver: Solver,
+    light_solver: LightSolver,
+    // Print which buttons are pressed for each machine.
+    explain: bool,
+    // Print how many duplicate machines were answered from the cache.
+    verbose: bool,
+    // Skip machines that cannot be solved instead of failing. Part 2 then only prints a summary.
+    lenient: bool,
+    // Print a table of the solutions of both parts at the end.
+    table: bool,
+    // Print random machines with this many machines, lights, buttons and seed instead of solving.
+    generate: Option<(usize, usize, usize, u64)>,
 }
 
-type Button = Vec<usize>;
-
-struct Machine {
-    lights: Vec<bool>,
-    buttons: Vec<Button>,
-    joltage: Vec<usize>,
-}
-
-impl Machine {
-    fn from_input(input: &str) -> Result<Vec<Machine>, Error> {
-        let re = Regex::new(r"\[([.#]*)\]\s+([()0-9, ]+)\s+\{([0-9,]+)}")
-            .map_err(|_| Error::InvalidInput(input.to_string()))?;
-
-        let mut machines = Vec::new();
-        for (line, [raw_lights, raw_buttons, raw_joltages]) in
-            re.captures_iter(input).map(|c| c.extract())
-        {
-            let lights: Vec<bool> = raw_lights.chars().map(|c| c == '#').collect();
-            let joltage: Vec<usize> = raw_joltages
-                .split(',')
-                .map(|s| {
-                    s.parse::<usize>()
-                        .map_err(|_| Error::InvalidInput(line.to_string()))
-                })
-                .collect::<Result<Vec<usize>, Error>>()?;
-
-            let buttons: Vec<Button> = raw_buttons
-                .split(' ')
-                .map(|s| {
-                    if s.len() >= 2 {
-                        s[1..s.len() - 1]
-                            .split(',')
-                            .map(|s| {
-                                s.parse::<usize>()
-                                    .map_err(|_| Error::InvalidInput(line.to_string()))
-                            })
-                            .collect()
-                    } else {
-                        Err(Error::InvalidInput(line.to_string()))
-                    }
-                })
-                .collect::<Result<Vec<Button>, Error>>()?;
-
-            machines.push(Machine {
-                lights,
-                buttons,
-                joltage,
-            });
-        }
-
-        return Ok(machines);
-    }
-
-    fn light_up(&self) -> Result<usize, Error> {
-        // Each button needs to be pressed at most once. So we can simple try all paths with each button pressed,
-        // or not pressed. There aren't that many paths.
-        let lights = vec![false; self.lights.len()];
-        let value = self
-            .recurse_buttons(&lights, 0, &self.buttons)
-            .ok_or(Error::NoSolution)?;
-        return Ok(value);
-    }
-
-    fn recurse_buttons(
-        &self,
-        lights: &Vec<bool>,
-        pressed: usize,
-        remaining: &Vec<Button>,
-    ) -> Option<usize> {
-        let mut remaining = remaining.clone();
-        match remaining.pop() {
-            None => {
-                return None;
-            }
-            Some(button) => {
-                let mut lights_pressed = lights.clone();
-                for light in button {
-                    lights_pressed[light] = !lights_pressed[light];
+impl Options {
+    fn from_args() -> Result<Options, Error> {
+        let mut options = Options {
+            solver: Solver::Auto,
+            light_solver: LightSolver::Auto,
+            explain: false,
+            verbose: false,
+            lenient: false,
+            table: false,
+            generate: None,
+        };
+        #[cfg(feature = "z3")]
+        let mut z3_timeout = None;
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--explain" => options.explain = true,
+                "--verbose" => options.verbose = true,
+                "--lenient" => options.lenient = true,
+                "--table" => options.table = true,
+                #[cfg(feature = "z3")]
+                "--z3-timeout" => {
+                    let value = args.next().ok_or(Error::InvalidArgument(arg))?;
+                    let timeout = value.parse().map_err(|_| Error::InvalidArgument(value))?;
+                    z3_timeout = Some(timeout);
                 }
-                if self.lights == lights_pressed {
-                    return Some(pressed + 1);
+                "--gen" => {
+                    let value = args.next().ok_or(Error::InvalidArgument(arg.clone()))?;
+                    let parts: Vec<&str> = value.split(':').collect();
+                    let [machines, lights, buttons, seed] = parts[..] else {
+                        return Err(Error::InvalidArgument(value));
+                    };
+                    let invalid = |_| Error::InvalidArgument(value.clone());
+                    options.generate = Some((
+                        machines.parse().map_err(invalid)?,
+                        lights.parse().map_err(invalid)?,
+                        buttons.parse().map_err(invalid)?,
+                        seed.parse().map_err(invalid)?,
+                    ));
                 }
-                let non_pressed_path = self.recurse_buttons(lights, pressed, &remaining);
-                let pressed_path = self.recurse_buttons(&lights_pressed, pressed + 1, &remaining);
-                match (non_pressed_path, pressed_path) {
-                    (None, None) => return None,
-                    (None, Some(value)) => return Some(value),
-                    (Some(value), None) => return Some(value),
-                    (Some(value_non_pressed), Some(value_pressed)) => {
-                        return Some(value_non_pressed.min(value_pressed));
-                    }
+                "--light-solver" => {
+                    let name = args.next().ok_or(Error::InvalidArgument(arg))?;
+                    options.light_solver = match name.as_str() {
+                        "auto" => LightSolver::Auto,
+                        "bfs" => LightSolver::Bfs,
+                        "mitm" => LightSolver::MeetInTheMiddle,
+                        _ => return Err(Error::InvalidArgument(name)),
+                    };
                 }
-            }
-        }
-    }
-
-    fn best_joltage_z3(&self) -> Result<usize, Error> {
-        let button_consts: Vec<_> = (0..self.buttons.len())
-            .into_iter()
-            .map(|index| format!("button_{}", index))
-            .map(|name| z3::ast::Int::new_const(name))
-            .collect();
-        let result_const = z3::ast::Int::new_const("result");
-
-        let optimizer = z3::Optimize::new();
-        // Buttons cannot get pressed a negative number of times.
-        for button in button_consts.iter() {
-            optimizer.assert(&z3::ast::Int::ge(button, z3::ast::Int::from_u64(0)));
-        }
-
-        // For each joltage, find the affected buttons. The sum of the button (presses) must match the joltage.
-        for (index, value) in self.joltage.iter().enumerate() {
-            let mut affected = Vec::new();
-            for (button_index, button) in self.buttons.iter().enumerate() {
-                if button.contains(&index) {
-                    affected.push(&button_consts[button_index]);
+                "--solver" => {
+                    let name = args.next().ok_or(Error::InvalidArgument(arg))?;
+                    options.solver = match name.as_str() {
+                        "auto" => Solver::Auto,
+                        "native" => Solver::Native,
+                        "dp" => Solver::Dp,
+                        #[cfg(feature = "z3")]
+                        "z3" => Solver::Z3 { timeout: None },
+                        _ => return Err(Error::InvalidArgument(name)),
+                    };
                 }
+                _ => return Err(Error::InvalidArgument(arg)),
             }
-            let sum = z3::ast::Int::add(&affected);
-            optimizer.assert(&sum.eq(z3::ast::Int::from_u64(*value as u64)));
         }
-
-        optimizer.assert(&z3::ast::Int::add(&button_consts).eq(&result_const));
-        optimizer.minimize(&result_const);
-        match optimizer.check(&[]) {
-            z3::SatResult::Unsat => {
-                return Err(Error::NoSolution);
-            }
-            z3::SatResult::Unknown => {
-                return Err(Error::NoSolution);
-            }
-            z3::SatResult::Sat => {}
+        #[cfg(feature = "z3")]
+        if let Solver::Z3 { timeout } = &mut options.solver {
+            *timeout = z3_timeout;
         }
-
-        let solution = optimizer.get_model().ok_or(Error::NoSolution)?;
-        let value = solution
-            .get_const_interp(&result_const)
-            .map(|v| v.as_u64())
-            .flatten()
-            .ok_or(Error::NoSolution)?;
-        return Ok(value as usize);
+        return Ok(options);
     }
 }
 
-fn part1(input: &str) -> Result<(), Error> {
+fn part1(input: &str, options: &Options) -> Result<Vec<Option<Solution>>, Error> {
     let machines = Machine::from_input(input)?;
+    let (solutions, hits) = light_up_all(&machines, options.light_solver, options.lenient)?;
     let mut sum = 0;
-    for machine in machines {
-        sum += machine.light_up()?;
+    let mut unsolvable = Vec::new();
+    for (index, (machine, solution)) in machines.iter().zip(&solutions).enumerate() {
+        let Some(solution) = solution else {
+            unsolvable.push((index + 1).to_string());
+            continue;
+        };
+        if options.explain {
+            let pressed: Vec<String> = machine
+                .buttons()
+                .iter()
+                .zip(&solution.presses)
+                .filter(|(_, pressed)| **pressed)
+                .map(|(button, _)| format_button(button))
+                .collect();
+            println!(
+                "Machine {}: {}: {}",
+                index + 1,
+                format_presses(machine, solution.count as u64, solution.cost),
+                pressed.join(" ")
+            );
+        }
+        sum += solution.cost;
     }
     println!("Part 1: {}", sum);
-    return Ok(());
+    if !unsolvable.is_empty() {
+        println!("Unsolvable machines: {}", unsolvable.join(", "));
+    }
+    if options.verbose {
+        println!("Cache hits: {}", hits);
+    }
+    return Ok(solutions);
 }
 
-fn part2(input: &str) -> Result<(), Error> {
+// Returns `None` in lenient mode, which only knows the sum.
+fn part2(input: &str, options: &Options) -> Result<Option<Vec<Solution2>>, Error> {
     let machines = Machine::from_input(input)?;
-    let mut sum = 0;
-    for machine in machines {
-        sum += machine.best_joltage_z3()?;
+    if options.lenient {
+        let (sum, _, unsolvable) = solve_all_lenient(&machines, options.solver)?;
+        println!("Part 2: {}", sum);
+        if !unsolvable.is_empty() {
+            println!("Unsolvable machines: {}", unsolvable.len());
+        }
+        return Ok(None);
     }
+    let (solutions, hits) = best_joltage_solutions(&machines, options.solver)?;
+    if options.explain {
+        for (index, (machine, solution)) in machines.iter().zip(&solutions).enumerate() {
+            let pressed: Vec<String> = machine
+                .buttons()
+                .iter()
+                .zip(&solution.presses)
+                .filter(|(_, count)| **count > 0)
+                .map(|(button, count)| format!("{}x{}", count, format_button(button)))
+                .collect();
+            println!(
+                "Machine {}: {}: {}",
+                index + 1,
+                format_presses(machine, solution.total, solution.cost),
+                pressed.join(" ")
+            );
+        }
+    }
+    let sum: u64 = solutions.iter().map(|solution| solution.cost).sum();
     println!("Part 2: {}", sum);
-    return Ok(());
+    if options.verbose {
+        println!("Cache hits: {}", hits);
+    }
+    return Ok(Some(solutions));
 }
 
 fn main() -> Result<(), Error> {
     let input = include_str!("../rsc/input.txt");
+    let options = Options::from_args()?;
+
+    if let Some((machines, lights, buttons, seed)) = options.generate {
+        print!("{}", gen_input(machines, lights, buttons, seed)?);
+        return Ok(());
+    }
 
     let start1 = Instant::now();
-    part1(input)?;
+    let lights = part1(input, &options)?;
     println!("Elapsed: {:.2?}\n", start1.elapsed());
 
     let start2 = Instant::now();
-    part2(input)?;
+    let joltages = part2(input, &options)?;
     println!("Elapsed: {:.2?}", start2.elapsed());
 
+    if let (true, Some(joltages)) = (options.table, joltages) {
+        let machines = Machine::from_input(input)?;
+        print!("\n{}", solution_table(&machines, &lights, &joltages));
+    }
+
     Ok(())
 }
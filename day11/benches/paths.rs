@@ -0,0 +1,55 @@
+use aoc::{Graph, layered_dag, layered_pairs};
+use criterion::{Criterion, criterion_group, criterion_main};
+
+// Run with `cargo bench`. About half a million nodes and a million edges. Interning the names
+// while parsing costs about as much as counting the paths, but it only happens once.
+fn bench_count_all_paths(c: &mut Criterion) {
+    let input = layered_dag(62_500, 8);
+    let graph = Graph::from_input(&input).unwrap();
+    let mut group = c.benchmark_group("count_all_paths");
+    group.sample_size(10);
+    group.bench_function("parse", |b| b.iter(|| Graph::from_input(&input).unwrap()));
+    group.bench_function("count", |b| b.iter(|| graph.count_all_paths().unwrap()));
+    group.finish();
+}
+
+// A thousand queries on 100k nodes. Run with `cargo bench --features parallel` to compare with
+// the parallel version. That both agree is checked by `test_count_paths_pairs`.
+fn bench_count_paths_pairs(c: &mut Criterion) {
+    let (width, layers) = (10_000, 10);
+    let graph = Graph::from_input(&layered_dag(width, layers)).unwrap();
+    let pairs = layered_pairs(1_000, width, layers, 5, 43);
+    let pairs: Vec<(&str, &str)> = pairs
+        .iter()
+        .map(|(from, to)| (from.as_str(), to.as_str()))
+        .collect();
+    let mut group = c.benchmark_group("count_paths_pairs");
+    group.sample_size(10);
+    group.bench_function("serial", |b| {
+        b.iter(|| graph.count_paths_pairs(&pairs).unwrap())
+    });
+    #[cfg(feature = "parallel")]
+    group.bench_function("parallel", |b| {
+        b.iter(|| graph.count_paths_pairs_parallel(&pairs).unwrap())
+    });
+    group.finish();
+}
+
+// Taking the first few of over a million paths only walks down to the first few ends.
+fn bench_paths_iter(c: &mut Criterion) {
+    let graph = Graph::from_input(&layered_dag(1000, 11)).unwrap();
+    let mut group = c.benchmark_group("paths_iter");
+    group.sample_size(10);
+    group.bench_function("first 3", |b| {
+        b.iter(|| graph.paths_iter("you", "out").take(3).count())
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_count_all_paths,
+    bench_count_paths_pairs,
+    bench_paths_iter
+);
+criterion_main!(benches);
@@ -0,0 +1,2328 @@
+#[cfg(feature = "bigint")]
+use num_bigint::BigUint;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use rng::next_random;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    // A line that isn't a node with its targets, or an edge. Lines are counted from 1.
+    #[allow(dead_code)]
+    InvalidInput {
+        line_no: usize,
+        message: String,
+    },
+
+    // A node has more than one line. Lines are counted from 1.
+    #[allow(dead_code)]
+    DuplicateKey {
+        node: String,
+        line_no: usize,
+    },
+
+    #[allow(dead_code)]
+    MissingNode(String),
+
+    // Counting paths only works on acyclic graphs. The nodes along one of the cycles, the last one
+    // leading back to the first.
+    #[allow(dead_code)]
+    CycleDetected {
+        example_cycle: Vec<String>,
+    },
+
+    // `follow_path` recursed deeper than `Graph::max_depth`.
+    #[cfg(test)]
+    DepthExceeded,
+
+    // There are more paths than fit into a `u128`.
+    CountOverflow,
+
+    #[allow(dead_code)]
+    InvalidArgument(String),
+
+    // Counting the simple paths of an undirected graph takes exponential time, so it gives up
+    // after `limit` steps.
+    #[allow(dead_code)]
+    TooManySteps {
+        limit: usize,
+    },
+
+    #[allow(dead_code)]
+    WriteFailed(String),
+
+    // `--strict` found something suspicious about the graph.
+    #[allow(dead_code)]
+    ValidationFailed(Vec<Warning>),
+}
+
+// Something suspicious about the graph, see `Graph::validate`.
+#[derive(Debug, PartialEq)]
+pub enum Warning {
+    // An edge to a node without a line of its own, so it's silently a dead end. Except for "out".
+    DanglingTarget { from: String, to: String },
+    // A node that cannot be reached from "you" or "svr".
+    Unreachable(String),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::DanglingTarget { from, to } => {
+                write!(f, "{} leads to {}, which leads nowhere", from, to)
+            }
+            Warning::Unreachable(node) => write!(f, "{} cannot be reached", node),
+        }
+    }
+}
+
+// The arithmetic needed to count paths, so the counts can be big integers as well. The number of
+// paths grows exponentially with the length of the paths.
+pub trait Count: Clone {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn is_zero(&self) -> bool;
+    fn plus(&self, other: &Self) -> Result<Self, Error>;
+    fn times(&self, other: &Self) -> Result<Self, Error>;
+    fn from_u128(count: u128) -> Self;
+}
+
+// The number of paths between two nodes. If there's a cycle on the way, the paths can go around
+// it any number of times.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathCount<C: Count = u128> {
+    Finite(C),
+    Infinite,
+}
+
+impl<C: Count + fmt::Display> fmt::Display for PathCount<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathCount::Finite(count) => write!(f, "{}", count),
+            PathCount::Infinite => write!(f, "infinite"),
+        }
+    }
+}
+
+impl<C: Count> PathCount<C> {
+    // The paths along consecutive segments: the product of the paths of each segment. Infinite if
+    // any of them is, unless another has no paths at all. Stops at the first segment without
+    // paths, so the later ones aren't even counted.
+    fn product(
+        segments: impl IntoIterator<Item = Result<PathCount<C>, Error>>,
+    ) -> Result<PathCount<C>, Error> {
+        let mut count = C::one();
+        let mut infinite = false;
+        for segment in segments {
+            match segment? {
+                PathCount::Finite(paths) if paths.is_zero() => {
+                    return Ok(PathCount::Finite(paths));
+                }
+                PathCount::Finite(paths) if !infinite => count = count.times(&paths)?,
+                PathCount::Finite(_) => {}
+                PathCount::Infinite => infinite = true,
+            }
+        }
+        if infinite {
+            return Ok(PathCount::Infinite);
+        }
+        return Ok(PathCount::Finite(count));
+    }
+
+    // The paths of two sets of paths that have none in common.
+    fn plus(&self, other: &PathCount<C>) -> Result<PathCount<C>, Error> {
+        match (self, other) {
+            (PathCount::Finite(a), PathCount::Finite(b)) => {
+                return Ok(PathCount::Finite(a.plus(b)?));
+            }
+            _ => return Ok(PathCount::Infinite),
+        }
+    }
+}
+
+// The graph with each strongly connected component contracted into a single node. The paths
+// between different components never go in circles, so they can be counted like on any other
+// acyclic graph.
+struct Condensation {
+    // The component of each node of the graph. The components are numbered such that the edges
+    // between them always lead to lower numbers, so counting down is a topological order.
+    component: Vec<u32>,
+    // The components each component leads to, once for each edge between their nodes.
+    connections: Vec<Vec<u32>>,
+    // Whether paths can go around in circles inside the component: it has more than one node, or
+    // a node leading to itself.
+    cyclic: Vec<bool>,
+}
+
+impl Count for u128 {
+    fn zero() -> u128 {
+        return 0;
+    }
+
+    fn one() -> u128 {
+        return 1;
+    }
+
+    fn is_zero(&self) -> bool {
+        return *self == 0;
+    }
+
+    fn plus(&self, other: &u128) -> Result<u128, Error> {
+        return self.checked_add(*other).ok_or(Error::CountOverflow);
+    }
+
+    fn times(&self, other: &u128) -> Result<u128, Error> {
+        return self.checked_mul(*other).ok_or(Error::CountOverflow);
+    }
+
+    fn from_u128(count: u128) -> u128 {
+        return count;
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl Count for BigUint {
+    fn zero() -> BigUint {
+        return BigUint::ZERO;
+    }
+
+    fn one() -> BigUint {
+        return BigUint::from(1u32);
+    }
+
+    fn is_zero(&self) -> bool {
+        return *self == BigUint::ZERO;
+    }
+
+    fn plus(&self, other: &BigUint) -> Result<BigUint, Error> {
+        return Ok(self + other);
+    }
+
+    fn times(&self, other: &BigUint) -> Result<BigUint, Error> {
+        return Ok(self * other);
+    }
+
+    fn from_u128(count: u128) -> BigUint {
+        return BigUint::from(count);
+    }
+}
+
+// Whether the depth-first search in `find_cycle` is still searching below a node or is done with
+// it.
+#[derive(Clone, Copy)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+// Part 2 asks for the paths passing through both of these nodes, they're named in the puzzle.
+pub const PART2_WAYPOINTS: [&str; 2] = ["dac", "fft"];
+
+// How deep `follow_path` may recurse before giving up, instead of overflowing the stack.
+#[cfg(test)]
+const DEFAULT_MAX_DEPTH: usize = 10_000;
+
+// How many edges `count_simple_paths_between` may follow before giving up. The number of simple
+// paths can grow with the factorial of the number of nodes, so limiting the nodes doesn't bound
+// the work: a complete graph of 20 nodes already has more than 10^17 of them.
+const MAX_SIMPLE_PATH_STEPS: usize = 10_000_000;
+
+pub struct Graph {
+    // The name of each node. Nodes are referred to by their index in here, the names are only
+    // needed when talking to the outside.
+    names: Vec<String>,
+    indices: HashMap<String, u32>,
+    // The nodes each node leads to, by index.
+    connections: Vec<Vec<u32>>,
+    // Whether each node has a line of its own in the input.
+    declared: Vec<bool>,
+    // Every edge leads both ways. Then the paths can only be counted as simple paths, the
+    // counting on the condensation and the memoization in `follow_path` don't work.
+    undirected: bool,
+    #[cfg(test)]
+    max_depth: usize,
+}
+
+// A node name as a quoted DOT identifier. Quotes and backslashes in the name are escaped.
+fn dot_id(name: &str) -> String {
+    return format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""));
+}
+
+impl Graph {
+    fn empty(undirected: bool) -> Graph {
+        return Graph {
+            names: Vec::new(),
+            indices: HashMap::new(),
+            connections: Vec::new(),
+            declared: Vec::new(),
+            undirected,
+            #[cfg(test)]
+            max_depth: DEFAULT_MAX_DEPTH,
+        };
+    }
+
+    // Undirected if `undirected` is set or if any line looks like an undirected edge, otherwise
+    // like `from_input_with_cycles`.
+    pub fn parse(input: &str, undirected: bool) -> Result<Graph, Error> {
+        if undirected || input.contains(" - ") {
+            return Graph::from_undirected_input(input);
+        }
+        return Graph::from_input_with_cycles(input);
+    }
+
+    // The lines of the input that aren't blank, with their line numbers counted from 1.
+    fn input_lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
+        return input
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+    }
+
+    // Each line is an edge like "a - b", leading both ways.
+    fn from_undirected_input(input: &str) -> Result<Graph, Error> {
+        let mut graph = Graph::empty(true);
+        for (line_no, line) in Graph::input_lines(input) {
+            let invalid = |message: &str| Error::InvalidInput {
+                line_no,
+                message: message.to_string(),
+            };
+            let (a, b) = line.split_once(" - ").ok_or(invalid("missing \" - \""))?;
+            let (a, b) = (a.trim(), b.trim());
+            if a.is_empty() || b.is_empty() {
+                return Err(invalid("missing node"));
+            }
+            let a = graph.intern(a);
+            let b = graph.intern(b);
+            graph.connections[a as usize].push(b);
+            if a != b {
+                graph.connections[b as usize].push(a);
+            }
+            graph.declared[a as usize] = true;
+            graph.declared[b as usize] = true;
+        }
+        return Ok(graph);
+    }
+
+    pub fn from_input(input: &str) -> Result<Graph, Error> {
+        let graph = Graph::from_input_with_cycles(input)?;
+
+        // The memoization in `follow_path` assumes that there are no cycles, with one the counts
+        // would silently be wrong.
+        if let Some(example_cycle) = graph.find_cycle() {
+            return Err(Error::CycleDetected { example_cycle });
+        }
+        return Ok(graph);
+    }
+
+    // Like `from_input`, but also accepts cycles. The path counting works on the condensation, so
+    // it can deal with them, as well as `count_simple_paths` and `paths_iter`.
+    // The targets are separated by whitespace, commas or both.
+    pub fn from_input_with_cycles(input: &str) -> Result<Graph, Error> {
+        let mut graph = Graph::empty(false);
+        for (line_no, line) in Graph::input_lines(input) {
+            let invalid = |message: &str| Error::InvalidInput {
+                line_no,
+                message: message.to_string(),
+            };
+            let (node, raw_targets) = line.split_once(':').ok_or(invalid("missing ':'"))?;
+            let node = node.trim();
+            if node.is_empty() {
+                return Err(invalid("missing node"));
+            }
+            let raw_targets: Vec<&str> = raw_targets
+                .split(|c| c == ',' || char::is_whitespace(c))
+                .filter(|target| !target.is_empty())
+                .collect();
+            if raw_targets.is_empty() {
+                return Err(invalid("missing targets"));
+            }
+
+            let node = graph.intern(node);
+            if graph.declared[node as usize] {
+                return Err(Error::DuplicateKey {
+                    node: graph.name(node).to_string(),
+                    line_no,
+                });
+            }
+            graph.declared[node as usize] = true;
+            let targets: Vec<u32> = raw_targets
+                .into_iter()
+                .map(|target| graph.intern(target))
+                .collect();
+
+            graph.connections[node as usize] = targets;
+        }
+        return Ok(graph);
+    }
+
+    // The index of the node with the given name, adding the node if it's new.
+    fn intern(&mut self, name: &str) -> u32 {
+        if let Some(node) = self.indices.get(name) {
+            return *node;
+        }
+        let node = self.names.len() as u32;
+        self.names.push(name.to_string());
+        self.indices.insert(name.to_string(), node);
+        self.connections.push(Vec::new());
+        self.declared.push(false);
+        return node;
+    }
+
+    fn index(&self, name: &str) -> Option<u32> {
+        return self.indices.get(name).copied();
+    }
+
+    // Like `index`, but unknown names are an error.
+    pub fn node(&self, name: &str) -> Result<u32, Error> {
+        return self.index(name).ok_or(Error::MissingNode(name.to_string()));
+    }
+
+    fn name(&self, node: u32) -> &str {
+        return &self.names[node as usize];
+    }
+
+    fn connections(&self, node: u32) -> &[u32] {
+        return &self.connections[node as usize];
+    }
+
+    // Things that are most likely mistakes in the input, but don't keep the paths from being
+    // counted: edges to nodes that are never declared and nodes that neither "you" nor "svr" lead
+    // to. Without either of them, nothing is reported as unreachable.
+    pub fn validate(&self) -> Vec<Warning> {
+        let mut warnings: Vec<Warning> = Vec::new();
+
+        for (node, connections) in self.connections.iter().enumerate() {
+            for connection in connections {
+                let to = self.name(*connection);
+                if !self.declared[*connection as usize] && to != "out" {
+                    warnings.push(Warning::DanglingTarget {
+                        from: self.name(node as u32).to_string(),
+                        to: to.to_string(),
+                    });
+                }
+            }
+        }
+
+        let starts: Vec<u32> = ["you", "svr"]
+            .iter()
+            .filter_map(|name| self.index(name))
+            .collect();
+        if starts.is_empty() {
+            return warnings;
+        }
+        let mut reached = vec![false; self.names.len()];
+        for start in &starts {
+            reached[*start as usize] = true;
+        }
+        let mut queue = VecDeque::from(starts);
+        while let Some(node) = queue.pop_front() {
+            for connection in self.connections(node) {
+                if !reached[*connection as usize] {
+                    reached[*connection as usize] = true;
+                    queue.push_back(*connection);
+                }
+            }
+        }
+        for (node, reached) in reached.iter().enumerate() {
+            if !reached {
+                warnings.push(Warning::Unreachable(self.name(node as u32).to_string()));
+            }
+        }
+        return warnings;
+    }
+
+    // A depth-first search without recursion. The stack holds the current path, so reaching a
+    // node that is still in progress closes a cycle, which is the part of the stack from that
+    // node on. The nodes are tried as starting points sorted by name so the example is always
+    // the same.
+    fn find_cycle(&self) -> Option<Vec<String>> {
+        let mut starts: Vec<u32> = (0..self.names.len() as u32).collect();
+        starts.sort_by_key(|node| self.name(*node));
+
+        let mut visits: Vec<Option<Visit>> = vec![None; self.names.len()];
+        for start in starts {
+            if visits[start as usize].is_some() {
+                continue;
+            }
+            visits[start as usize] = Some(Visit::InProgress);
+            // Each entry is a node and the index of its next connection to visit.
+            let mut stack: Vec<(u32, usize)> = vec![(start, 0)];
+            while let Some((node, next)) = stack.pop() {
+                let Some(connection) = self.connections(node).get(next) else {
+                    visits[node as usize] = Some(Visit::Done);
+                    continue;
+                };
+                stack.push((node, next + 1));
+                match visits[*connection as usize] {
+                    Some(Visit::InProgress) => {
+                        let position = stack.iter().position(|(n, _)| n == connection)?;
+                        return Some(
+                            stack[position..]
+                                .iter()
+                                .map(|(n, _)| self.name(*n).to_string())
+                                .collect(),
+                        );
+                    }
+                    Some(Visit::Done) => {}
+                    None => {
+                        visits[*connection as usize] = Some(Visit::InProgress);
+                        stack.push((*connection, 0));
+                    }
+                }
+            }
+        }
+        return None;
+    }
+
+    pub fn count_all_paths(&self) -> Result<PathCount, Error> {
+        return self.count_paths("you", "out");
+    }
+
+    // The original recursive counting, kept to cross-check `count_all_paths` on small graphs.
+    #[cfg(test)]
+    fn count_all_paths_recursive(&self) -> Result<u128, Error> {
+        // Nodes that cannot reach "out" don't contribute any paths, so get rid of them first.
+        let pruned = self.pruned("out");
+        let mut cache = HashMap::new();
+        return pruned.follow_path_between("you", "out", &mut cache);
+    }
+
+    // `count_all_paths_recursive` without pruning the dead ends first.
+    #[cfg(test)]
+    fn count_all_paths_unpruned(&self) -> Result<u128, Error> {
+        let mut cache = HashMap::new();
+        return self.follow_path_between("you", "out", &mut cache);
+    }
+
+    // All nodes from which `target` can be reached, not including `target` itself (unless it's on
+    // a cycle). Indexed by node.
+    fn ancestors(&self, target: u32) -> Vec<bool> {
+        let mut reverse: Vec<Vec<u32>> = vec![Vec::new(); self.names.len()];
+        for (node, connections) in self.connections.iter().enumerate() {
+            for connection in connections {
+                reverse[*connection as usize].push(node as u32);
+            }
+        }
+
+        let mut ancestors = vec![false; self.names.len()];
+        let mut queue = VecDeque::from([target]);
+        while let Some(node) = queue.pop_front() {
+            for predecessor in &reverse[node as usize] {
+                if !ancestors[*predecessor as usize] {
+                    ancestors[*predecessor as usize] = true;
+                    queue.push_back(*predecessor);
+                }
+            }
+        }
+        return ancestors;
+    }
+
+    // All nodes that can be reached from `source`, not including `source` itself (unless it's on a
+    // cycle). Indexed by node, like `ancestors`.
+    fn descendants(&self, source: u32) -> Vec<bool> {
+        let mut descendants = vec![false; self.names.len()];
+        let mut queue = VecDeque::from([source]);
+        while let Some(node) = queue.pop_front() {
+            for connection in self.connections(node) {
+                if !descendants[*connection as usize] {
+                    descendants[*connection as usize] = true;
+                    queue.push_back(*connection);
+                }
+            }
+        }
+        return descendants;
+    }
+
+    // The nodes on at least one path from `start` to `end`, including both ends: those that can
+    // be reached from `start` and can reach `end`. Empty if there's no path at all.
+    pub fn nodes_on_any_path(&self, start: &str, end: &str) -> HashSet<String> {
+        let (Some(start), Some(end)) = (self.index(start), self.index(end)) else {
+            return HashSet::new();
+        };
+        return (0..self.names.len() as u32)
+            .zip(self.on_any_path(start, end))
+            .filter(|(_, on_path)| *on_path)
+            .map(|(node, _)| self.name(node).to_string())
+            .collect();
+    }
+
+    // `nodes_on_any_path` by index, indexed by node.
+    fn on_any_path(&self, start: u32, end: u32) -> Vec<bool> {
+        let descendants = self.descendants(start);
+        let ancestors = self.ancestors(end);
+        let reached = |node: u32| node == start || descendants[node as usize];
+        let reaches = |node: u32| node == end || ancestors[node as usize];
+        if !reached(end) {
+            return vec![false; self.names.len()];
+        }
+        return (0..self.names.len() as u32)
+            .map(|node| reached(node) && reaches(node))
+            .collect();
+    }
+
+    // The nodes on any path from `from` to `to` through all `waypoints`, indexed by node. Like in
+    // `count_paths_via`, the waypoints are visited in the order of their components. Waypoints
+    // sharing a component can be visited in any order, but then their paths cover the same nodes
+    // anyway.
+    pub fn on_any_path_via(&self, from: u32, to: u32, waypoints: &[u32]) -> Vec<bool> {
+        let condensation = self.condensation();
+        let mut stops = waypoints.to_vec();
+        stops.sort_by_key(|node| std::cmp::Reverse(condensation.component[*node as usize]));
+
+        let mut on_path = vec![false; self.names.len()];
+        let starts = [from].into_iter().chain(stops.clone());
+        for (previous, next) in starts.zip(stops.into_iter().chain([to])) {
+            let segment = self.on_any_path(previous, next);
+            if !segment[next as usize] {
+                // One of the segments has no path, so there's no path at all.
+                return vec![false; self.names.len()];
+            }
+            for (node, on_segment) in segment.into_iter().enumerate() {
+                on_path[node] |= on_segment;
+            }
+        }
+        return on_path;
+    }
+
+    // A copy of the graph in which only the nodes that can reach `target` have connections. The
+    // other nodes are still there, so the indices don't change.
+    #[cfg(test)]
+    fn pruned(&self, target: &str) -> Graph {
+        let target = self.index(target);
+        let ancestors = match target {
+            Some(target) => self.ancestors(target),
+            None => vec![false; self.names.len()],
+        };
+        let keep = |node: u32| Some(node) == target || ancestors[node as usize];
+
+        let connections = self
+            .connections
+            .iter()
+            .enumerate()
+            .map(|(node, targets)| {
+                if !keep(node as u32) {
+                    return Vec::new();
+                }
+                return targets
+                    .iter()
+                    .copied()
+                    .filter(|connection| keep(*connection))
+                    .collect();
+            })
+            .collect();
+        return Graph {
+            names: self.names.clone(),
+            indices: self.indices.clone(),
+            connections,
+            declared: self.declared.clone(),
+            undirected: self.undirected,
+            max_depth: self.max_depth,
+        };
+    }
+
+    // The number of paths from `from` to `to`, without recursion, on the condensation of the
+    // graph, see `Condensation::count_paths`. Unknown nodes have no paths.
+    pub fn count_paths(&self, from: &str, to: &str) -> Result<PathCount, Error> {
+        return self.count_paths_as(from, to);
+    }
+
+    // `count_paths` with any kind of count.
+    pub fn count_paths_as<C: Count>(&self, from: &str, to: &str) -> Result<PathCount<C>, Error> {
+        let (Some(from), Some(to)) = (self.index(from), self.index(to)) else {
+            return Ok(PathCount::Finite(C::zero()));
+        };
+        if self.undirected {
+            let count = self.count_simple_paths_between(from, to, &[])?;
+            return Ok(PathCount::Finite(C::from_u128(count)));
+        }
+        return self.condensation().count_paths(from, to);
+    }
+
+    // Tarjan's algorithm, without recursion so long chains don't overflow the stack. A component
+    // is complete once the depth-first search is done with its first node, and by then all
+    // components reachable from it are complete. So they get lower numbers.
+    fn condensation(&self) -> Condensation {
+        const UNVISITED: u32 = u32::MAX;
+        let mut order = vec![UNVISITED; self.names.len()];
+        let mut low = vec![0; self.names.len()];
+        let mut on_stack = vec![false; self.names.len()];
+        let mut stack: Vec<u32> = Vec::new();
+        let mut component = vec![0; self.names.len()];
+        let mut components = 0;
+        let mut next_order = 0;
+
+        for root in 0..self.names.len() as u32 {
+            if order[root as usize] != UNVISITED {
+                continue;
+            }
+            // Each entry is a node and the index of its next connection to visit.
+            let mut calls: Vec<(u32, usize)> = vec![(root, 0)];
+            order[root as usize] = next_order;
+            low[root as usize] = next_order;
+            next_order += 1;
+            stack.push(root);
+            on_stack[root as usize] = true;
+
+            while let Some((node, next)) = calls.pop() {
+                if let Some(connection) = self.connections(node).get(next) {
+                    calls.push((node, next + 1));
+                    let index = *connection as usize;
+                    if order[index] == UNVISITED {
+                        order[index] = next_order;
+                        low[index] = next_order;
+                        next_order += 1;
+                        stack.push(*connection);
+                        on_stack[index] = true;
+                        calls.push((*connection, 0));
+                    } else if on_stack[index] {
+                        low[node as usize] = low[node as usize].min(order[index]);
+                    }
+                    continue;
+                }
+
+                if let Some((parent, _)) = calls.last() {
+                    low[*parent as usize] = low[*parent as usize].min(low[node as usize]);
+                }
+                if low[node as usize] == order[node as usize] {
+                    while let Some(member) = stack.pop() {
+                        on_stack[member as usize] = false;
+                        component[member as usize] = components;
+                        if member == node {
+                            break;
+                        }
+                    }
+                    components += 1;
+                }
+            }
+        }
+
+        let mut connections = vec![Vec::new(); components as usize];
+        let mut cyclic = vec![false; components as usize];
+        for (node, targets) in self.connections.iter().enumerate() {
+            let from = component[node];
+            for target in targets {
+                let to = component[*target as usize];
+                if from == to {
+                    cyclic[from as usize] = true;
+                } else {
+                    connections[from as usize].push(to);
+                }
+            }
+        }
+        return Condensation {
+            component,
+            connections,
+            cyclic,
+        };
+    }
+
+    // For each node on any path from `from` to `to`, how many of those paths pass through it:
+    // the paths leading to it times the paths leading from it to `to`. Two sweeps over the
+    // components of the condensation instead of a `count_paths_via` per node, limited to the
+    // nodes on any path so the rest of the graph can't overflow the counts. Infinite for the
+    // nodes of a cyclic component and those before and after it. Includes `from` and `to`
+    // themselves, which all paths pass through. Undirected graphs count simple paths instead, like
+    // `count_paths`.
+    pub fn path_through_counts(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Result<HashMap<String, PathCount>, Error> {
+        let (Some(from), Some(to)) = (self.index(from), self.index(to)) else {
+            return Ok(HashMap::new());
+        };
+        if self.undirected {
+            return self.simple_path_through_counts(from, to);
+        }
+        let on_path = self.on_any_path(from, to);
+        if !on_path[to as usize] {
+            return Ok(HashMap::new());
+        }
+
+        // Counting down from `first` to `last` is a topological order of the components.
+        let condensation = self.condensation();
+        let first = condensation.component[from as usize] as usize;
+        let last = condensation.component[to as usize] as usize;
+        let mut relevant = vec![false; first + 1];
+        for node in (0..self.names.len()).filter(|node| on_path[*node]) {
+            relevant[condensation.component[node] as usize] = true;
+        }
+        let next_components = |component: usize| {
+            return condensation.connections[component]
+                .iter()
+                .map(|next| *next as usize)
+                .filter(|next| relevant[*next]);
+        };
+
+        let mut from_start = vec![PathCount::Finite(0); first + 1];
+        from_start[first] = PathCount::Finite(1);
+        for component in (last..=first)
+            .rev()
+            .filter(|component| relevant[*component])
+        {
+            if condensation.cyclic[component] {
+                from_start[component] = PathCount::Infinite;
+            }
+            for next in next_components(component) {
+                from_start[next] = from_start[next].plus(&from_start[component])?;
+            }
+        }
+
+        let mut to_end = vec![PathCount::Finite(0); first + 1];
+        to_end[last] = PathCount::Finite(1);
+        for component in (last..=first).filter(|component| relevant[*component]) {
+            for next in next_components(component) {
+                to_end[component] = to_end[component].plus(&to_end[next])?;
+            }
+            if condensation.cyclic[component] {
+                to_end[component] = PathCount::Infinite;
+            }
+        }
+
+        let mut counts = HashMap::new();
+        for node in (0..self.names.len()).filter(|node| on_path[*node]) {
+            let component = condensation.component[node] as usize;
+            let through = PathCount::product([
+                Ok(from_start[component].clone()),
+                Ok(to_end[component].clone()),
+            ])?;
+            counts.insert(self.name(node as u32).to_string(), through);
+        }
+        return Ok(counts);
+    }
+
+    // `path_through_counts` for undirected graphs: every simple path adds one to each of its
+    // nodes.
+    fn simple_path_through_counts(
+        &self,
+        from: u32,
+        to: u32,
+    ) -> Result<HashMap<String, PathCount>, Error> {
+        let mut through = vec![0u128; self.names.len()];
+        self.visit_simple_paths(from, to, &[], |path| {
+            for node in path.iter().map(|(node, _)| *node).chain([to]) {
+                through[node as usize] = through[node as usize].plus(&1)?;
+            }
+            return Ok(());
+        })?;
+        return Ok((0..self.names.len() as u32)
+            .filter(|node| through[*node as usize] > 0)
+            .map(|node| {
+                let count = PathCount::Finite(through[node as usize]);
+                return (self.name(node).to_string(), count);
+            })
+            .collect());
+    }
+
+    // For each node that can be reached from `start`, how many paths lead there. Assumes that
+    // there are no cycles, edges back onto the current path are ignored. The depth-first search
+    // finishes each node after all nodes reachable from it, so in reverse, each node comes after
+    // all its predecessors and the counts can be pushed forward in a single pass.
+    pub fn path_counts_from(&self, start: &str) -> Result<HashMap<String, u128>, Error> {
+        let Some(start_node) = self.index(start) else {
+            return Ok(HashMap::from([(start.to_string(), 1)]));
+        };
+
+        // Without recursion, so long chains don't overflow the stack. Each entry is a node and
+        // the index of its next connection to visit.
+        let mut finished: Vec<u32> = Vec::new();
+        let mut seen = vec![false; self.names.len()];
+        seen[start_node as usize] = true;
+        let mut stack: Vec<(u32, usize)> = vec![(start_node, 0)];
+        while let Some((node, next)) = stack.pop() {
+            match self.connections(node).get(next) {
+                Some(connection) => {
+                    stack.push((node, next + 1));
+                    if !seen[*connection as usize] {
+                        seen[*connection as usize] = true;
+                        stack.push((*connection, 0));
+                    }
+                }
+                None => finished.push(node),
+            }
+        }
+
+        let mut counts: Vec<u128> = vec![0; self.names.len()];
+        counts[start_node as usize] = 1;
+        for node in finished.iter().rev() {
+            let count = counts[*node as usize];
+            for connection in self.connections(*node) {
+                counts[*connection as usize] = counts[*connection as usize].plus(&count)?;
+            }
+        }
+        return Ok(finished
+            .iter()
+            .map(|node| (self.name(*node).to_string(), counts[*node as usize]))
+            .collect());
+    }
+
+    // The graph in Graphviz' DOT format, laid out from left to right, with the `highlight` nodes
+    // filled. Nodes and edges are sorted by name so the output only changes with the graph. An
+    // undirected graph has each edge only once.
+    pub fn to_dot(&self, highlight: &[&str]) -> String {
+        let mut nodes: Vec<u32> = (0..self.names.len() as u32).collect();
+        nodes.sort_by_key(|node| self.name(*node));
+        let (kind, arrow) = if self.undirected {
+            ("graph", "--")
+        } else {
+            ("digraph", "->")
+        };
+
+        let mut dot = format!("{} {{\n    rankdir=LR;\n", kind);
+        for node in &nodes {
+            let name = self.name(*node);
+            if highlight.contains(&name) {
+                dot += &format!("    {} [style=filled, fillcolor=gold];\n", dot_id(name));
+            } else {
+                dot += &format!("    {};\n", dot_id(name));
+            }
+        }
+        for node in &nodes {
+            let name = self.name(*node);
+            let mut targets: Vec<&str> = self
+                .connections(*node)
+                .iter()
+                .map(|connection| self.name(*connection))
+                .filter(|target| !self.undirected || name <= *target)
+                .collect();
+            targets.sort();
+            for target in targets {
+                dot += &format!("    {} {} {};\n", dot_id(name), arrow, dot_id(target));
+            }
+        }
+        dot += "}\n";
+        return dot;
+    }
+
+    // The paths of part 2, as the two roads of `count_svr_paths_recursive`: through "dac" first
+    // or through "fft" first. Their six segments are independent of each other, so they're
+    // counted with `count_paths_pairs`, in parallel with the `parallel` feature. At most one of
+    // the roads has paths, unless both waypoints are on the same cycle. Undirected graphs only
+    // have simple paths, which cannot be split like that.
+    pub fn count_svr_paths(&self) -> Result<PathCount, Error> {
+        if self.undirected {
+            return self.count_paths_via("svr", "out", &PART2_WAYPOINTS);
+        }
+        for node in ["svr", "out"].iter().chain(&PART2_WAYPOINTS) {
+            self.node(node)?;
+        }
+        let [first, second] = PART2_WAYPOINTS;
+        let segments = [
+            ("svr", first),
+            (first, second),
+            (second, "out"),
+            ("svr", second),
+            (second, first),
+            (first, "out"),
+        ];
+        #[cfg(feature = "parallel")]
+        let mut road1 = self.count_paths_pairs_parallel(&segments)?;
+        #[cfg(not(feature = "parallel"))]
+        let mut road1 = self.count_paths_pairs(&segments)?;
+
+        let road2 = road1.split_off(3);
+        let road1 = PathCount::product(road1.into_iter().map(Ok))?;
+        let road2 = PathCount::product(road2.into_iter().map(Ok))?;
+        return road1.plus(&road2);
+    }
+
+    // The number of paths for each pair of `from` and `to`, in the same order. The condensation
+    // is only built once and shared by all pairs. Unknown nodes have no paths, like in
+    // `count_paths`.
+    pub fn count_paths_pairs(&self, pairs: &[(&str, &str)]) -> Result<Vec<PathCount>, Error> {
+        let condensation = self.condensation();
+        return pairs
+            .iter()
+            .map(|(from, to)| self.count_pair(&condensation, from, to))
+            .collect();
+    }
+
+    // Like `count_paths_pairs`, but counts the pairs in parallel. Counting only reads the graph
+    // and the condensation, each pair has its own counts.
+    #[cfg(feature = "parallel")]
+    pub fn count_paths_pairs_parallel(
+        &self,
+        pairs: &[(&str, &str)],
+    ) -> Result<Vec<PathCount>, Error> {
+        let condensation = self.condensation();
+        return pairs
+            .par_iter()
+            .map(|(from, to)| self.count_pair(&condensation, from, to))
+            .collect();
+    }
+
+    // `count_paths` on an already built condensation.
+    fn count_pair(
+        &self,
+        condensation: &Condensation,
+        from: &str,
+        to: &str,
+    ) -> Result<PathCount, Error> {
+        let (Some(from), Some(to)) = (self.index(from), self.index(to)) else {
+            return Ok(PathCount::Finite(0));
+        };
+        if self.undirected {
+            let count = self.count_simple_paths_between(from, to, &[])?;
+            return Ok(PathCount::Finite(count));
+        }
+        return condensation.count_paths(from, to);
+    }
+
+    // The number of paths from `from` to `to` passing through all `waypoints`. Between the
+    // components of the condensation there are no cycles, so the waypoints can only be passed in
+    // topological order of their components, any other order has no paths. So that's the
+    // generalization of `count_svr_paths_recursive`'s two roads: the product of the paths
+    // between consecutive waypoints in that order. Infinite if any of them is, unless another
+    // has no paths at all. All nodes need to be in the graph.
+    pub fn count_paths_via(
+        &self,
+        from: &str,
+        to: &str,
+        waypoints: &[&str],
+    ) -> Result<PathCount, Error> {
+        return self.count_paths_via_as(from, to, waypoints);
+    }
+
+    // `count_paths_via` with any kind of count.
+    pub fn count_paths_via_as<C: Count>(
+        &self,
+        from: &str,
+        to: &str,
+        waypoints: &[&str],
+    ) -> Result<PathCount<C>, Error> {
+        let from = self.node(from)?;
+        let to = self.node(to)?;
+        let mut stops: Vec<u32> = Vec::with_capacity(waypoints.len());
+        for waypoint in waypoints {
+            stops.push(self.node(waypoint)?);
+        }
+        if self.undirected {
+            let count = self.count_simple_paths_between(from, to, &stops)?;
+            return Ok(PathCount::Finite(C::from_u128(count)));
+        }
+        let condensation = self.condensation();
+        stops.sort_by_key(|node| std::cmp::Reverse(condensation.component[*node as usize]));
+
+        let starts = [from].into_iter().chain(stops.clone());
+        let segments = starts
+            .zip(stops.into_iter().chain([to]))
+            .map(|(previous, next)| condensation.count_paths::<C>(previous, next));
+        return PathCount::product(segments);
+    }
+
+    // The nodes besides `from` and `to` that every path from `from` to `to` passes through, in
+    // the order they're passed. Works on the components of the condensation that are on any
+    // path: in topological order, every path passes a component exactly if no edge between them
+    // jumps over it. Nodes of a component with more than one node are left out, the paths can go
+    // around them. Empty if there are no paths at all.
+    pub fn mandatory_waypoints(&self, from: &str, to: &str) -> Vec<&str> {
+        let (Some(from), Some(to)) = (self.index(from), self.index(to)) else {
+            return Vec::new();
+        };
+        let on_path = self.on_any_path(from, to);
+        if !on_path[to as usize] {
+            return Vec::new();
+        }
+
+        // Counting down from `first` to `last` is a topological order of the components.
+        let condensation = self.condensation();
+        let first = condensation.component[from as usize] as usize;
+        let last = condensation.component[to as usize] as usize;
+        let mut members: Vec<Vec<u32>> = vec![Vec::new(); first + 1];
+        for node in (0..self.names.len() as u32).filter(|node| on_path[*node as usize]) {
+            members[condensation.component[node as usize] as usize].push(node);
+        }
+
+        // The furthest component the edges of the components before the current one lead to.
+        let mut furthest = first;
+        let mut waypoints = Vec::new();
+        for component in (last..=first).rev() {
+            if members[component].is_empty() {
+                continue;
+            }
+            if furthest == component
+                && component != first
+                && component != last
+                && let [node] = members[component][..]
+            {
+                waypoints.push(self.name(node));
+            }
+            for next in &condensation.connections[component] {
+                if !members[*next as usize].is_empty() {
+                    furthest = furthest.min(*next as usize);
+                }
+            }
+        }
+        return waypoints;
+    }
+
+    // Counts the paths from `from` to `to` as the product of the paths between the nodes that
+    // all of them pass through, see `mandatory_waypoints`. Without any, that's just all paths.
+    // Also returns the waypoints, so the caller can tell whether there were any.
+    pub fn count_paths_via_mandatory(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Result<(PathCount, Vec<&str>), Error> {
+        let waypoints = self.mandatory_waypoints(from, to);
+        if waypoints.is_empty() {
+            return Ok((self.count_paths(from, to)?, waypoints));
+        }
+        let count = self.count_paths_via(from, to, &waypoints)?;
+        return Ok((count, waypoints));
+    }
+
+    // The original recursive counting, kept to cross-check `count_svr_paths` on small graphs.
+    #[cfg(test)]
+    fn count_svr_paths_recursive(&self) -> Result<u128, Error> {
+        // It works like this: each path must pass through "dac" AND "fft". Since this is a
+        // directed graph, we can simple trace partial paths and multiply those intermediate
+        // results.
+        // I'm going to call each of the two possibilities a "road" (svr -> dac -> fft -> out
+        // and svr -> fft -> dac -> out).
+        let mut cache = HashMap::new();
+        let road1_part1 = self.follow_path_between("svr", "dac", &mut cache)?;
+        let road1_part2 = self.follow_path_between("dac", "fft", &mut cache)?;
+        let road1_part3 = self.follow_path_between("fft", "out", &mut cache)?;
+
+        let road2_part1 = self.follow_path_between("svr", "fft", &mut cache)?;
+        let road2_part2 = self.follow_path_between("fft", "dac", &mut cache)?;
+        let road2_part3 = self.follow_path_between("dac", "out", &mut cache)?;
+
+        let road1 = road1_part1.times(&road1_part2)?.times(&road1_part3)?;
+        let road2 = road2_part1.times(&road2_part2)?.times(&road2_part3)?;
+        return road1.plus(&road2);
+    }
+
+    // The number of paths from `start` to `end` that don't visit any node twice, also if the graph
+    // has cycles. Unlike `follow_path` nothing is cached, since the paths from a node depend on
+    // the nodes already visited on the way there. So this takes exponential time.
+    pub fn count_simple_paths(&self, start: &str, end: &str) -> Result<u128, Error> {
+        if start == end {
+            return Ok(1);
+        }
+        let (Some(start), Some(end)) = (self.index(start), self.index(end)) else {
+            return Ok(0);
+        };
+        return self.count_simple_paths_between(start, end, &[]);
+    }
+
+    // Like `count_simple_paths`, but only counting the paths through all `waypoints`. That's how
+    // undirected graphs are counted, where every edge is a cycle.
+    fn count_simple_paths_between(
+        &self,
+        from: u32,
+        to: u32,
+        waypoints: &[u32],
+    ) -> Result<u128, Error> {
+        let mut count: u128 = 0;
+        self.visit_simple_paths(from, to, waypoints, |_| {
+            count = count.plus(&1)?;
+            return Ok(());
+        })?;
+        return Ok(count);
+    }
+
+    // Calls `found` with each simple path from `from` to `to` through all `waypoints`, given as
+    // the nodes before `to`. A depth-first search without recursion, like `paths_iter`, that fails
+    // once it has followed `MAX_SIMPLE_PATH_STEPS` edges.
+    fn visit_simple_paths(
+        &self,
+        from: u32,
+        to: u32,
+        waypoints: &[u32],
+        mut found: impl FnMut(&[(u32, usize)]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let mut is_waypoint = vec![false; self.names.len()];
+        for waypoint in waypoints {
+            is_waypoint[*waypoint as usize] = true;
+        }
+        // The number of waypoints not on the current path yet.
+        let mut remaining = is_waypoint.iter().filter(|is| **is).count();
+        let mut visited = vec![false; self.names.len()];
+        // Each entry is a node on the current path and the index of its next connection to visit.
+        let mut stack: Vec<(u32, usize)> = Vec::new();
+        let mut entering = Some(from);
+        let mut steps = 0;
+        loop {
+            if let Some(node) = entering.take() {
+                let waypoint = is_waypoint[node as usize] as usize;
+                if node == to {
+                    if remaining == waypoint {
+                        found(&stack)?;
+                    }
+                } else {
+                    remaining -= waypoint;
+                    visited[node as usize] = true;
+                    stack.push((node, 0));
+                }
+            }
+
+            let Some((node, next)) = stack.pop() else {
+                break;
+            };
+            let Some(connection) = self.connections(node).get(next) else {
+                // All connections done, leave the node.
+                remaining += is_waypoint[node as usize] as usize;
+                visited[node as usize] = false;
+                continue;
+            };
+            steps += 1;
+            if steps > MAX_SIMPLE_PATH_STEPS {
+                return Err(Error::TooManySteps {
+                    limit: MAX_SIMPLE_PATH_STEPS,
+                });
+            }
+            stack.push((node, next + 1));
+            if !visited[*connection as usize] {
+                entering = Some(*connection);
+            }
+        }
+        return Ok(());
+    }
+
+    // All paths from `from` to `to`, one at a time. A depth-first search without recursion whose
+    // stack is just the current path, so taking a few paths of a huge graph is cheap. Nodes that
+    // are already on the current path are skipped, so cycles don't loop forever. Unknown nodes
+    // have no paths.
+    pub fn paths_iter(&self, from: &str, to: &str) -> impl Iterator<Item = Vec<String>> + '_ {
+        let ends = self.index(from).zip(self.index(to));
+        return self.paths_iter_within(ends, vec![true; self.names.len()]);
+    }
+
+    // Like `paths_iter`, but the paths only pass through the nodes marked in `allowed`, indexed
+    // by node. No paths if `ends` is `None`.
+    pub fn paths_iter_within(
+        &self,
+        ends: Option<(u32, u32)>,
+        allowed: Vec<bool>,
+    ) -> impl Iterator<Item = Vec<String>> + '_ {
+        // Each entry is a node and the index of its next connection to visit.
+        let mut stack: Vec<(u32, usize)> = match ends {
+            Some((from, _)) if allowed[from as usize] => vec![(from, 0)],
+            _ => Vec::new(),
+        };
+        let to = ends.map(|(_, to)| to);
+
+        return std::iter::from_fn(move || {
+            while let Some((node, next)) = stack.pop() {
+                if Some(node) == to {
+                    let path = stack
+                        .iter()
+                        .map(|(n, _)| self.name(*n).to_string())
+                        .chain([self.name(node).to_string()])
+                        .collect();
+                    return Some(path);
+                }
+                let Some(connection) = self.connections(node).get(next) else {
+                    continue;
+                };
+                stack.push((node, next + 1));
+                if allowed[*connection as usize] && !stack.iter().any(|(n, _)| n == connection) {
+                    stack.push((*connection, 0));
+                }
+            }
+            return None;
+        });
+    }
+
+    // `follow_path` between two nodes given by their names. Unknown nodes have no paths.
+    #[cfg(test)]
+    fn follow_path_between(
+        &self,
+        from: &str,
+        to: &str,
+        cache: &mut HashMap<(u32, u32), u128>,
+    ) -> Result<u128, Error> {
+        if from == to {
+            return Ok(1);
+        }
+        let (Some(from), Some(to)) = (self.index(from), self.index(to)) else {
+            return Ok(0);
+        };
+        return self.follow_path(from, to, &HashSet::new(), cache);
+    }
+
+    // Recurses once per node on the path, which are all in `visited`. So the size of `visited` is
+    // the depth, which is capped at `max_depth`.
+    #[cfg(test)]
+    fn follow_path(
+        &self,
+        node: u32,
+        target: u32,
+        visited: &HashSet<u32>,
+        cache: &mut HashMap<(u32, u32), u128>,
+    ) -> Result<u128, Error> {
+        if node == target {
+            return Ok(1);
+        }
+
+        let cache_key = (node, target);
+        if let Some(count) = cache.get(&cache_key) {
+            return Ok(*count);
+        }
+
+        if visited.contains(&node) {
+            return Ok(0);
+        }
+        if visited.len() >= self.max_depth {
+            return Err(Error::DepthExceeded);
+        }
+
+        let mut updated_visited = visited.clone();
+        updated_visited.insert(node);
+
+        let mut count: u128 = 0;
+        for connection in self.connections(node) {
+            let recursed_count = self.follow_path(*connection, target, &updated_visited, cache)?;
+            count = count.plus(&recursed_count)?;
+        }
+
+        cache.insert(cache_key, count);
+        return Ok(count);
+    }
+}
+
+impl Condensation {
+    // The number of paths between the nodes `from` and `to` of the graph. Only the components
+    // that are reachable from `from` and can reach `to` matter. If any of those is cyclic, there
+    // are infinitely many paths, otherwise the path counts are pushed forward in topological
+    // order.
+    fn count_paths<C: Count>(&self, from: u32, to: u32) -> Result<PathCount<C>, Error> {
+        let from = self.component[from as usize] as usize;
+        let to = self.component[to as usize] as usize;
+        if to > from {
+            return Ok(PathCount::Finite(C::zero()));
+        }
+
+        // The components in between only have connections to lower numbers.
+        let mut reaches_to = vec![false; from + 1];
+        reaches_to[to] = true;
+        for component in (to + 1)..=from {
+            reaches_to[component] = self.connections[component]
+                .iter()
+                .any(|next| (*next as usize) >= to && reaches_to[*next as usize]);
+        }
+        if !reaches_to[from] {
+            return Ok(PathCount::Finite(C::zero()));
+        }
+
+        let mut counts = vec![C::zero(); from + 1];
+        counts[from] = C::one();
+        for component in (to..=from).rev() {
+            if counts[component].is_zero() || !reaches_to[component] {
+                continue;
+            }
+            if self.cyclic[component] {
+                return Ok(PathCount::Infinite);
+            }
+            let count = counts[component].clone();
+            for next in &self.connections[component] {
+                let next = *next as usize;
+                if next >= to && reaches_to[next] {
+                    counts[next] = counts[next].plus(&count)?;
+                }
+            }
+        }
+        return Ok(PathCount::Finite(counts.swap_remove(to)));
+    }
+}
+
+// "you" leads to all `width` nodes of the first of `layers` layers, each node leads to two nodes
+// of the next layer, and the nodes of the last layer lead to "out". So there are
+// `width * 2^(layers - 1)` paths. For tests and benchmarks.
+pub fn layered_dag(width: usize, layers: usize) -> String {
+    let first: Vec<String> = (0..width).map(|i| format!("l0_{}", i)).collect();
+    let mut input = format!("you: {}\n", first.join(" "));
+    for layer in 0..layers {
+        for i in 0..width {
+            if layer + 1 == layers {
+                input += &format!("l{}_{}: out\n", layer, i);
+            } else {
+                input += &format!(
+                    "l{}_{}: l{}_{} l{}_{}\n",
+                    layer,
+                    i,
+                    layer + 1,
+                    (i * 7 + 1) % width,
+                    layer + 1,
+                    (i * 13 + 5) % width
+                );
+            }
+        }
+    }
+    return input;
+}
+
+// `count` random pairs of nodes of `layered_dag(width, layers)`, each `to` at most `distance`
+// layers after its `from`. Those in the same layer have no paths between them, those further
+// apart usually several. For tests and benchmarks.
+pub fn layered_pairs(
+    count: usize,
+    width: usize,
+    layers: usize,
+    distance: usize,
+    seed: u64,
+) -> Vec<(String, String)> {
+    let mut state = seed.max(1);
+    return (0..count)
+        .map(|_| {
+            let layer = (next_random(&mut state) as usize) % layers;
+            let to_layer = layer + (next_random(&mut state) as usize) % (distance + 1);
+            let from = (next_random(&mut state) as usize) % width;
+            let to = (next_random(&mut state) as usize) % width;
+            (
+                format!("l{}_{}", layer, from),
+                format!("l{}_{}", to_layer.min(layers - 1), to),
+            )
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Builds "you: n1", "n1: n2", ..., "nX: out".
+    fn chain(length: usize) -> String {
+        let mut input = String::from("you: n1\n");
+        for i in 1..length {
+            input += &format!("n{}: n{}\n", i, i + 1);
+        }
+        input += &format!("n{}: out\n", length);
+        return input;
+    }
+
+    #[test]
+    fn test_count_all_paths() {
+        let graph = Graph::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        assert_eq!(graph.count_all_paths().unwrap(), PathCount::Finite(5));
+        assert_eq!(
+            graph.count_all_paths().unwrap(),
+            PathCount::Finite(graph.count_all_paths_recursive().unwrap())
+        );
+
+        let graph = Graph::from_input(&chain(100)).unwrap();
+        assert_eq!(
+            graph.count_all_paths().unwrap(),
+            PathCount::Finite(graph.count_all_paths_recursive().unwrap())
+        );
+
+        let graph = Graph::from_input(&chain_with_dead_ends(20, 5)).unwrap();
+        assert_eq!(
+            graph.count_all_paths().unwrap(),
+            PathCount::Finite(graph.count_all_paths_recursive().unwrap())
+        );
+        assert_eq!(
+            graph.count_paths("n3", "d3_5").unwrap(),
+            PathCount::Finite(1)
+        );
+        assert_eq!(
+            graph.count_paths("d3_5", "n3").unwrap(),
+            PathCount::Finite(0)
+        );
+        assert_eq!(graph.count_paths("n3", "n3").unwrap(), PathCount::Finite(1));
+        assert_eq!(
+            graph.count_paths("missing", "out").unwrap(),
+            PathCount::Finite(0)
+        );
+
+        let graph = Graph::from_input(&layered_dag(5, 3)).unwrap();
+        assert_eq!(graph.count_all_paths().unwrap(), PathCount::Finite(5 << 2));
+        assert_eq!(graph.count_all_paths_recursive().unwrap(), 5 << 2);
+    }
+
+    #[test]
+    fn test_count_svr_paths() {
+        let graph = Graph::from_input(include_str!("../rsc/sample2.txt")).unwrap();
+        assert_eq!(graph.count_svr_paths().unwrap(), PathCount::Finite(2));
+        assert_eq!(graph.count_svr_paths_recursive().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_count_all_paths_long_chain() {
+        // Deep enough to overflow the stack with the recursive `follow_path`.
+        let graph = Graph::from_input(&chain(200_000)).unwrap();
+        assert_eq!(graph.count_all_paths().unwrap(), PathCount::Finite(1));
+    }
+
+    #[test]
+    fn test_interned_names() {
+        let graph = Graph::from_input("you: a b\na: out\nb: a out").unwrap();
+        assert_eq!(graph.names, ["you", "a", "b", "out"]);
+        let node = |name| graph.index(name).unwrap();
+        assert_eq!(graph.name(node("b")), "b");
+        assert_eq!(graph.connections(node("b")), [node("a"), node("out")]);
+        assert_eq!(graph.connections(node("out")), []);
+        assert_eq!(graph.index("bogus"), None);
+
+        // Unknown names still fail or have no paths, by name.
+        assert_eq!(
+            graph.count_paths("you", "out").unwrap(),
+            PathCount::Finite(3)
+        );
+        assert_eq!(
+            graph.count_paths("bogus", "out").unwrap(),
+            PathCount::Finite(0)
+        );
+        assert_eq!(graph.count_simple_paths("you", "bogus").unwrap(), 0);
+        match graph.count_paths_via("you", "out", &["a", "bogus"]) {
+            Err(Error::MissingNode(name)) => assert_eq!(name, "bogus"),
+            result => panic!("unexpected {:?}", result),
+        }
+        match graph.count_paths_via("bogus", "out", &[]) {
+            Err(Error::MissingNode(name)) => assert_eq!(name, "bogus"),
+            result => panic!("unexpected {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_count_all_paths_cycle() {
+        // `from_input` already rejects cycles.
+        let graph = Graph::from_input_with_cycles("you: a\na: b\nb: a out").unwrap();
+        assert_eq!(graph.count_all_paths().unwrap(), PathCount::Infinite);
+        assert_eq!(graph.count_paths("b", "out").unwrap(), PathCount::Infinite);
+        assert_eq!(graph.count_paths("out", "a").unwrap(), PathCount::Finite(0));
+        assert_eq!(graph.find_cycle().unwrap(), ["a", "b"]);
+
+        // A node leading to itself.
+        let graph = Graph::from_input_with_cycles("you: a\na: a out").unwrap();
+        assert_eq!(graph.count_all_paths().unwrap(), PathCount::Infinite);
+
+        // Cycles off the paths from "you" to "out" don't matter: one that can't reach "out", one
+        // that can't be reached from "you", and one only behind "out".
+        let input = concat!(
+            "you: a b\na: out c\nb: out\nc: d\nd: c\n",
+            "e: f\nf: e a\n",
+            "out: g\ng: h\nh: g\n"
+        );
+        let graph = Graph::from_input_with_cycles(input).unwrap();
+        assert_eq!(graph.count_all_paths().unwrap(), PathCount::Finite(2));
+        assert_eq!(graph.count_paths("e", "out").unwrap(), PathCount::Infinite);
+        assert_eq!(
+            graph.count_paths_via("you", "out", &["a"]).unwrap(),
+            PathCount::Finite(1)
+        );
+        // Ending in a cycle is just as infinite.
+        assert_eq!(
+            graph.count_paths_via("you", "d", &["a"]).unwrap(),
+            PathCount::Infinite
+        );
+        // No paths at all beat infinitely many on another part of the way.
+        assert_eq!(
+            graph.count_paths_via("e", "out", &["b"]).unwrap(),
+            PathCount::Finite(0)
+        );
+        assert_eq!(PathCount::<u128>::Infinite.to_string(), "infinite");
+
+        // Same as without cycles.
+        let graph = Graph::from_input_with_cycles(include_str!("../rsc/sample2.txt")).unwrap();
+        assert_eq!(graph.count_svr_paths().unwrap(), PathCount::Finite(2));
+    }
+
+    fn example_cycle(input: &str) -> Option<Vec<String>> {
+        match Graph::from_input(input) {
+            Err(Error::CycleDetected { example_cycle }) => return Some(example_cycle),
+            Err(error) => panic!("unexpected {:?}", error),
+            Ok(_) => return None,
+        }
+    }
+
+    #[test]
+    fn test_cycle_detected() {
+        assert_eq!(
+            example_cycle("you: a\na: b\nb: a out"),
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(
+            example_cycle("you: x\nx: y out\ny: z\nz: x"),
+            Some(vec!["x".to_string(), "y".to_string(), "z".to_string()])
+        );
+        assert_eq!(example_cycle("you: you out"), Some(vec!["you".to_string()]));
+
+        // Reaching a node twice on different paths is not a cycle.
+        assert_eq!(example_cycle("you: a b\na: c\nb: c\nc: out"), None);
+
+        // The counts of acyclic graphs don't change.
+        let graph = Graph::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        assert_eq!(graph.count_all_paths().unwrap(), PathCount::Finite(5));
+        let graph = Graph::from_input(include_str!("../rsc/sample2.txt")).unwrap();
+        assert_eq!(graph.count_svr_paths().unwrap(), PathCount::Finite(2));
+    }
+
+    // A chain from "you" to "out" where every node also leads into a binary tree of the given
+    // depth that never reaches "out".
+    fn chain_with_dead_ends(length: usize, depth: usize) -> String {
+        let mut input = String::from("you: n1\n");
+        for i in 1..=length {
+            let next = if i == length {
+                "out".to_string()
+            } else {
+                format!("n{}", i + 1)
+            };
+            input += &format!("n{}: {} d{}_1\n", i, next, i);
+            for node in 1..(1 << depth) {
+                input += &format!(
+                    "d{}_{}: d{}_{} d{}_{}\n",
+                    i,
+                    node,
+                    i,
+                    2 * node,
+                    i,
+                    2 * node + 1
+                );
+            }
+        }
+        return input;
+    }
+
+    #[test]
+    fn test_count_all_paths_pruned() {
+        let graph = Graph::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        assert_eq!(graph.count_all_paths_recursive().unwrap(), 5);
+        assert_eq!(graph.count_all_paths_unpruned().unwrap(), 5);
+
+        let graph = Graph::from_input(&chain_with_dead_ends(20, 5)).unwrap();
+        let pruned = graph.pruned("out");
+        let kept = pruned.connections.iter().filter(|c| !c.is_empty()).count();
+        assert_eq!(kept, 21);
+        assert!(pruned.connections(pruned.index("d1_1").unwrap()).is_empty());
+        assert_eq!(graph.count_all_paths_recursive().unwrap(), 1);
+        assert_eq!(graph.count_all_paths_unpruned().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let mut graph = Graph::from_input(&chain(1000)).unwrap();
+        assert_eq!(graph.count_all_paths_recursive().unwrap(), 1);
+
+        graph.max_depth = 100;
+        assert!(matches!(
+            graph.count_all_paths_recursive(),
+            Err(Error::DepthExceeded)
+        ));
+
+        // The chain has 1001 nodes before "out".
+        graph.max_depth = 1001;
+        assert_eq!(graph.count_all_paths_recursive().unwrap(), 1);
+        graph.max_depth = 1000;
+        assert!(matches!(
+            graph.count_all_paths_recursive(),
+            Err(Error::DepthExceeded)
+        ));
+    }
+
+    #[test]
+    fn test_path_counts_from() {
+        let graph = Graph::from_input("you: a b\na: c\nb: c sink2\nc: sink1 sink2").unwrap();
+        let counts = graph.path_counts_from("you").unwrap();
+        assert_eq!(counts["you"], 1);
+        assert_eq!(counts["c"], 2);
+        assert_eq!(counts["sink1"], 2);
+        assert_eq!(counts["sink2"], 3);
+        assert_eq!(counts.len(), 6);
+
+        let counts = graph.path_counts_from("b").unwrap();
+        assert_eq!((counts["sink1"], counts["sink2"]), (1, 2));
+        assert!(!counts.contains_key("a"));
+
+        let graph = Graph::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        assert_eq!(graph.path_counts_from("you").unwrap()["out"], 5);
+
+        // Deeper than `follow_path` is allowed to go.
+        let graph = Graph::from_input(&chain(20_000)).unwrap();
+        assert_eq!(graph.path_counts_from("you").unwrap()["out"], 1);
+    }
+
+    #[test]
+    fn test_count_paths_via() {
+        let graph = Graph::from_input(include_str!("../rsc/sample2.txt")).unwrap();
+        assert_eq!(
+            graph
+                .count_paths_via("svr", "out", &["dac", "fft"])
+                .unwrap(),
+            PathCount::Finite(2)
+        );
+        assert_eq!(
+            graph
+                .count_paths_via("svr", "out", &["fft", "dac"])
+                .unwrap(),
+            PathCount::Finite(2)
+        );
+        assert_eq!(
+            graph.count_paths_via("svr", "out", &["fft"]).unwrap(),
+            PathCount::Finite(4)
+        );
+        assert_eq!(
+            graph.count_paths_via("svr", "out", &[]).unwrap(),
+            PathCount::Finite(8)
+        );
+        assert_eq!(
+            graph
+                .count_paths_via("svr", "out", &["aaa", "bbb"])
+                .unwrap(),
+            PathCount::Finite(0)
+        );
+        assert!(matches!(
+            graph.count_paths_via("svr", "out", &["missing"]),
+            Err(Error::MissingNode(node)) if node == "missing"
+        ));
+        assert!(matches!(
+            graph.count_paths_via("svr", "nowhere", &[]),
+            Err(Error::MissingNode(node)) if node == "nowhere"
+        ));
+    }
+
+    #[test]
+    fn test_mandatory_waypoints() {
+        // Every path passes through w1, w2 and w3, with 2, 2 and 3 ways between them.
+        let input = "you: a1 a2\na1: w1\na2: w1\nw1: b1 b2\nb1: w2\nb2: w2\n\
+            w2: c1 c2 c3\nc1: w3\nc2: w3\nc3: w3\nw3: out\nside: w2";
+        let graph = Graph::from_input(input).unwrap();
+        assert_eq!(graph.mandatory_waypoints("you", "out"), ["w1", "w2", "w3"]);
+        assert_eq!(
+            graph.count_paths_via_mandatory("you", "out").unwrap(),
+            (PathCount::Finite(12), vec!["w1", "w2", "w3"])
+        );
+        assert_eq!(
+            graph.count_paths("you", "out").unwrap(),
+            PathCount::Finite(12)
+        );
+        assert_eq!(graph.mandatory_waypoints("w1", "w2"), Vec::<&str>::new());
+
+        let graph = Graph::from_input(include_str!("../rsc/sample2.txt")).unwrap();
+        assert_eq!(graph.mandatory_waypoints("svr", "out"), ["ccc", "fff"]);
+
+        // No node is on every path.
+        let graph = Graph::from_input("you: a b\na: c out\nb: c\nc: out").unwrap();
+        assert!(graph.mandatory_waypoints("you", "out").is_empty());
+        assert_eq!(
+            graph.count_paths_via_mandatory("you", "out").unwrap(),
+            (PathCount::Finite(3), Vec::new())
+        );
+        assert!(graph.mandatory_waypoints("out", "you").is_empty());
+
+        // Cycles are fine, only the nodes inside them can't be told apart.
+        let input = "you: a\na: b\nb: a w\nw: c d\nc: out\nd: out";
+        let graph = Graph::from_input_with_cycles(input).unwrap();
+        assert_eq!(graph.mandatory_waypoints("you", "out"), ["w"]);
+        assert_eq!(
+            graph.count_paths_via_mandatory("you", "out").unwrap(),
+            (PathCount::Infinite, vec!["w"])
+        );
+        let graph = Graph::from_input_with_cycles("you: a\na: a out").unwrap();
+        assert_eq!(graph.mandatory_waypoints("you", "out"), ["a"]);
+
+        // The paths from the other layers would overflow, but they don't start at "you".
+        let input = three_way_layers(90).replace("you:", "other:") + "you: w\nw: out\n";
+        let graph = Graph::from_input(&input).unwrap();
+        assert_eq!(graph.mandatory_waypoints("you", "out"), ["w"]);
+        assert_eq!(
+            graph.mandatory_waypoints("other", "out"),
+            Vec::<&str>::new()
+        );
+    }
+
+    #[test]
+    fn test_path_through_counts() {
+        // Like `count_svr_paths`, the paths through a node are the product of the paths of the
+        // two segments it splits them into.
+        let graph = Graph::from_input(include_str!("../rsc/sample2.txt")).unwrap();
+        let counts = graph.path_through_counts("svr", "out").unwrap();
+        for node in PART2_WAYPOINTS {
+            let before = graph.count_paths("svr", node).unwrap();
+            let after = graph.count_paths(node, "out").unwrap();
+            let (PathCount::Finite(before), PathCount::Finite(after)) = (before, after) else {
+                panic!("infinite paths through {}", node);
+            };
+            assert_eq!(counts[node], PathCount::Finite(before * after), "{}", node);
+        }
+        let finite = |node: &str| match counts[node] {
+            PathCount::Finite(count) => count,
+            PathCount::Infinite => panic!("infinite paths through {}", node),
+        };
+        assert_eq!((finite("dac"), finite("fft")), (4, 4));
+        assert_eq!((finite("svr"), finite("out"), finite("ccc")), (8, 8, 8));
+        assert_eq!(counts.len(), 14);
+
+        // Every node against a query per node.
+        let graph = Graph::from_input(&layered_dag(3, 4)).unwrap();
+        let counts = graph.path_through_counts("you", "out").unwrap();
+        assert_eq!(counts.len(), graph.names.len());
+        for (node, count) in &counts {
+            assert_eq!(
+                graph.count_paths_via("you", "out", &[node]).unwrap(),
+                *count,
+                "{}",
+                node
+            );
+        }
+
+        // Dead ends have no paths through them.
+        let graph = Graph::from_input(&chain_with_dead_ends(3, 2)).unwrap();
+        let counts = graph.path_through_counts("you", "out").unwrap();
+        assert!(counts.values().all(|count| *count == PathCount::Finite(1)));
+        assert_eq!(counts.len(), 5);
+        assert!(
+            graph
+                .path_through_counts("you", "bogus")
+                .unwrap()
+                .is_empty()
+        );
+        assert!(graph.path_through_counts("out", "you").unwrap().is_empty());
+
+        // A cycle on the way makes all counts infinite, one beside it doesn't matter.
+        let graph = Graph::from_input_with_cycles("you: a\na: you out").unwrap();
+        let counts = graph.path_through_counts("you", "out").unwrap();
+        assert_eq!(counts.len(), 3);
+        assert!(counts.values().all(|count| *count == PathCount::Infinite));
+        let graph = Graph::from_input_with_cycles("you: a b\na: out\nb: c\nc: b").unwrap();
+        let counts = graph.path_through_counts("you", "out").unwrap();
+        assert_eq!(counts.len(), 3);
+        assert!(counts.values().all(|count| *count == PathCount::Finite(1)));
+
+        // The paths from the other layers would overflow, but they don't start at "you".
+        let input = three_way_layers(90).replace("you:", "other:") + "you: out\n";
+        let graph = Graph::from_input(&input).unwrap();
+        let counts = graph.path_through_counts("you", "out").unwrap();
+        assert_eq!(counts["out"], PathCount::Finite(1));
+        assert_eq!(counts.len(), 2);
+
+        // Simple paths on undirected graphs, also with a cycle.
+        let graph = Graph::parse("you - a\na - out\nyou - out", false).unwrap();
+        let counts = graph.path_through_counts("you", "out").unwrap();
+        assert_eq!(counts["you"], PathCount::Finite(2));
+        assert_eq!(counts["a"], PathCount::Finite(1));
+        assert_eq!(counts["out"], PathCount::Finite(2));
+    }
+
+    #[test]
+    fn test_nodes_on_any_path() {
+        // "side" hangs off the path but never reaches "out", "before" leads into it but cannot
+        // be reached from "you".
+        let input = "before: a\nyou: a b\na: c side\nb: c\nc: out\nside: end";
+        let graph = Graph::from_input(input).unwrap();
+        let nodes = graph.nodes_on_any_path("you", "out");
+        let expected: HashSet<String> = ["you", "a", "b", "c", "out"]
+            .iter()
+            .map(|node| node.to_string())
+            .collect();
+        assert_eq!(nodes, expected);
+
+        let nodes = graph.nodes_on_any_path("a", "end");
+        assert_eq!(nodes.len(), 3);
+        assert!(graph.nodes_on_any_path("out", "you").is_empty());
+        assert!(graph.nodes_on_any_path("you", "bogus").is_empty());
+        assert_eq!(graph.nodes_on_any_path("c", "c").len(), 1);
+
+        // All the nodes of a cycle on the way are relevant.
+        let graph = Graph::from_input_with_cycles("you: a\na: b\nb: a out\nc: a").unwrap();
+        assert_eq!(graph.nodes_on_any_path("you", "out").len(), 4);
+
+        let graph = Graph::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        let nodes = graph.nodes_on_any_path("you", "out");
+        for path in graph.paths_iter("you", "out") {
+            assert!(path.iter().all(|node| nodes.contains(node)));
+        }
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let graph = Graph::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        assert_eq!(
+            graph.to_dot(&["you", "out", "bogus"]),
+            "digraph {
+    rankdir=LR;
+    \"aaa\";
+    \"bbb\";
+    \"ccc\";
+    \"ddd\";
+    \"eee\";
+    \"fff\";
+    \"ggg\";
+    \"hhh\";
+    \"iii\";
+    \"out\" [style=filled, fillcolor=gold];
+    \"you\" [style=filled, fillcolor=gold];
+    \"aaa\" -> \"hhh\";
+    \"aaa\" -> \"you\";
+    \"bbb\" -> \"ddd\";
+    \"bbb\" -> \"eee\";
+    \"ccc\" -> \"ddd\";
+    \"ccc\" -> \"eee\";
+    \"ccc\" -> \"fff\";
+    \"ddd\" -> \"ggg\";
+    \"eee\" -> \"out\";
+    \"fff\" -> \"out\";
+    \"ggg\" -> \"out\";
+    \"hhh\" -> \"ccc\";
+    \"hhh\" -> \"fff\";
+    \"hhh\" -> \"iii\";
+    \"iii\" -> \"out\";
+    \"you\" -> \"bbb\";
+    \"you\" -> \"ccc\";
+}
+"
+        );
+
+        // The order of the lines doesn't matter.
+        let shuffled = Graph::from_input("you: b a\nb: out\na: out").unwrap();
+        let sorted = Graph::from_input("a: out\nb: out\nyou: a b").unwrap();
+        assert_eq!(shuffled.to_dot(&[]), sorted.to_dot(&[]));
+
+        let graph = Graph::parse("b - a\na - c", false).unwrap();
+        assert_eq!(
+            graph.to_dot(&["a"]),
+            "graph {
+    rankdir=LR;
+    \"a\" [style=filled, fillcolor=gold];
+    \"b\";
+    \"c\";
+    \"a\" -- \"b\";
+    \"a\" -- \"c\";
+}
+"
+        );
+
+        // Names can contain characters that need escaping.
+        let graph = Graph::from_input("you: a\"b\na\"b: c\\d").unwrap();
+        assert_eq!(
+            graph.to_dot(&[]),
+            "digraph {
+    rankdir=LR;
+    \"a\\\"b\";
+    \"c\\\\d\";
+    \"you\";
+    \"a\\\"b\" -> \"c\\\\d\";
+    \"you\" -> \"a\\\"b\";
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let accepted = [
+            ("a: b c", vec!["b", "c"]),
+            ("a:  b   c", vec!["b", "c"]),
+            ("a: b, c", vec!["b", "c"]),
+            ("a:b,c", vec!["b", "c"]),
+            ("  a :\tb ,  c  ", vec!["b", "c"]),
+            ("a: b", vec!["b"]),
+        ];
+        for (line, targets) in accepted {
+            let graph = Graph::from_input_with_cycles(line).unwrap();
+            let node = graph.node("a").unwrap();
+            let names: Vec<&str> = graph
+                .connections(node)
+                .iter()
+                .map(|connection| graph.name(*connection))
+                .collect();
+            assert_eq!(names, targets, "{:?}", line);
+            assert_eq!(graph.names.len(), targets.len() + 1, "{:?}", line);
+        }
+
+        let rejected = ["a:", "a:  ", "a: ,", ": b", "  : b", "a b", ""];
+        for line in rejected {
+            let input = format!("you: a\n{}\na: out", line);
+            let result = Graph::from_input_with_cycles(&input);
+            if line.is_empty() {
+                // Blank lines are skipped.
+                assert!(result.is_ok());
+                continue;
+            }
+            assert!(
+                matches!(result, Err(Error::InvalidInput { line_no: 2, .. })),
+                "{:?}",
+                line
+            );
+        }
+
+        // The line numbers count blank lines as well.
+        assert!(matches!(
+            Graph::from_input_with_cycles("\nyou: a\n\na: b\nyou: out\n"),
+            Err(Error::DuplicateKey { node, line_no: 5 }) if node == "you"
+        ));
+        assert!(matches!(
+            Graph::parse("a - b\n\n - c", false),
+            Err(Error::InvalidInput { line_no: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_count_simple_paths() {
+        // you -> a -> b -> out, you -> a -> b -> c -> a is a cycle, and b -> c -> out.
+        let input = "you: a\na: b\nb: c out\nc: a out";
+        assert!(matches!(
+            Graph::from_input(input),
+            Err(Error::CycleDetected { .. })
+        ));
+        let graph = Graph::from_input_with_cycles(input).unwrap();
+        assert_eq!(graph.count_simple_paths("you", "out").unwrap(), 2);
+        assert_eq!(graph.count_simple_paths("c", "out").unwrap(), 2);
+        assert_eq!(graph.count_simple_paths("a", "a").unwrap(), 1);
+
+        // A complete graph on 4 nodes besides "you" and "out".
+        let input = "you: a\na: b c d out\nb: a c d out\nc: a b d out\nd: a b c out";
+        let graph = Graph::from_input_with_cycles(input).unwrap();
+        // From a, 0 to 3 of the other 3 nodes in any order: 1 + 3 + 6 + 6.
+        assert_eq!(graph.count_simple_paths("you", "out").unwrap(), 16);
+
+        // Same as the memoized counting on acyclic graphs.
+        for input in [
+            include_str!("../rsc/sample1.txt"),
+            &chain_with_dead_ends(5, 3),
+        ] {
+            let graph = Graph::from_input(input).unwrap();
+            assert_eq!(
+                PathCount::Finite(graph.count_simple_paths("you", "out").unwrap()),
+                graph.count_all_paths().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_paths_iter() {
+        let graph = Graph::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        let paths: Vec<Vec<String>> = graph.paths_iter("you", "out").collect();
+        assert_eq!(
+            PathCount::Finite(paths.len() as u128),
+            graph.count_all_paths().unwrap()
+        );
+        for path in &paths {
+            assert_eq!(path.first().unwrap(), "you");
+            assert_eq!(path.last().unwrap(), "out");
+        }
+        let distinct: HashSet<&Vec<String>> = paths.iter().collect();
+        assert_eq!(distinct.len(), paths.len());
+
+        assert_eq!(
+            graph.paths_iter("you", "you").collect::<Vec<_>>(),
+            [["you"]]
+        );
+        assert_eq!(graph.paths_iter("bogus", "out").count(), 0);
+        assert_eq!(graph.paths_iter("out", "you").count(), 0);
+
+        // Cycles are not followed around.
+        let graph = Graph::from_input_with_cycles("you: a\na: b\nb: c out\nc: a out").unwrap();
+        assert_eq!(graph.paths_iter("you", "out").count(), 2);
+
+        // Over a million paths, but only the first few are looked for, and the rest can still be
+        // taken from where it stopped.
+        let graph = Graph::from_input(&layered_dag(1000, 11)).unwrap();
+        assert_eq!(
+            graph.count_all_paths().unwrap(),
+            PathCount::Finite(1000 << 10)
+        );
+        let mut paths = graph.paths_iter("you", "out");
+        let first: Vec<Vec<String>> = paths.by_ref().take(3).collect();
+        assert_eq!(first.len(), 3);
+        assert!(first.iter().all(|path| path.len() == 13));
+        let fourth = paths.next().unwrap();
+        assert_eq!(fourth.len(), 13);
+        assert!(!first.contains(&fourth));
+
+        // Only the nodes between the waypoints, the dead end behind "bbb" is left out.
+        let graph = Graph::from_input("svr: aaa bbb\naaa: fft\nbbb: ccc\nfft: out").unwrap();
+        let allowed = graph.on_any_path_via(
+            graph.node("svr").unwrap(),
+            graph.node("out").unwrap(),
+            &[graph.node("fft").unwrap()],
+        );
+        let names: HashSet<&str> = (0..graph.names.len() as u32)
+            .filter(|node| allowed[*node as usize])
+            .map(|node| graph.name(node))
+            .collect();
+        assert_eq!(names, HashSet::from(["svr", "aaa", "fft", "out"]));
+    }
+
+    // "you" leads to all three nodes of the first of `layers` layers, each node leads to all
+    // three nodes of the next layer, and the last layer leads to "out". So there are 3^layers
+    // paths.
+    fn three_way_layers(layers: usize) -> String {
+        let layer = |index: usize| {
+            let nodes: Vec<String> = (0..3).map(|i| format!("l{}_{}", index, i)).collect();
+            return nodes.join(" ");
+        };
+        let mut input = format!("you: {}\n", layer(0));
+        for index in 0..layers {
+            let next = if index + 1 == layers {
+                "out".to_string()
+            } else {
+                layer(index + 1)
+            };
+            for i in 0..3 {
+                input += &format!("l{}_{}: {}\n", index, i, next);
+            }
+        }
+        return input;
+    }
+
+    #[test]
+    fn test_count_overflow() {
+        // Way more than fits into a `u64`.
+        let graph = Graph::from_input(&three_way_layers(64)).unwrap();
+        let count = graph.count_all_paths_recursive().unwrap();
+        assert_eq!(count.to_string(), "3433683820292512484657849089281");
+        assert_eq!(graph.count_all_paths().unwrap(), PathCount::Finite(count));
+        assert_eq!(
+            graph.count_paths_via("you", "out", &["l40_1"]).unwrap(),
+            PathCount::Finite(count / 3)
+        );
+
+        // 3^81 doesn't fit into a `u128` anymore.
+        let graph = Graph::from_input(&three_way_layers(81)).unwrap();
+        assert!(matches!(graph.count_all_paths(), Err(Error::CountOverflow)));
+        assert!(matches!(
+            graph.count_all_paths_recursive(),
+            Err(Error::CountOverflow)
+        ));
+        // Only the paths through the waypoint are counted, and they still fit.
+        assert_eq!(
+            graph.count_paths_via("you", "out", &["l40_1"]).unwrap(),
+            PathCount::Finite(3u128.pow(80))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bigint")]
+    fn test_count_bigint() {
+        let graph = Graph::from_input(&three_way_layers(100)).unwrap();
+        let expected = BigUint::from(3u32).pow(100);
+        assert_eq!(
+            graph.count_paths_as::<BigUint>("you", "out").unwrap(),
+            PathCount::Finite(expected.clone())
+        );
+        assert_eq!(
+            graph
+                .count_paths_via_as::<BigUint>("you", "out", &["l40_1", "l80_2"])
+                .unwrap(),
+            PathCount::Finite(expected / 9u32)
+        );
+
+        // Small counts are the same either way.
+        let graph = Graph::from_input(include_str!("../rsc/sample2.txt")).unwrap();
+        assert_eq!(
+            graph
+                .count_paths_via_as::<BigUint>("svr", "out", &PART2_WAYPOINTS)
+                .unwrap()
+                .to_string(),
+            graph.count_svr_paths().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let graph = Graph::from_input(include_str!("../rsc/sample2.txt")).unwrap();
+        assert_eq!(graph.validate(), []);
+
+        // "aaa" leads to "you", but nothing leads to "aaa", nor to the other two.
+        let graph = Graph::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        assert_eq!(
+            graph.validate(),
+            [
+                Warning::Unreachable("aaa".to_string()),
+                Warning::Unreachable("hhh".to_string()),
+                Warning::Unreachable("iii".to_string()),
+            ]
+        );
+
+        // The typo "bb" is a dead end.
+        let graph = Graph::from_input("you: a bb\na: out\nb: out").unwrap();
+        assert_eq!(
+            graph.validate(),
+            [
+                Warning::DanglingTarget {
+                    from: "you".to_string(),
+                    to: "bb".to_string()
+                },
+                Warning::Unreachable("b".to_string()),
+            ]
+        );
+
+        // Without "you" and "svr", there's nothing to be reachable from.
+        let graph = Graph::from_input("a: b\nb: out").unwrap();
+        assert_eq!(graph.validate(), []);
+    }
+
+    // The complete graph of `n` nodes named "n0" to "n{n-1}".
+    fn complete_graph(n: usize) -> String {
+        let mut input = String::new();
+        for a in 0..n {
+            for b in (a + 1)..n {
+                input += &format!("n{} - n{}\n", a, b);
+            }
+        }
+        return input;
+    }
+
+    // A `width` x `height` grid with the corners "you" and "out".
+    fn grid_graph(width: usize, height: usize) -> String {
+        let name = |x: usize, y: usize| {
+            if (x, y) == (0, 0) {
+                return "you".to_string();
+            }
+            if (x, y) == (width - 1, height - 1) {
+                return "out".to_string();
+            }
+            return format!("g{}_{}", x, y);
+        };
+        let mut input = String::new();
+        for y in 0..height {
+            for x in 0..width {
+                if x + 1 < width {
+                    input += &format!("{} - {}\n", name(x, y), name(x + 1, y));
+                }
+                if y + 1 < height {
+                    input += &format!("{} - {}\n", name(x, y), name(x, y + 1));
+                }
+            }
+        }
+        return input;
+    }
+
+    #[test]
+    fn test_undirected() {
+        let graph = Graph::parse("you - a\na - out\nyou - out", false).unwrap();
+        assert!(graph.undirected);
+        assert_eq!(graph.count_all_paths().unwrap(), PathCount::Finite(2));
+        assert_eq!(
+            graph.count_paths("out", "you").unwrap(),
+            PathCount::Finite(2)
+        );
+        assert_eq!(
+            graph.count_paths_via("you", "out", &["a"]).unwrap(),
+            PathCount::Finite(1)
+        );
+        assert_eq!(
+            graph.count_paths("you", "bogus").unwrap(),
+            PathCount::Finite(0)
+        );
+        assert!(matches!(
+            graph.count_paths_via("you", "out", &["bogus"]),
+            Err(Error::MissingNode(node)) if node == "bogus"
+        ));
+        assert_eq!(graph.validate(), []);
+
+        // Forced, and then every line has to be an edge.
+        assert!(!Graph::parse("you: out", false).unwrap().undirected);
+        assert!(matches!(
+            Graph::parse("you: out", true),
+            Err(Error::InvalidInput { line_no: 1, .. })
+        ));
+
+        // Between two nodes of the complete graph of 6 nodes, a path passes through 0 to 4 of the
+        // other 4 nodes in any order: 1 + 4 + 12 + 24 + 24.
+        let graph = Graph::parse(&complete_graph(6), false).unwrap();
+        assert_eq!(
+            graph.count_paths("n0", "n5").unwrap(),
+            PathCount::Finite(65)
+        );
+
+        // Compare to enumerating the paths, with and without waypoints.
+        for input in [complete_graph(6), grid_graph(3, 3), grid_graph(4, 3)] {
+            let graph = Graph::parse(&input, false).unwrap();
+            for (from, to) in [("n0", "n5"), ("you", "out"), ("g1_0", "g0_2")] {
+                let paths: Vec<Vec<String>> = graph.paths_iter(from, to).collect();
+                assert_eq!(
+                    graph.count_paths(from, to).unwrap(),
+                    PathCount::Finite(paths.len() as u128),
+                    "{} to {}",
+                    from,
+                    to
+                );
+                for waypoint in ["n3", "g1_1", "g2_0"] {
+                    if graph.index(waypoint).is_none() || graph.index(from).is_none() {
+                        continue;
+                    }
+                    let through = paths
+                        .iter()
+                        .filter(|path| path.iter().any(|node| node == waypoint))
+                        .count();
+                    assert_eq!(
+                        graph.count_paths_via(from, to, &[waypoint]).unwrap(),
+                        PathCount::Finite(through as u128)
+                    );
+                }
+            }
+        }
+        let graph = Graph::parse(&grid_graph(3, 3), false).unwrap();
+        assert_eq!(graph.count_all_paths().unwrap(), PathCount::Finite(12));
+
+        // Too many paths to count, even though the graphs are small. A long chain has a single
+        // path, no matter how many nodes.
+        for graph in [grid_graph(7, 7), complete_graph(20)] {
+            let graph = Graph::parse(&graph, false).unwrap();
+            let (from, to) = (graph.name(0), graph.name(graph.names.len() as u32 - 1));
+            assert!(matches!(
+                graph.count_paths(from, to),
+                Err(Error::TooManySteps {
+                    limit: MAX_SIMPLE_PATH_STEPS
+                })
+            ));
+        }
+        let input: String = (0..100_000)
+            .map(|i| format!("n{} - n{}\n", i, i + 1))
+            .collect();
+        let graph = Graph::parse(&input, false).unwrap();
+        assert_eq!(
+            graph.count_paths("n0", "n100000").unwrap(),
+            PathCount::Finite(1)
+        );
+    }
+
+    #[test]
+    fn test_count_paths_pairs() {
+        let graph = Graph::from_input(&layered_dag(20, 12)).unwrap();
+        let pairs = layered_pairs(200, 20, 12, 6, 11);
+        let pairs: Vec<(&str, &str)> = pairs
+            .iter()
+            .map(|(from, to)| (from.as_str(), to.as_str()))
+            .collect();
+        let serial = graph.count_paths_pairs(&pairs).unwrap();
+        for ((from, to), count) in pairs.iter().zip(&serial) {
+            assert_eq!(graph.count_paths(from, to).unwrap(), *count);
+        }
+        assert!(serial.contains(&PathCount::Finite(0)));
+        assert!(
+            serial
+                .iter()
+                .any(|count| matches!(count, PathCount::Finite(paths) if *paths > 1))
+        );
+        #[cfg(feature = "parallel")]
+        assert_eq!(graph.count_paths_pairs_parallel(&pairs).unwrap(), serial);
+
+        let graph = Graph::from_input_with_cycles("you: a\na: b\nb: a out\nc: out").unwrap();
+        let pairs = [
+            ("you", "out"),
+            ("c", "out"),
+            ("bogus", "out"),
+            ("out", "you"),
+        ];
+        let expected = [
+            PathCount::Infinite,
+            PathCount::Finite(1),
+            PathCount::Finite(0),
+            PathCount::Finite(0),
+        ];
+        assert_eq!(graph.count_paths_pairs(&pairs).unwrap(), expected);
+        #[cfg(feature = "parallel")]
+        assert_eq!(graph.count_paths_pairs_parallel(&pairs).unwrap(), expected);
+
+        // Part 2 as the sum of its two roads.
+        let graph = Graph::from_input_with_cycles(include_str!("../rsc/sample2.txt")).unwrap();
+        assert_eq!(graph.count_svr_paths().unwrap(), PathCount::Finite(2));
+        let input = include_str!("../rsc/sample2.txt").replace("fff: ggg", "fff: eee ggg");
+        let graph = Graph::from_input_with_cycles(&input).unwrap();
+        assert_eq!(graph.count_svr_paths().unwrap(), PathCount::Infinite);
+        let graph = Graph::from_input("svr: dac fft\ndac: out\nfft: out").unwrap();
+        assert_eq!(graph.count_svr_paths().unwrap(), PathCount::Finite(0));
+        let graph = Graph::from_input("svr: dac\ndac: out").unwrap();
+        assert!(matches!(
+            graph.count_svr_paths(),
+            Err(Error::MissingNode(node)) if node == "fft"
+        ));
+    }
+}
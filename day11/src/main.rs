@@ -1,128 +1,427 @@
-use std::collections::{HashMap, HashSet};
+use aoc::{Error, Graph, PART2_WAYPOINTS, PathCount};
+#[cfg(feature = "bigint")]
+use num_bigint::BigUint;
 use std::time::Instant;
 
-#[derive(Debug)]
-enum Error {
-    #[allow(dead_code)]
-    InvalidInput(String),
+// Which paths to count instead of solving the puzzle, given with `--from`, `--to` and `--via`.
+struct Query {
+    from: String,
+    to: String,
+    // The paths have to pass through all of these, in any order.
+    via: Vec<String>,
+}
 
-    #[allow(dead_code)]
-    MissingNode(String),
+impl Query {
+    // The paths of part 1.
+    fn part1() -> Query {
+        return Query {
+            from: "you".to_string(),
+            to: "out".to_string(),
+            via: Vec::new(),
+        };
+    }
 }
 
-struct Graph {
-    connections: HashMap<String, Vec<String>>,
+struct Options {
+    query: Option<Query>,
+    // Print this many paths of the query, or of part 1 without one, instead of counting them.
+    list_paths: Option<usize>,
+    // Print what `Graph::validate` finds before solving.
+    lint: bool,
+    // Like `lint`, but fail instead of solving if anything was found.
+    strict: bool,
+    // Print the nodes the most paths of the query, or of part 1 without one, pass through.
+    bottlenecks: bool,
+    // Count the paths of the query, or of part 1 without one, via the nodes all of them pass
+    // through instead of its `--via`.
+    mandatory: bool,
+    // Write the graph in Graphviz' DOT format to this path.
+    dot: Option<String>,
+    // Read the input as undirected edges like "a - b", even if it doesn't look like that.
+    undirected: bool,
 }
 
-impl Graph {
-    fn from_input(input: &str) -> Result<Graph, Error> {
-        let mut connections = HashMap::new();
-        for line in input.trim().lines() {
-            let (node, raw_targets) = line
-                .split_once(':')
-                .ok_or(Error::InvalidInput(line.to_string()))?;
-
-            let targets: Vec<String> = raw_targets
-                .trim()
-                .split(' ')
-                .map(|s| s.to_string())
-                .collect();
-
-            connections.insert(node.to_string(), targets);
-        }
-        return Ok(Graph { connections });
-    }
-
-    fn count_all_paths(&self) -> usize {
-        let mut cache = HashMap::new();
-        return self.follow_path("you", "out", &HashSet::new(), &mut cache);
-    }
-
-    fn count_svr_paths(&self) -> usize {
-        // It works like this: each path must pass through "dac" AND "fft". Since this is a
-        // directed graph, we can simple trace partial paths and multiply those intermediate
-        // results.
-        // I'm going to call each of the two possibilities a "road" (svr -> dac -> fft -> out
-        // and svr -> fft -> dac -> out).
-        let mut cache = HashMap::new();
-        let road1_part1 = self.follow_path("svr", "dac", &HashSet::new(), &mut cache);
-        let road1_part2 = self.follow_path("dac", "fft", &HashSet::new(), &mut cache);
-        let road1_part3 = self.follow_path("fft", "out", &HashSet::new(), &mut cache);
-
-        let road2_part1 = self.follow_path("svr", "fft", &HashSet::new(), &mut cache);
-        let road2_part2 = self.follow_path("fft", "dac", &HashSet::new(), &mut cache);
-        let road2_part3 = self.follow_path("dac", "out", &HashSet::new(), &mut cache);
-
-        return (road1_part1 * road1_part2 * road1_part3)
-            + (road2_part1 * road2_part2 * road2_part3);
-    }
-
-    fn follow_path(
-        &self,
-        node: &str,
-        target: &str,
-        visited: &HashSet<&str>,
-        cache: &mut HashMap<(String, String), usize>,
-    ) -> usize {
-        if node == target {
-            return 1;
-        }
+impl Options {
+    fn from_args() -> Result<Options, Error> {
+        return Options::parse(std::env::args().skip(1));
+    }
 
-        let cache_key = (node.to_string(), target.to_string());
-        if let Some(count) = cache.get(&cache_key) {
-            return *count;
+    // Any of `--from`, `--to` and `--via` makes it a query, the others default to the endpoints
+    // of part 1 without waypoints.
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, Error> {
+        let mut options = Options {
+            query: None,
+            list_paths: None,
+            lint: false,
+            strict: false,
+            bottlenecks: false,
+            mandatory: false,
+            dot: None,
+            undirected: false,
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--lint" => {
+                    options.lint = true;
+                    continue;
+                }
+                "--strict" => {
+                    options.strict = true;
+                    continue;
+                }
+                "--bottlenecks" => {
+                    options.bottlenecks = true;
+                    continue;
+                }
+                "--mandatory" => {
+                    options.mandatory = true;
+                    continue;
+                }
+                "--undirected" => {
+                    options.undirected = true;
+                    continue;
+                }
+                _ => {}
+            }
+            if !["--from", "--to", "--via", "--list-paths", "--dot"].contains(&arg.as_str()) {
+                return Err(Error::InvalidArgument(arg));
+            }
+            let value = args.next().ok_or(Error::InvalidArgument(arg.clone()))?;
+            if arg == "--dot" {
+                options.dot = Some(value);
+                continue;
+            }
+            if arg == "--list-paths" {
+                let count = value.parse().map_err(|_| Error::InvalidArgument(value))?;
+                options.list_paths = Some(count);
+                continue;
+            }
+            let query = options.query.get_or_insert_with(Query::part1);
+            match arg.as_str() {
+                "--from" => query.from = value,
+                "--to" => query.to = value,
+                _ => query.via = value.split(',').map(|node| node.to_string()).collect(),
+            }
         }
+        return Ok(options);
+    }
+}
 
-        if visited.contains(node) {
-            return 0;
-        }
+fn run_query(graph: &Graph, query: &Query) -> Result<PathCount, Error> {
+    let via: Vec<&str> = query.via.iter().map(|node| node.as_str()).collect();
+    let count = graph.count_paths_via(&query.from, &query.to, &via)?;
+    println!("Paths from {} to {}: {}", query.from, query.to, count);
+    return Ok(count);
+}
 
-        let mut updated_visited = visited.clone();
-        updated_visited.insert(node);
+// Prints the first `count` paths of the query, with the nodes joined by arrows. Returns how many
+// there were. The search only follows nodes between consecutive waypoints, but a path can still
+// visit the waypoints in the wrong order or not at all, so it's checked for them as well.
+fn list_paths(graph: &Graph, query: &Query, count: usize) -> Result<usize, Error> {
+    let from = graph.node(&query.from)?;
+    let to = graph.node(&query.to)?;
+    let mut waypoints = Vec::with_capacity(query.via.len());
+    for waypoint in &query.via {
+        waypoints.push(graph.node(waypoint)?);
+    }
+    let allowed = graph.on_any_path_via(from, to, &waypoints);
+    let paths = graph
+        .paths_iter_within(Some((from, to)), allowed)
+        .filter(|path| query.via.iter().all(|node| path.contains(node)))
+        .take(count);
+    let mut listed = 0;
+    for path in paths {
+        println!("{}", path.join(" -> "));
+        listed += 1;
+    }
+    return Ok(listed);
+}
 
-        match self.connections.get(node) {
-            Some(connections) => {
-                let mut count = 0;
-                for connection in connections {
-                    let recursed_count =
-                        self.follow_path(connection, target, &updated_visited, cache);
-                    count += recursed_count;
-                }
+// How many bottlenecks `--bottlenecks` prints.
+const BOTTLENECK_COUNT: usize = 10;
 
-                cache.insert(cache_key, count);
-                return count;
-            }
-            None => {
-                return 0;
+// Prints the nodes besides the endpoints that the most paths of the query pass through, and
+// which share of all paths that is. Waypoints of the query are ignored. Returns them in that
+// order.
+fn bottlenecks(graph: &Graph, query: &Query) -> Result<Vec<(String, PathCount)>, Error> {
+    let counts = graph.path_through_counts(&query.from, &query.to)?;
+    let total = counts
+        .get(&query.to)
+        .cloned()
+        .unwrap_or(PathCount::Finite(0));
+    let mut nodes: Vec<(String, PathCount)> = counts
+        .into_iter()
+        .filter(|(node, _)| *node != query.from && *node != query.to)
+        .collect();
+    // Infinite counts first.
+    let rank = |count: &PathCount| match count {
+        PathCount::Finite(count) => (false, *count),
+        PathCount::Infinite => (true, 0),
+    };
+    nodes.sort_by(|a, b| rank(&b.1).cmp(&rank(&a.1)).then_with(|| a.0.cmp(&b.0)));
+    nodes.truncate(BOTTLENECK_COUNT);
+    println!("Paths from {} to {}: {}", query.from, query.to, total);
+    for (node, count) in &nodes {
+        match (count, &total) {
+            (PathCount::Finite(count), PathCount::Finite(total)) => {
+                let share = 100.0 * *count as f64 / *total as f64;
+                println!("{}: {} ({:.1}%)", node, count, share);
             }
+            _ => println!("{}: {}", node, count),
         }
     }
+    return Ok(nodes);
+}
+
+// Counts the paths of the query via the nodes all of them pass through, printing those nodes.
+// Warns if there are none, then it's just all paths.
+fn mandatory(graph: &Graph, query: &Query) -> Result<PathCount, Error> {
+    let (count, waypoints) = graph.count_paths_via_mandatory(&query.from, &query.to)?;
+    if waypoints.is_empty() {
+        eprintln!(
+            "Warning: no mandatory waypoints between {} and {}, counting all paths",
+            query.from, query.to
+        );
+    } else {
+        println!("Mandatory waypoints: {}", waypoints.join(", "));
+    }
+    println!("Paths from {} to {}: {}", query.from, query.to, count);
+    return Ok(count);
+}
+
+// Prints what `Graph::validate` finds. With `strict`, that's an error.
+fn lint(graph: &Graph, strict: bool) -> Result<(), Error> {
+    let warnings = graph.validate();
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    if strict && !warnings.is_empty() {
+        return Err(Error::ValidationFailed(warnings));
+    }
+    return Ok(());
 }
 
-fn part1(input: &str) -> Result<(), Error> {
-    let graph = Graph::from_input(input)?;
-    let count = graph.count_all_paths();
+fn part1(graph: &Graph) -> Result<(), Error> {
+    #[cfg(feature = "bigint")]
+    let count = graph.count_paths_as::<BigUint>("you", "out")?;
+    #[cfg(not(feature = "bigint"))]
+    let count = graph.count_all_paths()?;
     println!("Part 1: {}", count);
     return Ok(());
 }
 
-fn part2(input: &str) -> Result<(), Error> {
-    let graph = Graph::from_input(input)?;
-    let count = graph.count_svr_paths();
+fn part2(graph: &Graph) -> Result<(), Error> {
+    #[cfg(feature = "bigint")]
+    let count = graph.count_paths_via_as::<BigUint>("svr", "out", &PART2_WAYPOINTS)?;
+    #[cfg(not(feature = "bigint"))]
+    let count = graph.count_svr_paths()?;
     println!("Part 2: {}", count);
     return Ok(());
 }
 
 fn main() -> Result<(), Error> {
     let input = include_str!("../rsc/input.txt");
+    let options = Options::from_args()?;
+    let graph = Graph::parse(input, options.undirected)?;
+
+    if options.lint || options.strict {
+        lint(&graph, options.strict)?;
+    }
+
+    if let Some(path) = &options.dot {
+        let highlight: Vec<&str> = match &options.query {
+            Some(query) => [query.from.as_str(), query.to.as_str()]
+                .into_iter()
+                .chain(query.via.iter().map(|node| node.as_str()))
+                .collect(),
+            None => ["you", "svr", "out"]
+                .into_iter()
+                .chain(PART2_WAYPOINTS)
+                .collect(),
+        };
+        std::fs::write(path, graph.to_dot(&highlight))
+            .map_err(|e| Error::WriteFailed(e.to_string()))?;
+    }
+
+    if let Some(count) = options.list_paths {
+        let query = options.query.unwrap_or_else(Query::part1);
+        list_paths(&graph, &query, count)?;
+        return Ok(());
+    }
+
+    if options.bottlenecks {
+        let query = options.query.unwrap_or_else(Query::part1);
+        bottlenecks(&graph, &query)?;
+        return Ok(());
+    }
+
+    if options.mandatory {
+        let query = options.query.unwrap_or_else(Query::part1);
+        mandatory(&graph, &query)?;
+        return Ok(());
+    }
+
+    if let Some(query) = &options.query {
+        let start = Instant::now();
+        run_query(&graph, query)?;
+        println!("Elapsed: {:.2?}", start.elapsed());
+        return Ok(());
+    }
 
     let start1 = Instant::now();
-    part1(input)?;
+    part1(&graph)?;
     println!("Elapsed: {:.2?}\n", start1.elapsed());
 
     let start2 = Instant::now();
-    part2(input)?;
+    part2(&graph)?;
     println!("Elapsed: {:.2?}", start2.elapsed());
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(args: &[&str]) -> Result<Query, Error> {
+        let options = Options::parse(args.iter().map(|arg| arg.to_string()))?;
+        return Ok(options.query.unwrap());
+    }
+
+    #[test]
+    fn test_query() {
+        let sample1 = Graph::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        let sample2 = Graph::from_input(include_str!("../rsc/sample2.txt")).unwrap();
+        let part1 = query(&["--from", "you", "--to", "out"]).unwrap();
+        assert_eq!(run_query(&sample1, &part1).unwrap(), PathCount::Finite(5));
+        let part2 = query(&["--from", "svr", "--to", "out", "--via", "dac,fft"]).unwrap();
+        assert_eq!(part2.via, ["dac", "fft"]);
+        assert_eq!(run_query(&sample2, &part2).unwrap(), PathCount::Finite(2));
+        let reversed = query(&["--via", "fft,dac", "--from", "svr"]).unwrap();
+        assert_eq!(
+            run_query(&sample2, &reversed).unwrap(),
+            PathCount::Finite(2)
+        );
+
+        let bogus = query(&["--from", "svr", "--via", "dac,bogus"]).unwrap();
+        assert!(matches!(
+            run_query(&sample2, &bogus),
+            Err(Error::MissingNode(node)) if node == "bogus"
+        ));
+        let bogus = query(&["--from", "bogus"]).unwrap();
+        assert!(matches!(
+            run_query(&sample1, &bogus),
+            Err(Error::MissingNode(node)) if node == "bogus"
+        ));
+
+        // Cycles on the way make it infinite.
+        let graph = Graph::from_input_with_cycles("e: f\nf: e a\na: out").unwrap();
+        assert_eq!(
+            run_query(&graph, &query(&["--from", "f"]).unwrap()).unwrap(),
+            PathCount::Infinite
+        );
+
+        assert!(Options::parse(std::iter::empty()).unwrap().query.is_none());
+        assert!(matches!(
+            query(&["--from"]),
+            Err(Error::InvalidArgument(arg)) if arg == "--from"
+        ));
+        assert!(matches!(
+            query(&["--bogus", "x"]),
+            Err(Error::InvalidArgument(arg)) if arg == "--bogus"
+        ));
+    }
+
+    #[test]
+    fn test_options() {
+        let options = Options::parse(
+            ["--lint", "--from", "a", "--strict"]
+                .iter()
+                .map(|arg| arg.to_string()),
+        )
+        .unwrap();
+        assert!(options.lint && options.strict);
+        assert_eq!(options.query.unwrap().from, "a");
+
+        let options = Options::parse(["--bottlenecks"].iter().map(|arg| arg.to_string())).unwrap();
+        assert!(options.bottlenecks && options.query.is_none());
+        let options = Options::parse(["--dot", "graph.dot"].iter().map(|arg| arg.to_string()));
+        assert_eq!(options.unwrap().dot.as_deref(), Some("graph.dot"));
+        let options = Options::parse(["--undirected"].iter().map(|arg| arg.to_string())).unwrap();
+        assert!(options.undirected);
+
+        let options = Options::parse(
+            ["--list-paths", "10", "--from", "svr", "--via", "dac,fft"]
+                .iter()
+                .map(|arg| arg.to_string()),
+        )
+        .unwrap();
+        assert_eq!(options.list_paths, Some(10));
+        assert_eq!(options.query.unwrap().via, ["dac", "fft"]);
+        assert!(matches!(
+            Options::parse(["--list-paths", "x"].iter().map(|arg| arg.to_string())),
+            Err(Error::InvalidArgument(arg)) if arg == "x"
+        ));
+    }
+
+    #[test]
+    fn test_list_paths() {
+        let sample2 = Graph::from_input(include_str!("../rsc/sample2.txt")).unwrap();
+        let via = query(&["--from", "svr", "--via", "dac,fft"]).unwrap();
+        assert_eq!(list_paths(&sample2, &via, 10).unwrap(), 2);
+        assert_eq!(
+            list_paths(&sample2, &query(&["--from", "svr"]).unwrap(), 3).unwrap(),
+            3
+        );
+
+        // The search is limited to the nodes between the waypoints, so the dead end behind "bbb"
+        // isn't visited. Unknown waypoints are an error instead of listing nothing.
+        let graph = Graph::from_input("svr: aaa bbb\naaa: fft\nbbb: ccc\nfft: out").unwrap();
+        let via_fft = query(&["--from", "svr", "--via", "fft"]).unwrap();
+        assert_eq!(list_paths(&graph, &via_fft, 10).unwrap(), 1);
+        assert!(matches!(
+            list_paths(&sample2, &query(&["--from", "svr", "--via", "bogus"]).unwrap(), 10),
+            Err(Error::MissingNode(node)) if node == "bogus"
+        ));
+        assert!(matches!(
+            list_paths(&sample2, &query(&["--from", "nowhere"]).unwrap(), 10),
+            Err(Error::MissingNode(node)) if node == "nowhere"
+        ));
+    }
+
+    #[test]
+    fn test_bottlenecks() {
+        let graph = Graph::from_input(include_str!("../rsc/sample2.txt")).unwrap();
+        let query = query(&["--from", "svr"]).unwrap();
+        let nodes = bottlenecks(&graph, &query).unwrap();
+        assert_eq!(nodes.len(), BOTTLENECK_COUNT);
+        assert_eq!(nodes[0], ("ccc".to_string(), PathCount::Finite(8)));
+        assert_eq!(nodes[2], ("aaa".to_string(), PathCount::Finite(4)));
+    }
+
+    #[test]
+    fn test_mandatory() {
+        let options = Options::parse(["--mandatory"].iter().map(|arg| arg.to_string())).unwrap();
+        assert!(options.mandatory && options.query.is_none());
+        let graph = Graph::from_input(include_str!("../rsc/sample2.txt")).unwrap();
+        let query = query(&["--from", "svr"]).unwrap();
+        assert_eq!(mandatory(&graph, &query).unwrap(), PathCount::Finite(8));
+        let graph = Graph::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        assert_eq!(
+            mandatory(&graph, &Query::part1()).unwrap(),
+            PathCount::Finite(5)
+        );
+    }
+
+    #[test]
+    fn test_lint() {
+        let graph = Graph::from_input("you: a bb\na: out").unwrap();
+        assert!(lint(&graph, false).is_ok());
+        assert!(matches!(
+            lint(&graph, true),
+            Err(Error::ValidationFailed(warnings)) if warnings.len() == 1
+        ));
+        let graph = Graph::from_input(include_str!("../rsc/sample2.txt")).unwrap();
+        assert!(lint(&graph, true).is_ok());
+    }
+}
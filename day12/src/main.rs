@@ -1,3 +1,5 @@
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use std::time::Instant;
 
 #[derive(Debug)]
@@ -15,9 +17,12 @@ enum Error {
 type Shape = [[bool; 3]; 3];
 
 struct Present {
-    // All unique variants of the present, rotated and flipped.
+    // All unique variants of the present, rotated and flipped. Each is shifted to the top-left
+    // corner of its 3x3 box.
     #[allow(dead_code)]
     variants: Vec<Shape>,
+    // Width and height of the occupied part of each variant, in the same order.
+    bounding_boxes: Vec<(usize, usize)>,
     // How many cells are occupied by the present. Used to quickly estimate if a region can fit.
     occupied_cells: usize,
 }
@@ -28,6 +33,15 @@ struct Region {
     presents: Vec<usize>,
 }
 
+// A present placed into a region: the variant's top-left corner is at `x`, `y`.
+#[allow(dead_code)]
+struct Placement {
+    present: usize,
+    variant: usize,
+    x: usize,
+    y: usize,
+}
+
 struct TreeFarm {
     presents: Vec<Present>,
     regions: Vec<Region>,
@@ -145,6 +159,302 @@ impl TreeFarm {
             }
         }
     }
+
+    // How many of the regions can fit all their presents.
+    #[cfg_attr(feature = "parallel", allow(dead_code))]
+    fn count_fitting(&self) -> usize {
+        return self
+            .regions
+            .iter()
+            .filter(|region| self.can_fit(region))
+            .count();
+    }
+
+    // Like `count_fitting`, but checks the regions in parallel. Checking a region only reads the
+    // farm, any packing state belongs to that one region.
+    #[cfg(feature = "parallel")]
+    fn count_fitting_parallel(&self) -> usize {
+        return self
+            .regions
+            .par_iter()
+            .filter(|region| self.can_fit(region))
+            .count();
+    }
+
+    // Tries to place all presents of the region via backtracking. Returns where each present
+    // went, or `None` if they cannot be packed.
+    #[allow(dead_code)]
+    fn pack(&self, region: &Region) -> Option<Vec<Placement>> {
+        return self.pack_with_steps(region).0;
+    }
+
+    #[allow(dead_code)]
+    fn try_pack(&self, region: &Region) -> bool {
+        return self.pack(region).is_some();
+    }
+
+    // Like `pack`, but also returns how many steps the backtracking took: each recursion and each
+    // attempt to place a present counts as one.
+    fn pack_with_steps(&self, region: &Region) -> (Option<Vec<Placement>>, usize) {
+        // One entry per present to place. Identical presents are next to each other, which is
+        // needed to skip permutations of them.
+        let mut pieces = Vec::new();
+        for (present_index, count) in region.presents.iter().enumerate() {
+            for _ in 0..*count {
+                pieces.push(present_index);
+            }
+        }
+
+        let mut grid = vec![false; region.width * region.height];
+        let mut placements = Vec::new();
+        let mut steps = 0;
+        if self.pack_recursive(region, &pieces, &mut grid, &mut placements, &mut steps) {
+            return (Some(placements), steps);
+        }
+        return (None, steps);
+    }
+
+    fn pack_recursive(
+        &self,
+        region: &Region,
+        pieces: &[usize],
+        grid: &mut Vec<bool>,
+        placements: &mut Vec<Placement>,
+        steps: &mut usize,
+    ) -> bool {
+        *steps += 1;
+        let index = placements.len();
+        if index == pieces.len() {
+            return true;
+        }
+
+        // Quick check: is there enough space left at all?
+        let free = grid.iter().filter(|c| !**c).count();
+        let needed: usize = pieces[index..]
+            .iter()
+            .map(|p| self.presents[*p].occupied_cells)
+            .sum();
+        if needed > free {
+            return false;
+        }
+
+        // Identical presents are interchangeable, so only try positions after the previous one
+        // of the same kind.
+        let present_index = pieces[index];
+        let min_key = match placements.last() {
+            Some(last) if last.present == present_index => {
+                Some((last.y * region.width + last.x, last.variant))
+            }
+            _ => None,
+        };
+
+        let present = &self.presents[present_index];
+        for y in 0..region.height {
+            for x in 0..region.width {
+                for (variant_index, variant) in present.variants.iter().enumerate() {
+                    let (variant_width, variant_height) = present.bounding_boxes[variant_index];
+                    if x + variant_width > region.width || y + variant_height > region.height {
+                        continue;
+                    }
+                    let key = (y * region.width + x, variant_index);
+                    if min_key.is_some_and(|min_key| key <= min_key) {
+                        continue;
+                    }
+                    *steps += 1;
+                    if !Present::fits(variant, grid, region.width, x, y) {
+                        continue;
+                    }
+
+                    Present::set(variant, grid, region.width, x, y, true);
+                    placements.push(Placement {
+                        present: present_index,
+                        variant: variant_index,
+                        x,
+                        y,
+                    });
+                    if self.pack_recursive(region, pieces, grid, placements, steps) {
+                        return true;
+                    }
+                    placements.pop();
+                    Present::set(variant, grid, region.width, x, y, false);
+                }
+            }
+        }
+
+        return false;
+    }
+
+    // Like `try_pack`, but instead of trying every position for every present in input order, it
+    // always fills the top-left-most empty cell, trying the largest presents first. A present
+    // covering that cell must have its own top-left-most cell there, so there are only a few
+    // candidates per step. The cell may also stay empty.
+    #[allow(dead_code)]
+    fn try_pack_heuristic(&self, region: &Region) -> bool {
+        return self.try_pack_heuristic_with_steps(region).0;
+    }
+
+    fn try_pack_heuristic_with_steps(&self, region: &Region) -> (bool, usize) {
+        let mut remaining = region.presents.clone();
+        let needed: usize = remaining
+            .iter()
+            .enumerate()
+            .map(|(index, count)| self.presents[index].occupied_cells * count)
+            .sum();
+        if needed == 0 {
+            return (true, 0);
+        }
+
+        let mut order: Vec<usize> = (0..remaining.len()).collect();
+        order.sort_by_key(|index| std::cmp::Reverse(self.presents[*index].occupied_cells));
+
+        // Cells that are either occupied or deliberately left empty.
+        let mut grid = vec![false; region.width * region.height];
+        let mut steps = 0;
+        let fits = self.pack_heuristic_recursive(
+            region,
+            &order,
+            &mut remaining,
+            needed,
+            &mut grid,
+            &mut steps,
+        );
+        return (fits, steps);
+    }
+
+    fn pack_heuristic_recursive(
+        &self,
+        region: &Region,
+        order: &[usize],
+        remaining: &mut Vec<usize>,
+        needed: usize,
+        grid: &mut Vec<bool>,
+        steps: &mut usize,
+    ) -> bool {
+        *steps += 1;
+        if needed == 0 {
+            return true;
+        }
+
+        let Some(cell) = grid.iter().position(|c| !*c) else {
+            return false;
+        };
+        let free = grid[cell..].iter().filter(|c| !**c).count();
+        if needed > free {
+            return false;
+        }
+
+        let (cell_x, cell_y) = (cell % region.width, cell / region.width);
+        for &present_index in order {
+            if remaining[present_index] == 0 {
+                continue;
+            }
+            let present = &self.presents[present_index];
+            for (variant, (variant_width, variant_height)) in
+                present.variants.iter().zip(&present.bounding_boxes)
+            {
+                let Some((offset_x, offset_y)) = Present::first_cell(variant) else {
+                    continue;
+                };
+                if cell_x < offset_x || cell_y < offset_y {
+                    continue;
+                }
+                let (x, y) = (cell_x - offset_x, cell_y - offset_y);
+                if x + variant_width > region.width || y + variant_height > region.height {
+                    continue;
+                }
+                *steps += 1;
+                if !Present::fits(variant, grid, region.width, x, y) {
+                    continue;
+                }
+
+                Present::set(variant, grid, region.width, x, y, true);
+                remaining[present_index] -= 1;
+                let fits = self.pack_heuristic_recursive(
+                    region,
+                    order,
+                    remaining,
+                    needed - present.occupied_cells,
+                    grid,
+                    steps,
+                );
+                remaining[present_index] += 1;
+                Present::set(variant, grid, region.width, x, y, false);
+                if fits {
+                    return true;
+                }
+            }
+        }
+
+        // Leave the cell empty.
+        grid[cell] = true;
+        let fits = self.pack_heuristic_recursive(region, order, remaining, needed, grid, steps);
+        grid[cell] = false;
+        return fits;
+    }
+
+    // How much of the region is covered by presents once they're packed, or `None` if they
+    // cannot be packed.
+    #[allow(dead_code)]
+    fn packing_density(&self, region: &Region) -> Option<f64> {
+        let placements = self.pack(region)?;
+        let occupied: usize = placements
+            .iter()
+            .map(|p| self.presents[p.present].occupied_cells)
+            .sum();
+        return Some(occupied as f64 / (region.width * region.height) as f64);
+    }
+
+    // The smallest region, as width and height, that can hold all the given presents. A present
+    // may be listed more than once. The areas are tried in increasing order, starting with the
+    // cells the presents occupy. Each present fits into 3x3, so lining them up in a row of 3x3
+    // boxes always works and bounds the search. Among regions of the same area, the most square
+    // one wins, and the width is never less than the height. `None` for unknown presents.
+    #[allow(dead_code)]
+    fn minimal_region(&self, present_indices: &[usize]) -> Option<(usize, usize)> {
+        if present_indices.is_empty() {
+            return Some((0, 0));
+        }
+        let mut presents = vec![0; self.presents.len()];
+        for index in present_indices {
+            *presents.get_mut(*index)? += 1;
+        }
+        let occupied: usize = present_indices
+            .iter()
+            .map(|index| self.presents[*index].occupied_cells)
+            .sum();
+
+        for area in occupied..=(9 * present_indices.len()) {
+            for height in (1..=area.isqrt()).rev() {
+                if area % height != 0 {
+                    continue;
+                }
+                let region = Region {
+                    width: area / height,
+                    height,
+                    presents: presents.clone(),
+                };
+                if self.try_pack_heuristic(&region) {
+                    return Some((region.width, region.height));
+                }
+            }
+        }
+        return None;
+    }
+
+    // Cheap precheck whether the present can be placed at all in an empty region of the given
+    // size, by comparing the bounding boxes of its variants.
+    #[allow(dead_code)]
+    fn present_fits_in(&self, present_index: usize, width: usize, height: usize) -> bool {
+        let Some(present) = self.presents.get(present_index) else {
+            return false;
+        };
+        return present
+            .bounding_boxes
+            .iter()
+            .any(|(variant_width, variant_height)| {
+                *variant_width <= width && *variant_height <= height
+            });
+    }
 }
 
 impl Present {
@@ -169,37 +479,117 @@ impl Present {
                 }
             }
         }
+        if occupied_cells == 0 {
+            // Would count towards the presents to fit without taking any space.
+            return Err(Error::InvalidShape("Empty shape".to_string()));
+        }
 
-        let mut variants = vec![shape];
+        // Variants are compared after shifting them to the top-left corner, as the same shape
+        // at a different spot in the 3x3 box is no different when placing it.
+        let mut variants = Vec::new();
+        let mut add_variant = |variant: &Shape| {
+            let variant = Present::normalize(variant);
+            if !variants.contains(&variant) {
+                variants.push(variant);
+            }
+        };
+        add_variant(&shape);
         let flipped = Present::flip(&shape);
-        if !variants.contains(&flipped.0) {
-            variants.push(flipped.0);
-        }
-        if !variants.contains(&flipped.1) {
-            variants.push(flipped.1);
-        }
+        add_variant(&flipped.0);
+        add_variant(&flipped.1);
 
         for _ in 0..3 {
             let rotated = Present::rotate(&shape);
-            if !variants.contains(&rotated) {
-                variants.push(rotated);
-            }
+            add_variant(&rotated);
             let rotated_flipped = Present::flip(&rotated);
-            if !variants.contains(&rotated_flipped.0) {
-                variants.push(rotated_flipped.0);
-            }
-            if !variants.contains(&rotated_flipped.1) {
-                variants.push(rotated_flipped.1);
-            }
+            add_variant(&rotated_flipped.0);
+            add_variant(&rotated_flipped.1);
             shape = rotated;
         }
 
+        let bounding_boxes = variants.iter().map(Present::bounding_box).collect();
         Ok(Present {
             variants: variants,
+            bounding_boxes,
             occupied_cells,
         })
     }
 
+    // Whether the shape can be placed with its top-left corner at `x`, `y` without overlapping
+    // occupied cells.
+    fn fits(shape: &Shape, grid: &[bool], width: usize, x: usize, y: usize) -> bool {
+        for (dy, row) in shape.iter().enumerate() {
+            for (dx, occupied) in row.iter().enumerate() {
+                if *occupied && grid[(y + dy) * width + x + dx] {
+                    return false;
+                }
+            }
+        }
+        return true;
+    }
+
+    // The top-left-most occupied cell of the shape, as `x`, `y`.
+    fn first_cell(shape: &Shape) -> Option<(usize, usize)> {
+        for (y, row) in shape.iter().enumerate() {
+            for (x, occupied) in row.iter().enumerate() {
+                if *occupied {
+                    return Some((x, y));
+                }
+            }
+        }
+        return None;
+    }
+
+    // Width and height of the occupied part of the shape.
+    fn bounding_box(shape: &Shape) -> (usize, usize) {
+        let mut columns = [false; 3];
+        let mut rows = [false; 3];
+        for (y, row) in shape.iter().enumerate() {
+            for (x, occupied) in row.iter().enumerate() {
+                if *occupied {
+                    columns[x] = true;
+                    rows[y] = true;
+                }
+            }
+        }
+        let extent = |used: &[bool; 3]| match (
+            used.iter().position(|u| *u),
+            used.iter().rposition(|u| *u),
+        ) {
+            (Some(first), Some(last)) => last - first + 1,
+            _ => 0,
+        };
+        return (extent(&columns), extent(&rows));
+    }
+
+    // Shifts the shape up and left until its first row and first column are occupied.
+    fn normalize(shape: &Shape) -> Shape {
+        let top = shape
+            .iter()
+            .position(|row| row.iter().any(|c| *c))
+            .unwrap_or(0);
+        let left = (0..3)
+            .find(|x| shape.iter().any(|row| row[*x]))
+            .unwrap_or(0);
+        let mut normalized = [[false; 3]; 3];
+        for y in top..3 {
+            for x in left..3 {
+                normalized[y - top][x - left] = shape[y][x];
+            }
+        }
+        return normalized;
+    }
+
+    fn set(shape: &Shape, grid: &mut [bool], width: usize, x: usize, y: usize, value: bool) {
+        for (dy, row) in shape.iter().enumerate() {
+            for (dx, occupied) in row.iter().enumerate() {
+                if *occupied {
+                    grid[(y + dy) * width + x + dx] = value;
+                }
+            }
+        }
+    }
+
     fn rotate(shape: &Shape) -> Shape {
         let mut rotated = [[false; 3]; 3];
 
@@ -273,12 +663,10 @@ impl Region {
 
 fn part1(input: &str) -> Result<(), Error> {
     let tree_farm = TreeFarm::from_input(input)?;
-    let mut count = 0;
-    for region in &tree_farm.regions {
-        if tree_farm.can_fit(region) {
-            count += 1;
-        }
-    }
+    #[cfg(feature = "parallel")]
+    let count = tree_farm.count_fitting_parallel();
+    #[cfg(not(feature = "parallel"))]
+    let count = tree_farm.count_fitting();
     println!("Part 1: {}", count);
     return Ok(());
 }
@@ -292,3 +680,233 @@ fn main() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packing_density() {
+        let tree_farm = TreeFarm::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+
+        // Two presents with 7 cells each in a 4x4 region.
+        let density = tree_farm.packing_density(&tree_farm.regions[0]).unwrap();
+        assert!((density - 14.0 / 16.0).abs() < 1e-9);
+
+        // Two of them do not fit into 3x5.
+        let region = Region::from_input("3x5: 0 0 0 0 2 0").unwrap();
+        assert!(tree_farm.packing_density(&region).is_none());
+    }
+
+    #[test]
+    fn test_try_pack_heuristic() {
+        let tree_farm = TreeFarm::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+
+        // The third region of the sample takes too long for a test.
+        let region = &tree_farm.regions[1];
+        let (naive, naive_steps) = tree_farm.pack_with_steps(region);
+        let (heuristic, heuristic_steps) = tree_farm.try_pack_heuristic_with_steps(region);
+        assert!(naive.is_some());
+        assert!(heuristic);
+        assert!(
+            heuristic_steps < naive_steps,
+            "{} >= {}",
+            heuristic_steps,
+            naive_steps
+        );
+
+        let regions = [
+            &tree_farm.regions[0],
+            &Region::from_input("3x5: 0 0 0 0 2 0").unwrap(),
+            &Region::from_input("6x6: 1 1 1 1 0 0").unwrap(),
+            &Region::from_input("2x2: 0 0 0 0 0 0").unwrap(),
+        ];
+        for region in regions {
+            assert_eq!(
+                tree_farm.try_pack_heuristic(region),
+                tree_farm.try_pack(region)
+            );
+        }
+
+        // Shapes smaller than 3x3 right against the edges, both packers have to agree.
+        let tree_farm =
+            TreeFarm::from_input("0:\n...\n.##\n.##\n\n1:\n#..\n#..\n#..\n\n2x2: 1 0").unwrap();
+        assert!(tree_farm.try_pack_heuristic(&tree_farm.regions[0]));
+        for (region, expected) in [
+            ("4x2: 2 0", true),
+            ("3x2: 2 0", false),
+            ("3x1: 0 1", true),
+            ("5x2: 1 2", true),
+            ("4x3: 1 2", true),
+            ("2x2: 0 1", false),
+        ] {
+            let region = Region::from_input(region).unwrap();
+            assert_eq!(tree_farm.try_pack_heuristic(&region), expected);
+            assert_eq!(tree_farm.try_pack(&region), expected);
+        }
+    }
+
+    #[test]
+    fn test_present_fits_in() {
+        let tree_farm = TreeFarm::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        assert!(tree_farm.present_fits_in(0, 3, 3));
+        assert!(!tree_farm.present_fits_in(0, 5, 2));
+        assert!(!tree_farm.present_fits_in(6, 10, 10));
+
+        // A bar that's three tall fits lying down, but not in a 2x2 region.
+        let tree_farm = TreeFarm::from_input("0:\n#..\n#..\n#..\n\n3x1: 1").unwrap();
+        assert!(tree_farm.present_fits_in(0, 1, 3));
+        assert!(tree_farm.present_fits_in(0, 3, 1));
+        assert!(!tree_farm.present_fits_in(0, 2, 2));
+    }
+
+    #[test]
+    fn test_pack_small_shapes() {
+        // Shapes smaller than 3x3 may be placed right against the bottom and right edges.
+        let tree_farm = TreeFarm::from_input("0:\n...\n.##\n.##\n\n2x2: 1").unwrap();
+        assert_eq!(
+            tree_farm.presents[0].variants,
+            vec![[[true, true, false], [true, true, false], [false; 3]]]
+        );
+        let placements = tree_farm.pack(&tree_farm.regions[0]).unwrap();
+        assert_eq!((placements[0].x, placements[0].y), (0, 0));
+
+        let region = Region::from_input("4x2: 2").unwrap();
+        let placements = tree_farm.pack(&region).unwrap();
+        let positions: Vec<_> = placements.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(positions, vec![(0, 0), (2, 0)]);
+        assert!(!tree_farm.try_pack(&Region::from_input("3x2: 2").unwrap()));
+
+        // A bar lying down fits into a single row, standing up into a single column.
+        let tree_farm = TreeFarm::from_input("0:\n..#\n..#\n..#\n\n3x1: 1").unwrap();
+        assert!(tree_farm.try_pack(&tree_farm.regions[0]));
+        assert!(tree_farm.try_pack(&Region::from_input("2x3: 2").unwrap()));
+        assert!(!tree_farm.try_pack(&Region::from_input("2x2: 1").unwrap()));
+    }
+
+    #[test]
+    fn test_minimal_region() {
+        let tree_farm = TreeFarm::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+
+        // Like the first region of the sample, 3x5 would have enough cells but they don't fit.
+        assert_eq!(tree_farm.minimal_region(&[4, 4]), Some((4, 4)));
+        let region = Region::from_input("4x4: 0 0 0 0 2 0").unwrap();
+        assert!(tree_farm.try_pack(&region));
+
+        assert_eq!(tree_farm.minimal_region(&[0]), Some((3, 3)));
+        assert_eq!(tree_farm.minimal_region(&[]), Some((0, 0)));
+        assert_eq!(tree_farm.minimal_region(&[0, 6]), None);
+
+        // Full blocks can only be put side by side, and among equal areas the squarest wins.
+        let tree_farm = TreeFarm::from_input("0:\n###\n###\n###\n\n3x3: 1").unwrap();
+        assert_eq!(tree_farm.minimal_region(&[0, 0]), Some((6, 3)));
+        assert_eq!(tree_farm.minimal_region(&[0, 0, 0]), Some((9, 3)));
+        assert_eq!(tree_farm.minimal_region(&[0, 0, 0, 0]), Some((6, 6)));
+
+        // Small presents don't need a side of 3.
+        let tree_farm =
+            TreeFarm::from_input("0:\n###\n...\n...\n\n1:\n...\n.##\n.##\n\n3x1: 1 0").unwrap();
+        assert_eq!(tree_farm.minimal_region(&[0]), Some((3, 1)));
+        assert_eq!(tree_farm.minimal_region(&[0, 0]), Some((3, 2)));
+        assert_eq!(tree_farm.minimal_region(&[1, 1]), Some((4, 2)));
+        // Side by side would take 5x2, but the square also fits below the bar.
+        assert_eq!(tree_farm.minimal_region(&[0, 1]), Some((3, 3)));
+    }
+
+    // All distinct rotations and reflections of the shape, by mapping each cell through the
+    // eight symmetries of the square: mirrored or not, then rotated zero to three times. Like the
+    // variants, they're shifted to the top-left corner.
+    fn brute_force_variants(shape: &Shape) -> Vec<Shape> {
+        let mut variants: Vec<Shape> = Vec::new();
+        for symmetry in 0..8 {
+            let mut variant = [[false; 3]; 3];
+            for (y, row) in shape.iter().enumerate() {
+                for (x, occupied) in row.iter().enumerate() {
+                    let (mut new_x, mut new_y) = if symmetry >= 4 { (2 - x, y) } else { (x, y) };
+                    for _ in 0..symmetry % 4 {
+                        (new_x, new_y) = (2 - new_y, new_x);
+                    }
+                    variant[new_y][new_x] = *occupied;
+                }
+            }
+            let variant = Present::normalize(&variant);
+            if !variants.contains(&variant) {
+                variants.push(variant);
+            }
+        }
+        return variants;
+    }
+
+    #[test]
+    fn test_variants() {
+        for (lines, expected_count) in [
+            // L
+            (["#..", "#..", "##."], 8),
+            // S and Z, one turned upside down is the same shape one row lower.
+            ([".##", "##.", "..."], 4),
+            (["##.", ".##", "..."], 4),
+            // Plus, only has a single variant.
+            ([".#.", "###", ".#."], 1),
+            // T
+            (["###", ".#.", "..."], 4),
+            // Full diagonal
+            (["#..", ".#.", "..#"], 2),
+        ] {
+            let present = Present::from_input(&lines).unwrap();
+            let mut expected = brute_force_variants(&present.variants[0]);
+            assert_eq!(expected.len(), expected_count, "{:?}", lines);
+
+            let mut variants = present.variants.clone();
+            variants.sort();
+            variants.dedup();
+            assert_eq!(variants.len(), present.variants.len(), "{:?}", lines);
+            expected.sort();
+            assert_eq!(variants, expected, "{:?}", lines);
+        }
+
+        let tree_farm = TreeFarm::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        for present in &tree_farm.presents {
+            let mut expected = brute_force_variants(&present.variants[0]);
+            let mut variants = present.variants.clone();
+            expected.sort();
+            variants.sort();
+            assert_eq!(variants, expected);
+        }
+    }
+
+    #[test]
+    fn test_empty_present() {
+        assert!(matches!(
+            Present::from_input(&["...", "...", "..."]),
+            Err(Error::InvalidShape(_))
+        ));
+        assert!(Present::from_input(&["...", ".#.", "..."]).is_ok());
+
+        let input = "0:\n...\n...\n...\n\n4x4: 1\n";
+        assert!(matches!(
+            TreeFarm::from_input(input),
+            Err(Error::InvalidShape(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn test_count_fitting_parallel() {
+        // The presents of the sample with lots of regions that either clearly fit or clearly
+        // don't, so the count doesn't depend on the missing closer investigation.
+        let sample = include_str!("../rsc/sample1.txt");
+        let presents_end = sample.find("4x4").unwrap();
+        let mut input = sample[..presents_end].to_string();
+        for i in 0..500 {
+            let size = 3 + i % 20;
+            let count = i % 7;
+            input += &format!("{}x{}: {} 0 {} 0 0 0\n", size, size, count, count / 2);
+        }
+        let tree_farm = TreeFarm::from_input(&input).unwrap();
+        assert_eq!(tree_farm.regions.len(), 500);
+
+        let serial = tree_farm.count_fitting();
+        assert!(serial > 0 && serial < 500, "{}", serial);
+        assert_eq!(tree_farm.count_fitting_parallel(), serial);
+    }
+}
@@ -0,0 +1,20 @@
+use aoc::{generate_invalid_in_range, invalid_values};
+use criterion::{Criterion, criterion_group, criterion_main};
+
+// Run with `cargo bench`. That both approaches give the same values is checked by
+// `test_generate_matches_brute_force`.
+fn bench_invalid(c: &mut Criterion) {
+    let range = 1..=100_000_000;
+    let mut group = c.benchmark_group("invalid");
+    group.sample_size(10);
+    group.bench_function("brute force", |b| {
+        b.iter(|| invalid_values(&range, 2, u64::MAX))
+    });
+    group.bench_function("generate", |b| {
+        b.iter(|| generate_invalid_in_range(&range, 2, u64::MAX))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_invalid);
+criterion_main!(benches);
@@ -0,0 +1,177 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::{RangeInclusive, Rem};
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidRange(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidRange(input) => write!(f, "Invalid range: {}", input),
+        }
+    }
+}
+
+pub fn parse_range(input: &str) -> Result<RangeInclusive<u64>, Error> {
+    let (left, right) = input
+        .split_once('-')
+        .ok_or(Error::InvalidRange(input.to_string()))?;
+    let left = left
+        .parse::<u64>()
+        .map_err(|_| Error::InvalidRange(input.to_string()))?;
+    let right = right
+        .parse::<u64>()
+        .map_err(|_| Error::InvalidRange(input.to_string()))?;
+    Ok(left..=right)
+}
+
+pub fn parse_ranges(input: &str) -> Result<Vec<RangeInclusive<u64>>, Error> {
+    return input.trim().split(',').map(parse_range).collect();
+}
+
+// Brute force: checks every value in the range.
+pub fn invalid_values(
+    range: &RangeInclusive<u64>,
+    min_repetitions: u64,
+    max_repetitions: u64,
+) -> Vec<u64> {
+    let mut values = Vec::new();
+    for value in range.clone() {
+        if is_invalid_value(value, min_repetitions, max_repetitions) {
+            values.push(value);
+        }
+    }
+    return values;
+}
+
+pub fn is_invalid_value(value: u64, min_repetitions: u64, max_repetitions: u64) -> bool {
+    let digits = ((value as f64).log10().floor() + 1.0) as u64;
+    if digits < 2 {
+        return false;
+    }
+
+    for i in 1..(digits / 2 + 1) {
+        if digits.rem(i) != 0 {
+            // Only need to consider patterns of lengths that evenly divide the number of digits.
+            continue;
+        }
+
+        let repetitions = digits / i;
+        if repetitions < min_repetitions || repetitions > max_repetitions {
+            continue;
+        }
+
+        let pattern = value / 10u64.pow((digits - i) as u32);
+        let multiplicator = 10u64.pow(i as u32);
+        let mut candidate = 0;
+        for _ in 0..repetitions {
+            candidate *= multiplicator;
+            candidate += pattern;
+        }
+        if candidate == value {
+            return true;
+        }
+    }
+    false
+}
+
+// Same result as `invalid_values`, but instead of checking every value it generates the invalid
+// ones directly: repeating a pattern of `length` digits `repetitions` times is the same as
+// multiplying it with 1 followed by `repetitions - 1` groups of `length - 1` zeros and a 1, like
+// 123123 = 123 * 1001. So for each number of digits in the range and each way to split it, the
+// invalid values are the multiples of that factor by all patterns of the right length. A value
+// can be made of several patterns (1111 is 1 and 11 repeated), hence the set. Sorted ascending.
+pub fn generate_invalid_in_range(
+    range: &RangeInclusive<u64>,
+    min_repetitions: u64,
+    max_repetitions: u64,
+) -> Vec<u64> {
+    let start = *range.start() as u128;
+    let end = *range.end() as u128;
+    let mut values = BTreeSet::new();
+    for digits in 2..=20u32 {
+        let lowest = 10u128.pow(digits - 1);
+        let highest = 10u128.pow(digits) - 1;
+        if highest < start || lowest > end {
+            continue;
+        }
+
+        for length in 1..=digits / 2 {
+            if digits.rem(length) != 0 {
+                continue;
+            }
+            let repetitions = (digits / length) as u64;
+            if repetitions < min_repetitions || repetitions > max_repetitions {
+                continue;
+            }
+
+            let factor = highest / (10u128.pow(length) - 1);
+            let first = 10u128.pow(length - 1).max(start.div_ceil(factor));
+            let last = (10u128.pow(length) - 1).min(end / factor);
+            for pattern in first..=last {
+                values.insert((pattern * factor) as u64);
+            }
+        }
+    }
+    return values.into_iter().collect();
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    const SAMPLE: &str = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,\
+        1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,\
+        2121212118-2121212124";
+
+    #[test]
+    fn test_is_invalid_value() {
+        assert!(is_invalid_value(1010, 2, 2));
+        assert!(!is_invalid_value(1011, 2, 2));
+        assert!(is_invalid_value(1188511885, 2, 2));
+    }
+
+    #[test]
+    fn test_generate_invalid_in_range() {
+        let ranges = parse_ranges(SAMPLE).unwrap();
+        for (max_repetitions, expected) in [(2, 1227775554), (u64::MAX, 4174379265)] {
+            let sum: u64 = ranges
+                .iter()
+                .flat_map(|range| generate_invalid_in_range(range, 2, max_repetitions))
+                .sum();
+            assert_eq!(sum, expected);
+        }
+
+        assert_eq!(generate_invalid_in_range(&(95..=115), 2, 2), vec![99]);
+        assert_eq!(generate_invalid_in_range(&(95..=115), 2, 3), vec![99, 111]);
+        assert_eq!(generate_invalid_in_range(&(1..=9), 2, u64::MAX), vec![]);
+        assert_eq!(
+            generate_invalid_in_range(&(u64::MAX - 1000..=u64::MAX), 2, u64::MAX),
+            vec![]
+        );
+    }
+
+    // The benchmark relies on both approaches agreeing, see `benches/invalid.rs`.
+    #[test]
+    fn test_generate_matches_brute_force() {
+        for range in parse_ranges(SAMPLE)
+            .unwrap()
+            .iter()
+            .chain([&(1..=1_000_000)])
+        {
+            for max_repetitions in [2, 3, u64::MAX] {
+                assert_eq!(
+                    generate_invalid_in_range(range, 2, max_repetitions),
+                    invalid_values(range, 2, max_repetitions),
+                    "{:?} {}",
+                    range,
+                    max_repetitions
+                );
+            }
+        }
+    }
+}
@@ -1,7 +1,18 @@
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use std::time::Instant;
 
 #[derive(Debug)]
-enum Error {}
+enum Error {
+    // The assembled number (or the sum of them) doesn't fit into a u64.
+    Overflow,
+    // A bank has fewer batteries than digits are requested.
+    #[allow(dead_code)]
+    NotEnoughDigits {
+        num_digits: u64,
+        bank_len: usize,
+    },
+}
 
 #[allow(dead_code)]
 fn max_num_recursive(bank: &Vec<u64>, num_digits: u64) -> u64 {
@@ -18,26 +29,39 @@ fn max_num_recursive(bank: &Vec<u64>, num_digits: u64) -> u64 {
 // Since I wasn't satisfied with my recursive solution (took 5s for the second part), I looked
 // up how other people solved it. This is a pretty elegant algorithm, and it solve part 2 in
 // less than 2ms, so quite the improvement…
-fn max_num_iterative(bank: &Vec<u64>, num_digits: u64) -> u64 {
+//
+// Fails instead of silently overflowing if `num_digits` is too large for a u64.
+fn max_num_iterative_checked(bank: &[u64], num_digits: u64) -> Result<u64, Error> {
+    if num_digits == 0 {
+        return Ok(0);
+    }
+    if num_digits > bank.len() as u64 {
+        return Err(Error::NotEnoughDigits {
+            num_digits,
+            bank_len: bank.len(),
+        });
+    }
+
     let mut start = 0;
-    let mut sum = 0;
+    let mut sum: u64 = 0;
     for end in (bank.len() - (num_digits - 1) as usize)..=bank.len() {
         let mut index = start;
         let mut largest = 0;
 
-        for i in start..end {
-            let digit = bank[i];
-            if digit > largest {
-                largest = digit;
+        for (i, digit) in bank.iter().enumerate().take(end).skip(start) {
+            if *digit > largest {
+                largest = *digit;
                 index = i;
             }
         }
 
-        sum *= 10;
-        sum += largest;
+        sum = sum
+            .checked_mul(10)
+            .and_then(|sum| sum.checked_add(largest))
+            .ok_or(Error::Overflow)?;
         start = index + 1;
     }
-    return sum;
+    return Ok(sum);
 }
 
 fn recurse(
@@ -71,31 +95,67 @@ fn recurse(
     return new_max;
 }
 
-fn solve(input: &str, num_digits: u64) -> Result<u64, Error> {
+// Blank lines, like the one an empty input consists of, have no batteries and are skipped.
+fn parse_banks(input: &str) -> Vec<Vec<u64>> {
     let lines = input.trim().split('\n');
-    let banks = lines
+    return lines
+        .filter(|line| !line.trim().is_empty())
         .map(|line| {
             line.chars()
                 .map(|c| c.to_digit(10).unwrap_or(0) as u64)
                 .collect::<Vec<_>>()
         })
         .collect::<Vec<_>>();
+}
 
-    let sum = banks
-        .into_iter()
-        .map(|bank| max_num_iterative(&bank, num_digits))
-        .sum::<u64>();
+#[cfg_attr(feature = "parallel", allow(dead_code))]
+fn solve(input: &str, num_digits: u64) -> Result<u64, Error> {
+    let mut sum = 0;
+    for bank in parse_banks(input) {
+        let value = max_num_iterative_checked(&bank, num_digits)?;
+        sum = u64::checked_add(sum, value).ok_or(Error::Overflow)?;
+    }
 
     Ok(sum)
 }
 
+// Like `solve`, but for large inputs. The banks don't depend on each other and the sum doesn't
+// depend on the order, so the result is the same.
+#[cfg(feature = "parallel")]
+fn solve_parallel(input: &str, num_digits: u64) -> Result<u64, Error> {
+    return parse_banks(input)
+        .par_iter()
+        .map(|bank| max_num_iterative_checked(bank, num_digits))
+        .try_reduce(|| 0, |a, b| a.checked_add(b).ok_or(Error::Overflow));
+}
+
+// The largest number of any bank, along with the index of its line. On a tie, the first line
+// wins.
+#[allow(dead_code)]
+fn best_bank(input: &str, num_digits: u64) -> Result<(usize, u64), Error> {
+    let mut best = (0, 0);
+    for (index, bank) in parse_banks(input).iter().enumerate() {
+        let value = max_num_iterative_checked(bank, num_digits)?;
+        if value > best.1 {
+            best = (index, value);
+        }
+    }
+    return Ok(best);
+}
+
 fn part1(input: &str) -> Result<(), Error> {
+    #[cfg(feature = "parallel")]
+    let sum = solve_parallel(input, 2)?;
+    #[cfg(not(feature = "parallel"))]
     let sum = solve(input, 2)?;
     println!("Part 1: {}", sum);
     return Ok(());
 }
 
 fn part2(input: &str) -> Result<(), Error> {
+    #[cfg(feature = "parallel")]
+    let sum = solve_parallel(input, 12)?;
+    #[cfg(not(feature = "parallel"))]
     let sum = solve(input, 12)?;
     println!("Part 2: {}", sum);
     return Ok(());
@@ -114,3 +174,65 @@ fn main() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_num_iterative_checked() {
+        let sample = include_str!("../rsc/sample1.txt");
+        assert_eq!(solve(sample, 2).unwrap(), 357);
+        assert_eq!(solve(sample, 12).unwrap(), 3121910778619);
+
+        let bank = vec![9; 25];
+        assert_eq!(
+            max_num_iterative_checked(&bank, 19).unwrap(),
+            9999999999999999999
+        );
+        assert!(matches!(
+            max_num_iterative_checked(&bank, 20),
+            Err(Error::Overflow)
+        ));
+
+        assert_eq!(max_num_iterative_checked(&bank, 0).unwrap(), 0);
+        assert!(matches!(
+            max_num_iterative_checked(&[1, 2, 3], 4),
+            Err(Error::NotEnoughDigits {
+                num_digits: 4,
+                bank_len: 3
+            })
+        ));
+
+        // An empty input has no banks, rather than an empty one.
+        assert_eq!(solve("", 2).unwrap(), 0);
+        assert_eq!(solve("\n", 12).unwrap(), 0);
+
+        // Each bank fits, but their sum doesn't.
+        let input = "99999999999999999999\n99999999999999999999\n";
+        assert!(matches!(solve(input, 19), Err(Error::Overflow)));
+    }
+
+    #[test]
+    fn test_best_bank() {
+        let input = "811111111111119\n234234234234278\n";
+        assert_eq!(best_bank(input, 2).unwrap(), (0, 89));
+        assert_eq!(best_bank(input, 3).unwrap(), (0, 819));
+
+        let input = "818181911112111\n987654321111111\n";
+        assert_eq!(best_bank(input, 2).unwrap(), (1, 98));
+        assert_eq!(best_bank(input, 12).unwrap(), (1, 987654321111));
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn test_solve_parallel() {
+        let sample = include_str!("../rsc/sample1.txt");
+        for num_digits in [2, 12] {
+            assert_eq!(
+                solve_parallel(sample, num_digits).unwrap(),
+                solve(sample, num_digits).unwrap()
+            );
+        }
+    }
+}
@@ -1,7 +1,16 @@
+use std::fmt;
 use std::time::Instant;
 
 #[derive(Debug)]
-enum Error {}
+enum Error {
+    // A row is not as long as the first one. Rows are counted from 1.
+    #[allow(dead_code)]
+    RaggedGrid {
+        row: usize,
+        expected: usize,
+        got: usize,
+    },
+}
 
 #[derive(Eq, PartialEq)]
 enum Cell {
@@ -19,6 +28,19 @@ impl Map {
     fn from_str(input: &str) -> Result<Map, Error> {
         let lines: Vec<&str> = input.trim().lines().collect();
         let height = lines.len();
+        // The width is that of the first row, every other row has to match it. Otherwise the
+        // cells would silently end up in the wrong rows.
+        let width = lines.first().map_or(0, |line| line.chars().count());
+        for (index, line) in lines.iter().enumerate() {
+            let got = line.chars().count();
+            if got != width {
+                return Err(Error::RaggedGrid {
+                    row: index + 1,
+                    expected: width,
+                    got,
+                });
+            }
+        }
         let cells: Vec<Cell> = lines
             .iter()
             .flat_map(|line| {
@@ -29,7 +51,6 @@ impl Map {
                 })
             })
             .collect();
-        let width = if height > 0 { cells.len() / height } else { 0 };
         Ok(Map {
             width: width as isize,
             height: height as isize,
@@ -59,19 +80,28 @@ impl Map {
         count
     }
 
-    fn can_move(&self, x: isize, y: isize) -> bool {
+    // A roll can be moved if fewer than `threshold` rolls are adjacent.
+    fn can_move(&self, x: isize, y: isize, threshold: isize) -> bool {
         if self.get(x, y) == &Cell::Roll {
             let count = self.count_adjacent(x, y);
-            if count < 4 { return true } else { return false }
+            if count < threshold {
+                return true;
+            } else {
+                return false;
+            }
         }
         false
     }
 
     fn get_movable(&self) -> Vec<(isize, isize)> {
+        self.get_movable_with_threshold(4)
+    }
+
+    fn get_movable_with_threshold(&self, threshold: isize) -> Vec<(isize, isize)> {
         let mut movable = Vec::new();
         for x in 0..self.width {
             for y in 0..self.height {
-                if self.can_move(x, y) {
+                if self.can_move(x, y, threshold) {
                     movable.push((x, y));
                 }
             }
@@ -79,6 +109,25 @@ impl Map {
         movable
     }
 
+    // For each threshold from 0 to 8, how many rolls could be moved, i.e. have fewer than that
+    // many adjacent rolls. Counts the neighbors of each roll only once.
+    #[allow(dead_code)]
+    fn movable_by_threshold(&self) -> [usize; 9] {
+        let mut movable = [0; 9];
+        for x in 0..self.width {
+            for y in 0..self.height {
+                if self.get(x, y) != &Cell::Roll {
+                    continue;
+                }
+                let count = self.count_adjacent(x, y) as usize;
+                for slot in &mut movable[count + 1..] {
+                    *slot += 1;
+                }
+            }
+        }
+        movable
+    }
+
     fn remove_movable(&mut self, movable: Vec<(isize, isize)>) {
         for (x, y) in movable {
             self.cells[(x + y * self.width) as usize] = Cell::Empty;
@@ -86,6 +135,37 @@ impl Map {
     }
 }
 
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let c = match self.get(x, y) {
+                    Cell::Empty => '.',
+                    Cell::Roll => '@',
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// Removes the movable rolls for up to `rounds` rounds, stopping early once nothing can be moved
+// anymore. Returns how many rolls were removed in total.
+fn erode(map: &mut Map, rounds: usize, threshold: isize) -> usize {
+    let mut moved = 0;
+    for _ in 0..rounds {
+        let movable = map.get_movable_with_threshold(threshold);
+        if movable.is_empty() {
+            break;
+        }
+        moved += movable.len();
+        map.remove_movable(movable);
+    }
+    moved
+}
+
 fn part1(input: &str) -> Result<(), Error> {
     let map = Map::from_str(input)?;
     let movable = map.get_movable();
@@ -95,16 +175,7 @@ fn part1(input: &str) -> Result<(), Error> {
 
 fn part2(input: &str) -> Result<(), Error> {
     let mut map = Map::from_str(input)?;
-    let mut moved = 0;
-
-    loop {
-        let movable = map.get_movable();
-        if movable.len() == 0 {
-            break;
-        }
-        moved += movable.len();
-        map.remove_movable(movable.clone());
-    }
+    let moved = erode(&mut map, usize::MAX, 4);
 
     println!("Part 2: {}", moved);
     return Ok(());
@@ -123,3 +194,78 @@ fn main() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_erode() {
+        let input = include_str!("../rsc/sample1.txt");
+        let mut map = Map::from_str(input).unwrap();
+        assert_eq!(map.to_string(), input);
+
+        assert_eq!(erode(&mut map, 1, 4), 13);
+        assert_eq!(
+            map.to_string(),
+            ".......@..\n\
+             .@@.@.@.@@\n\
+             @@@@@...@@\n\
+             @.@@@@..@.\n\
+             .@.@@@@.@.\n\
+             .@@@@@@@.@\n\
+             .@.@.@.@@@\n\
+             ..@@@.@@@@\n\
+             .@@@@@@@@.\n\
+             ....@@@...\n"
+        );
+
+        // The remaining rounds, until it's stable.
+        assert_eq!(erode(&mut map, usize::MAX, 4), 30);
+        assert_eq!(erode(&mut map, usize::MAX, 4), 0);
+    }
+
+    #[test]
+    fn test_movable_by_threshold() {
+        let map = Map::from_str("@@@\n@@@\n@.@\n").unwrap();
+        let movable = map.movable_by_threshold();
+        assert_eq!(movable, [0, 0, 0, 2, 4, 6, 7, 7, 8]);
+        assert!(movable.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        let map = Map::from_str(include_str!("../rsc/sample1.txt")).unwrap();
+        let movable = map.movable_by_threshold();
+        assert!(movable.windows(2).all(|pair| pair[0] <= pair[1]));
+        for (threshold, count) in movable.iter().enumerate() {
+            assert_eq!(
+                map.get_movable_with_threshold(threshold as isize).len(),
+                *count
+            );
+        }
+        assert_eq!(movable[4], 13);
+    }
+
+    #[test]
+    fn test_ragged_grid() {
+        assert!(matches!(
+            Map::from_str("@@@\n@.@\n@@\n"),
+            Err(Error::RaggedGrid {
+                row: 3,
+                expected: 3,
+                got: 2
+            })
+        ));
+        assert!(matches!(
+            Map::from_str("@@\n@.@\n@@\n"),
+            Err(Error::RaggedGrid {
+                row: 2,
+                expected: 2,
+                got: 3
+            })
+        ));
+
+        let map = Map::from_str("@@@\n@.@\n").unwrap();
+        assert_eq!((map.width, map.height), (3, 2));
+        let map = Map::from_str("").unwrap();
+        assert_eq!((map.width, map.height), (0, 0));
+    }
+}
@@ -15,6 +15,13 @@ struct Cafeteria {
 }
 
 impl Cafeteria {
+    fn new(fresh_ranges: Vec<RangeInclusive<u64>>, ingredients: Vec<u64>) -> Cafeteria {
+        return Cafeteria {
+            fresh_ranges,
+            ingredients,
+        };
+    }
+
     fn from_input(input: &str) -> Result<Cafeteria, Error> {
         let (range_input, ingredient_input) =
             input.trim().split_once("\n\n").ok_or(Error::InvalidInput)?;
@@ -40,10 +47,16 @@ impl Cafeteria {
                 Ok(start..=end)
             })
             .collect::<Result<Vec<RangeInclusive<u64>>, Error>>()?;
-        Ok(Cafeteria {
-            fresh_ranges,
-            ingredients,
-        })
+        Ok(Cafeteria::new(fresh_ranges, ingredients))
+    }
+
+    // Some inputs consist of several cafeterias, separated by two blank lines.
+    fn from_input_multi(input: &str) -> Result<Vec<Cafeteria>, Error> {
+        return input
+            .trim()
+            .split("\n\n\n")
+            .map(Cafeteria::from_input)
+            .collect();
     }
 
     fn count_fresh(&self) -> u64 {
@@ -59,6 +72,29 @@ impl Cafeteria {
         return count;
     }
 
+    // Checks a single ingredient against the raw ranges, without consolidating them first.
+    #[allow(dead_code)]
+    fn is_fresh(&self, ingredient: u64) -> bool {
+        return self
+            .fresh_ranges
+            .iter()
+            .any(|range| range.contains(&ingredient));
+    }
+
+    // Consolidates the ranges once and returns a lookup doing a binary search over them,
+    // for testing many ingredients.
+    #[allow(dead_code)]
+    fn is_fresh_fast(&mut self) -> impl Fn(u64) -> bool {
+        self.consolidate_ranges();
+        let ranges = &self.fresh_ranges;
+        return move |ingredient| {
+            // The consolidated ranges are sorted and disjoint, so only the last range starting
+            // at or before the ingredient can contain it.
+            let index = ranges.partition_point(|range| *range.start() <= ingredient);
+            return index > 0 && ranges[index - 1].contains(&ingredient);
+        };
+    }
+
     fn count_possible_ids(&mut self) -> u64 {
         self.consolidate_ranges();
         let mut count = 0;
@@ -109,15 +145,15 @@ impl Cafeteria {
 }
 
 fn part1(input: &str) -> Result<(), Error> {
-    let cafeteria = Cafeteria::from_input(input)?;
-    let spoiled = cafeteria.count_fresh();
+    let cafeterias = Cafeteria::from_input_multi(input)?;
+    let spoiled: u64 = cafeterias.iter().map(|c| c.count_fresh()).sum();
     println!("Part 1: {}", spoiled);
     return Ok(());
 }
 
 fn part2(input: &str) -> Result<(), Error> {
-    let mut cafeteria = Cafeteria::from_input(input)?;
-    let possible_ids = cafeteria.count_possible_ids();
+    let mut cafeterias = Cafeteria::from_input_multi(input)?;
+    let possible_ids: u64 = cafeterias.iter_mut().map(|c| c.count_possible_ids()).sum();
     println!("Part 2: {}", possible_ids);
     return Ok(());
 }
@@ -135,3 +171,62 @@ fn main() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_input_multi() {
+        let sample = include_str!("../rsc/sample1.txt");
+        let cafeterias = Cafeteria::from_input_multi(sample).unwrap();
+        assert_eq!(cafeterias.len(), 1);
+        assert_eq!(cafeterias[0].count_fresh(), 3);
+
+        let input = format!("{}\n\n1-2\n\n1\n2\n3\n", sample);
+        let mut cafeterias = Cafeteria::from_input_multi(&input).unwrap();
+        let fresh: Vec<u64> = cafeterias.iter().map(|c| c.count_fresh()).collect();
+        assert_eq!(fresh, vec![3, 2]);
+        let possible: Vec<u64> = cafeterias
+            .iter_mut()
+            .map(|c| c.count_possible_ids())
+            .collect();
+        assert_eq!(possible, vec![14, 2]);
+    }
+
+    #[test]
+    fn test_new() {
+        let mut cafeteria = Cafeteria::new(
+            vec![3..=5, 10..=14, 16..=20, 12..=18],
+            vec![1, 5, 8, 11, 17, 32],
+        );
+        let mut parsed = Cafeteria::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        assert_eq!(cafeteria.count_fresh(), parsed.count_fresh());
+        assert_eq!(cafeteria.count_possible_ids(), parsed.count_possible_ids());
+        assert_eq!(cafeteria.count_fresh(), 3);
+        assert_eq!(cafeteria.count_possible_ids(), 14);
+    }
+
+    #[test]
+    fn test_is_fresh() {
+        let mut cafeteria = Cafeteria::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        let expected = cafeteria.count_fresh();
+        let ingredients = cafeteria.ingredients.clone();
+
+        let fresh: Vec<bool> = ingredients.iter().map(|&i| cafeteria.is_fresh(i)).collect();
+        assert_eq!(fresh, vec![false, true, false, true, true, false]);
+        assert_eq!(fresh.iter().filter(|&&f| f).count() as u64, expected);
+
+        let is_fresh = cafeteria.is_fresh_fast();
+        let fast: Vec<bool> = ingredients.iter().map(|&i| is_fresh(i)).collect();
+        assert_eq!(fast, fresh);
+        for ingredient in 0..=25 {
+            assert_eq!(
+                is_fresh(ingredient),
+                (3..=5).contains(&ingredient) || (10..=20).contains(&ingredient),
+                "ingredient {}",
+                ingredient
+            );
+        }
+    }
+}
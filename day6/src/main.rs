@@ -14,28 +14,54 @@ enum MathOperator {
     Multiply,
 }
 
+impl MathOperator {
+    fn from_str(op: &str) -> Result<MathOperator, Error> {
+        match op {
+            "+" => Ok(MathOperator::Add),
+            "*" => Ok(MathOperator::Multiply),
+            _ => return Err(Error::InvalidOperator(op.to_string())),
+        }
+    }
+}
+
 struct MathProblem {
     numbers: Vec<u64>,
     operator: MathOperator,
 }
 
+// Same as `MathProblem`, but for sheets with decimal numbers.
+#[allow(dead_code)]
+struct FloatProblem {
+    numbers: Vec<f64>,
+    operator: MathOperator,
+}
+
 impl MathProblem {
-    fn from_input_part1(input: &str) -> Result<Vec<MathProblem>, Error> {
-        let mut lines = input.trim().lines().collect::<Vec<&str>>();
+    // With `blank_as_zero`, blank cells are read as zeros instead of rejecting the row. Which
+    // problem a number belongs to is then decided by its position in the sheet, see
+    // `cells_by_column`. A zero doesn't change the sum of an Add column, but it would zero the
+    // product of a Multiply column, so blanks there are an error.
+    fn from_input_part1(input: &str, blank_as_zero: bool) -> Result<Vec<MathProblem>, Error> {
+        // Only blank lines are dropped, leading spaces are needed for the column positions.
+        let mut lines = input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect::<Vec<&str>>();
 
         // First, get the last line with the operators and create "problems" with the
         // corresponding operators. This strips the last line from `lines`.
         let operator_line = lines
             .pop()
-            .ok_or(Error::InvalidInput("Missing operator line".to_string()))?
-            .split_whitespace()
-            .filter(|s| !s.is_empty());
+            .ok_or(Error::InvalidInput("Missing operator line".to_string()))?;
+        let starts = operator_line
+            .chars()
+            .enumerate()
+            .filter(|(_, c)| *c != ' ')
+            .map(|(index, _)| index)
+            .collect::<Vec<usize>>();
         let operators = operator_line
-            .map(|op| match op {
-                "+" => Ok(MathOperator::Add),
-                "*" => Ok(MathOperator::Multiply),
-                _ => return Err(Error::InvalidOperator(op.to_string())),
-            })
+            .split_whitespace()
+            .map(MathOperator::from_str)
             .collect::<Result<Vec<MathOperator>, Error>>()?;
         let mut problems = operators
             .iter()
@@ -48,14 +74,25 @@ impl MathProblem {
         // Now iterate over all (remaining)lines and fill the numbers into the problems.
         let columns = operators.len();
         for line in lines {
-            let numbers = line
-                .split_whitespace()
-                .filter(|s| !s.is_empty())
-                .map(|s| {
-                    s.parse::<u64>()
-                        .map_err(|_| Error::InvalidNumber(s.to_string()))
-                })
-                .collect::<Result<Vec<u64>, Error>>()?;
+            let numbers = if blank_as_zero {
+                let cells = MathProblem::cells_by_column(line, &starts)?;
+                if let Some(index) = (0..columns).find(|index| {
+                    cells[*index].is_none() && matches!(operators[*index], MathOperator::Multiply)
+                }) {
+                    return Err(Error::InvalidInput(format!(
+                        "Blank cell in multiply column {} in line '{}'",
+                        index, line
+                    )));
+                }
+                cells.iter().map(|cell| cell.unwrap_or(0)).collect()
+            } else {
+                line.split_whitespace()
+                    .map(|s| {
+                        s.parse::<u64>()
+                            .map_err(|_| Error::InvalidNumber(s.to_string()))
+                    })
+                    .collect::<Result<Vec<u64>, Error>>()?
+            };
             if numbers.len() != columns {
                 return Err(Error::InvalidInput(format!(
                     "Invalid number of columns in line '{}'",
@@ -75,6 +112,89 @@ impl MathProblem {
         Ok(problems)
     }
 
+    // The cells of a row, `None` for blank ones. Each problem spans from its operator's column in
+    // `starts` up to the separator column before the next operator, and the numbers may be
+    // aligned anywhere inside it. A number outside of all problems, one running into a separator
+    // and two numbers in the same problem cannot be placed, so they're an error.
+    fn cells_by_column(line: &str, starts: &[usize]) -> Result<Vec<Option<u64>>, Error> {
+        let chars = line.chars().collect::<Vec<char>>();
+        let mut cells = vec![None; starts.len()];
+        let mut index = 0;
+        while index < chars.len() {
+            if chars[index] == ' ' {
+                index += 1;
+                continue;
+            }
+            let start = index;
+            while index < chars.len() && chars[index] != ' ' {
+                index += 1;
+            }
+            let number = chars[start..index].iter().collect::<String>();
+            let misplaced = || {
+                Error::InvalidInput(format!(
+                    "Cannot place '{}' at column {} in line '{}'",
+                    number, start, line
+                ))
+            };
+
+            let column = starts
+                .iter()
+                .rposition(|column_start| *column_start <= start)
+                .ok_or_else(misplaced)?;
+            let separator = starts.get(column + 1).map(|next| next - 1);
+            if separator.is_some_and(|separator| index > separator) || cells[column].is_some() {
+                return Err(misplaced());
+            }
+            let value = number
+                .parse::<u64>()
+                .map_err(|_| Error::InvalidNumber(number.clone()))?;
+            cells[column] = Some(value);
+        }
+        Ok(cells)
+    }
+
+    // Same layout as for part 1, but with the fields separated by commas, like "123,328,51,64".
+    // The last row contains the operators of the columns. Spaces around the fields are ignored.
+    #[allow(dead_code)]
+    fn from_csv(input: &str) -> Result<Vec<MathProblem>, Error> {
+        let rows = input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.split(',').map(|field| field.trim()).collect())
+            .collect::<Vec<Vec<&str>>>();
+        let (operator_row, number_rows) = rows
+            .split_last()
+            .ok_or(Error::InvalidInput("Missing operator line".to_string()))?;
+
+        let mut problems = operator_row
+            .iter()
+            .map(|op| {
+                Ok(MathProblem {
+                    numbers: Vec::new(),
+                    operator: MathOperator::from_str(op)?,
+                })
+            })
+            .collect::<Result<Vec<MathProblem>, Error>>()?;
+
+        for (line_index, row) in number_rows.iter().enumerate() {
+            if row.len() != problems.len() {
+                return Err(Error::InvalidInput(format!(
+                    "Invalid number of columns in line {}",
+                    line_index + 1
+                )));
+            }
+
+            for (problem, field) in problems.iter_mut().zip(row) {
+                let value = field
+                    .parse::<u64>()
+                    .map_err(|_| Error::InvalidNumber(field.to_string()))?;
+                problem.numbers.push(value);
+            }
+        }
+
+        Ok(problems)
+    }
+
     fn from_input_part2(input: &str) -> Result<Vec<MathProblem>, Error> {
         let mut problems = Vec::new();
 
@@ -91,6 +211,7 @@ impl MathProblem {
             .map(|line| line.len())
             .max()
             .ok_or(Error::InvalidInput("Empty input".to_string()))?;
+        MathProblem::validate_alignment(&lines)?;
 
         // Parse the two-dimensional vector from right to left, top to bottom. Parse the
         // numbers and push them to the `problems` once an operator is found.
@@ -134,6 +255,45 @@ impl MathProblem {
         Ok(problems)
     }
 
+    // Each operator sits in the leftmost column of its problem, and problems are separated by a
+    // column of spaces. If a digit shows up in such a separator column, or right next to an
+    // operator, the sheet is misaligned and the digit would end up in the wrong number.
+    fn validate_alignment(lines: &[Vec<char>]) -> Result<(), Error> {
+        let operator_line = lines
+            .last()
+            .ok_or(Error::InvalidInput("Missing operator line".to_string()))?;
+
+        for (index, char) in operator_line.iter().enumerate() {
+            if *char != '+' && *char != '*' {
+                continue;
+            }
+
+            if let Some(next) = operator_line.get(index + 1).filter(|c| **c != ' ') {
+                return Err(Error::InvalidInput(format!(
+                    "Stray '{}' next to operator in column {}",
+                    next, index
+                )));
+            }
+
+            if index == 0 {
+                continue;
+            }
+            for (line_index, line) in lines.iter().enumerate() {
+                let separator = line.get(index - 1).unwrap_or(&' ');
+                if *separator != ' ' {
+                    return Err(Error::InvalidInput(format!(
+                        "Misaligned '{}' in line {}, column {}",
+                        separator,
+                        line_index + 1,
+                        index - 1
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn calculate(&self) -> u64 {
         match self.operator {
             MathOperator::Add => self.numbers.iter().sum(),
@@ -142,8 +302,65 @@ impl MathProblem {
     }
 }
 
+#[allow(dead_code)]
+impl FloatProblem {
+    // Same layout as for part 1.
+    fn from_input(input: &str) -> Result<Vec<FloatProblem>, Error> {
+        let mut lines = input.trim().lines().collect::<Vec<&str>>();
+        let mut problems = lines
+            .pop()
+            .ok_or(Error::InvalidInput("Missing operator line".to_string()))?
+            .split_whitespace()
+            .map(|op| {
+                Ok(FloatProblem {
+                    numbers: Vec::new(),
+                    operator: MathOperator::from_str(op)?,
+                })
+            })
+            .collect::<Result<Vec<FloatProblem>, Error>>()?;
+
+        for line in lines {
+            let numbers = line
+                .split_whitespace()
+                .map(FloatProblem::parse_number)
+                .collect::<Result<Vec<f64>, Error>>()?;
+            if numbers.len() != problems.len() {
+                return Err(Error::InvalidInput(format!(
+                    "Invalid number of columns in line '{}'",
+                    line
+                )));
+            }
+            for (problem, value) in problems.iter_mut().zip(numbers) {
+                problem.numbers.push(value);
+            }
+        }
+
+        Ok(problems)
+    }
+
+    // Digits with an optional decimal point, which may also come first or last: "1.5", ".5" and
+    // "2." are fine. Unlike `f64::from_str`, signs, exponents, "inf" and "NaN" are rejected.
+    fn parse_number(s: &str) -> Result<f64, Error> {
+        let digits = s.chars().filter(|c| c.is_ascii_digit()).count();
+        let points = s.chars().filter(|c| *c == '.').count();
+        if digits == 0 || points > 1 || digits + points != s.len() {
+            return Err(Error::InvalidNumber(s.to_string()));
+        }
+        return s
+            .parse::<f64>()
+            .map_err(|_| Error::InvalidNumber(s.to_string()));
+    }
+
+    fn calculate(&self) -> f64 {
+        match self.operator {
+            MathOperator::Add => self.numbers.iter().sum(),
+            MathOperator::Multiply => self.numbers.iter().product(),
+        }
+    }
+}
+
 fn part1(input: &str) -> Result<(), Error> {
-    let problems = MathProblem::from_input_part1(input)?;
+    let problems = MathProblem::from_input_part1(input, false)?;
     let sum = problems.iter().map(|p| p.calculate()).sum::<u64>();
     println!("Part 1: {}", sum);
     return Ok(());
@@ -169,3 +386,137 @@ fn main() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part2_alignment() {
+        let problems = MathProblem::from_input_part2(include_str!("../rsc/sample1.txt")).unwrap();
+        let sum = problems.iter().map(|p| p.calculate()).sum::<u64>();
+        assert_eq!(sum, 3263827);
+
+        // The "387" column is shifted one to the left, into the separator column.
+        let shifted = "123 328  51 64 \n 45 64 387  23 \n  6 98  215 314\n*   +   *   +  \n";
+        assert!(matches!(
+            MathProblem::from_input_part2(shifted),
+            Err(Error::InvalidInput(_))
+        ));
+
+        // The second operator is shifted one to the left, right next to the first column.
+        let shifted = "123 328  51 64 \n 45 64  387 23 \n  6 98  215 314\n*  +    *   +  \n";
+        assert!(matches!(
+            MathProblem::from_input_part2(shifted),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_csv() {
+        let problems =
+            MathProblem::from_input_part1(include_str!("../rsc/sample1.txt"), false).unwrap();
+        let expected = problems.iter().map(|p| p.calculate()).sum::<u64>();
+        assert_eq!(expected, 4277556);
+
+        let csv = "123,328,51,64\n45,64,387,23\n6, 98, 215, 314\n*,+,*,+\n\n";
+        let problems = MathProblem::from_csv(csv).unwrap();
+        let sum = problems.iter().map(|p| p.calculate()).sum::<u64>();
+        assert_eq!(sum, expected);
+
+        assert!(matches!(
+            MathProblem::from_csv("1,2\n3\n+,*"),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            MathProblem::from_csv("1,x\n+,*"),
+            Err(Error::InvalidNumber(_))
+        ));
+        assert!(matches!(
+            MathProblem::from_csv("1,2\n+,-"),
+            Err(Error::InvalidOperator(_))
+        ));
+    }
+
+    #[test]
+    fn test_float_problem() {
+        let sheet = "1.5  .5  2.\n2   .25 0.5\n+   *   +\n";
+        let problems = FloatProblem::from_input(sheet).unwrap();
+        let results: Vec<f64> = problems.iter().map(|p| p.calculate()).collect();
+        for (result, expected) in results.iter().zip([3.5, 0.125, 2.5]) {
+            assert!(
+                (result - expected).abs() < 1e-9,
+                "{} != {}",
+                result,
+                expected
+            );
+        }
+        let sum = results.iter().sum::<f64>();
+        assert!((sum - 6.125).abs() < 1e-9);
+
+        // The integer sample works as well.
+        let problems = FloatProblem::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        let sum = problems.iter().map(|p| p.calculate()).sum::<f64>();
+        assert!((sum - 4277556.0).abs() < 1e-6);
+
+        for invalid in [".", "1.2.3", "-1", "1e5", "inf", "NaN", "1,5"] {
+            assert!(
+                matches!(
+                    FloatProblem::parse_number(invalid),
+                    Err(Error::InvalidNumber(_))
+                ),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_blank_as_zero() {
+        // The last cell of the second row, in an Add column, is blank.
+        let sheet = "123 328  51 64\n 45 64  387\n  6 98  215 314\n*   +   *   +\n";
+        assert!(matches!(
+            MathProblem::from_input_part1(sheet, false),
+            Err(Error::InvalidInput(_))
+        ));
+        let problems = MathProblem::from_input_part1(sheet, true).unwrap();
+        assert_eq!(problems[3].numbers, [64, 0, 314]);
+        let sum = problems.iter().map(|p| p.calculate()).sum::<u64>();
+        assert_eq!(sum, 4277556 - 23);
+
+        // A blank cell in a Multiply column would zero the product.
+        let sheet = "123 328  51 64\n 45 64\n  6 98  215 314\n*   +   *   +\n";
+        assert!(matches!(
+            MathProblem::from_input_part1(sheet, true),
+            Err(Error::InvalidInput(_))
+        ));
+
+        // Blank cells in front of other numbers don't shift them into the wrong problems.
+        let sheet = "123 328  51 64 \n 45     387 23 \n  6 98  215 314\n*   +   *   +  \n";
+        let problems = MathProblem::from_input_part1(sheet, true).unwrap();
+        assert_eq!(problems[1].numbers, [328, 0, 98]);
+        assert_eq!(problems[2].numbers, [51, 387, 215]);
+        assert_eq!(problems[3].numbers, [64, 23, 314]);
+        let sum = problems.iter().map(|p| p.calculate()).sum::<u64>();
+        assert_eq!(sum, 4277556 - 64);
+        let sheet = " 45 64      23\n  6 98  215 314\n*   +   *   +\n";
+        assert!(matches!(
+            MathProblem::from_input_part1(sheet, true),
+            Err(Error::InvalidInput(_))
+        ));
+
+        // Numbers that cannot be placed: ones running into the separator before the fourth
+        // problem, and two in the same problem.
+        for row in [" 45      387 23", " 45 64  3870 23", " 45 64  3 7 23"] {
+            let sheet = format!("123 328  51 64\n{}\n*   +   *   +\n", row);
+            assert!(
+                matches!(
+                    MathProblem::from_input_part1(&sheet, true),
+                    Err(Error::InvalidInput(_))
+                ),
+                "{}",
+                row
+            );
+        }
+    }
+}
@@ -37,12 +37,18 @@ struct SplitterNode {
 
 impl TachyonMap {
     fn from_input(input: &str) -> Result<TachyonMap, Error> {
+        let grid: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
+        TachyonMap::from_grid(&grid)
+    }
+
+    // Same as `from_input`, but with the lines already split into characters.
+    fn from_grid(grid: &[Vec<char>]) -> Result<TachyonMap, Error> {
         let mut fields: Vec<Field> = Vec::new();
         let mut width = 0;
         let mut height = 0;
         let mut start = (0, 0);
 
-        for line in input.lines() {
+        for line in grid {
             if width == 0 {
                 width = line.len();
             } else if width != line.len() {
@@ -50,7 +56,7 @@ impl TachyonMap {
             }
             height += 1;
 
-            for (i, c) in line.chars().enumerate() {
+            for (i, c) in line.iter().copied().enumerate() {
                 match c {
                     '.' => fields.push(Field::Empty),
                     'S' => {
@@ -80,7 +86,7 @@ impl TachyonMap {
         next_beams.push(beam);
 
         while let Some(beam) = next_beams.pop() {
-            if *beam.ys.end() == self.height {
+            if self.exits_map(&beam) {
                 // Beam has ran out of the map.
                 continue;
             }
@@ -129,6 +135,13 @@ impl TachyonMap {
         }
     }
 
+    // Whether the beam runs out of the bottom of the map instead of ending at a splitter. That's
+    // also the case for a beam that starts on the last row without a splitter, like the ones split
+    // off by a splitter on the last row.
+    fn exits_map(&self, beam: &TachyonBeam) -> bool {
+        return *beam.ys.end() >= self.height;
+    }
+
     fn split_beam(&self, beams: &Vec<TachyonBeam>, x: usize, y: usize) -> Vec<TachyonBeam> {
         let left_x = x - 1;
         let right_x = x + 1;
@@ -163,20 +176,64 @@ impl TachyonMap {
         return false;
     }
 
+    // All traced beams, each with whether it runs out of the bottom of the map (`true`) or ends
+    // at a splitter (`false`).
+    #[allow(dead_code)]
+    fn beam_fates(&self) -> Vec<(TachyonBeam, bool)> {
+        return self
+            .trace_beams()
+            .into_iter()
+            .map(|beam| {
+                let exits = self.exits_map(&beam);
+                return (beam, exits);
+            })
+            .collect();
+    }
+
     fn splitters_hit(&self) -> HashSet<(usize, usize)> {
         let mut splits = HashSet::new();
         for beam in self.trace_beams() {
-            let y = *beam.ys.end();
-            if y == self.height {
+            if self.exits_map(&beam) {
                 continue;
             }
 
-            splits.insert((beam.x, y));
+            splits.insert((beam.x, *beam.ys.end()));
         }
 
         return splits;
     }
 
+    // The splitters hit, ordered top to bottom and then left to right.
+    #[allow(dead_code)]
+    fn splitters_hit_sorted(&self) -> Vec<(usize, usize)> {
+        let mut splits: Vec<(usize, usize)> = self.splitters_hit().into_iter().collect();
+        splits.sort_by_key(|(x, y)| (*y, *x));
+        return splits;
+    }
+
+    #[allow(dead_code)]
+    fn total_splitters(&self) -> usize {
+        return self
+            .fields
+            .iter()
+            .filter(|field| matches!(field, Field::Splitter))
+            .count();
+    }
+
+    // Splitters that no beam ever reaches.
+    #[allow(dead_code)]
+    fn unreachable_splitters(&self) -> HashSet<(usize, usize)> {
+        let hit = self.splitters_hit();
+        let mut unreachable = HashSet::new();
+        for (index, field) in self.fields.iter().enumerate() {
+            let position = (index % self.width, index / self.width);
+            if matches!(field, Field::Splitter) && !hit.contains(&position) {
+                unreachable.insert(position);
+            }
+        }
+        return unreachable;
+    }
+
     fn build_splitter_graph(&self) -> (HashMap<(usize, usize), SplitterNode>, usize, usize) {
         let mut lookup: HashMap<(usize, usize), SplitterNode> = HashMap::new();
         let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
@@ -188,7 +245,9 @@ impl TachyonMap {
                 continue;
             }
 
-            if y == self.height {
+            // Beams that run out of the map always end at `self.height`, see `trace_beam`. This
+            // includes the start beam if `S` is on the last row.
+            if y >= self.height {
                 // Have reached the bottom. The input doesn't have a splitter here but the
                 // algorithm needs these nodes as the final value sinks.
                 let node = SplitterNode {
@@ -219,78 +278,84 @@ impl TachyonMap {
 
         return (lookup, first.x, *first.ys.end());
     }
-}
-
-fn part1(input: &str) -> Result<(), Error> {
-    let map = TachyonMap::from_input(input)?;
-    let splits = map.splitters_hit();
-    println!("Part 1: {}", splits.len());
-    return Ok(());
-}
-
-fn part2(input: &str) -> Result<(), Error> {
-    // The second part is a bit hard to explain. Of course a stupid recursive approach is way too
-    // slow because of the complexity explosion. After fiddling with it on paper, I realized the
-    // number of paths can "trickle down": the first splitter gets a 1. From here on, we visit each
-    // splitter, top to bottom, look at the left and right children and add the value of the parent
-    // to them. Splitters thus get a value equal to how often they get visited, that is how many
-    // unique paths pass through them.
-    let map = TachyonMap::from_input(input)?;
 
-    // First, build the graph. Luckily that's pretty fast.
-    let (mut lookup, first_x, first_y) = map.build_splitter_graph();
-
-    // Sort the coordinates of the splitters so we can iterate them top to bottom, left to right.
-    let mut queue: Vec<(usize, usize)> = lookup.keys().cloned().collect();
-    queue.sort_by(|a, b| {
-        // y first, x second, but in reverse so we can pop.
-        if a.1 < b.1 {
-            return Ordering::Greater;
-        } else if a.1 > b.1 {
-            return Ordering::Less;
-        } else {
-            if a.0 < b.0 {
+    fn count_timelines(&self) -> usize {
+        // The second part is a bit hard to explain. Of course a stupid recursive approach is way
+        // too slow because of the complexity explosion. After fiddling with it on paper, I
+        // realized the number of paths can "trickle down": the first splitter gets a 1. From here
+        // on, we visit each splitter, top to bottom, look at the left and right children and add
+        // the value of the parent to them. Splitters thus get a value equal to how often they get
+        // visited, that is how many unique paths pass through them.
+
+        // First, build the graph. Luckily that's pretty fast.
+        let (mut lookup, first_x, first_y) = self.build_splitter_graph();
+
+        // Sort the coordinates of the splitters so we can iterate them top to bottom, left to
+        // right.
+        let mut queue: Vec<(usize, usize)> = lookup.keys().cloned().collect();
+        queue.sort_by(|a, b| {
+            // y first, x second, but in reverse so we can pop.
+            if a.1 < b.1 {
                 return Ordering::Greater;
-            } else if a.0 > b.0 {
+            } else if a.1 > b.1 {
                 return Ordering::Less;
             } else {
-                return Ordering::Equal;
+                if a.0 < b.0 {
+                    return Ordering::Greater;
+                } else if a.0 > b.0 {
+                    return Ordering::Less;
+                } else {
+                    return Ordering::Equal;
+                }
             }
-        }
-    });
+        });
 
-    // At this point, the coordinate of the first splitter must be the last in the queue.
-    assert!(queue.last() == Some(&(first_x, first_y)));
+        // At this point, the coordinate of the first splitter must be the last in the queue.
+        assert!(queue.last() == Some(&(first_x, first_y)));
 
-    // Manually assign the value to the first splitter.
-    let first = lookup.get_mut(&(first_x, first_y)).unwrap();
-    first.value = 1;
+        // Manually assign the value to the first splitter.
+        let first = lookup.get_mut(&(first_x, first_y)).unwrap();
+        first.value = 1;
 
-    // "Trickle down" the values, which is the number of paths leading through them.
-    while let Some((x, y)) = queue.pop() {
-        let (value, left, right) = {
-            let node = lookup.get(&(x, y)).unwrap();
-            (node.value, node.left, node.right)
-        };
+        // "Trickle down" the values, which is the number of paths leading through them.
+        while let Some((x, y)) = queue.pop() {
+            let (value, left, right) = {
+                let node = lookup.get(&(x, y)).unwrap();
+                (node.value, node.left, node.right)
+            };
 
-        if let Some(left_key) = left {
-            let left_node = lookup.get_mut(&left_key).unwrap();
-            left_node.value += value;
-        }
-        if let Some(right_key) = right {
-            let right_node = lookup.get_mut(&right_key).unwrap();
-            right_node.value += value;
+            if let Some(left_key) = left {
+                let left_node = lookup.get_mut(&left_key).unwrap();
+                left_node.value += value;
+            }
+            if let Some(right_key) = right {
+                let right_node = lookup.get_mut(&right_key).unwrap();
+                right_node.value += value;
+            }
         }
+
+        // Sum up the values of the splitters below the bottom. These are not in the actual
+        // puzzle input, they exist just to gather the number of paths.
+        let sum = lookup
+            .values()
+            .filter(|node| node.y == self.height)
+            .map(|node| node.value)
+            .sum::<usize>();
+
+        return sum;
     }
+}
 
-    // Sum up the values of the splitters below the bottom. These are not in the actual
-    // puzzle input, they exist just to gather the number of paths.
-    let sum = lookup
-        .values()
-        .filter(|node| node.y == map.height)
-        .map(|node| node.value)
-        .sum::<usize>();
+fn part1(input: &str) -> Result<(), Error> {
+    let map = TachyonMap::from_input(input)?;
+    let splits = map.splitters_hit();
+    println!("Part 1: {}", splits.len());
+    return Ok(());
+}
 
+fn part2(input: &str) -> Result<(), Error> {
+    let map = TachyonMap::from_input(input)?;
+    let sum = map.count_timelines();
     println!("Part 2: {}", sum);
     return Ok(());
 }
@@ -308,3 +373,112 @@ fn main() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_splitter_counts() {
+        let map = TachyonMap::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        assert_eq!(map.total_splitters(), 22);
+        assert_eq!(map.splitters_hit().len(), 21);
+        assert_eq!(
+            map.splitters_hit().len() + map.unreachable_splitters().len(),
+            map.total_splitters()
+        );
+    }
+
+    #[test]
+    fn test_splitters_hit_sorted() {
+        let map = TachyonMap::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        let sorted = map.splitters_hit_sorted();
+        assert_eq!(sorted.len(), map.splitters_hit().len());
+        assert_eq!(
+            sorted,
+            vec![
+                (7, 2),
+                (6, 4),
+                (8, 4),
+                (5, 6),
+                (7, 6),
+                (9, 6),
+                (4, 8),
+                (6, 8),
+                (10, 8),
+                (3, 10),
+                (5, 10),
+                (9, 10),
+                (11, 10),
+                (2, 12),
+                (6, 12),
+                (12, 12),
+                (1, 14),
+                (3, 14),
+                (5, 14),
+                (7, 14),
+                (13, 14)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bottom_row() {
+        let map = TachyonMap::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        assert_eq!(map.count_timelines(), 40);
+
+        // A splitter on the last row: both of its beams leave the map right away.
+        let map = TachyonMap::from_input("..S..\n.....\n..^..").unwrap();
+        assert_eq!(map.splitters_hit(), HashSet::from([(2, 2)]));
+        let (lookup, first_x, first_y) = map.build_splitter_graph();
+        assert_eq!((first_x, first_y), (2, 2));
+        assert_eq!(lookup[&(2, 2)].left, Some((1, 3)));
+        assert_eq!(lookup[&(2, 2)].right, Some((3, 3)));
+        assert_eq!(map.count_timelines(), 2);
+
+        // The start on the last row.
+        let map = TachyonMap::from_input(".....\n..^..\n..S..").unwrap();
+        assert!(map.splitters_hit().is_empty());
+        assert_eq!(map.count_timelines(), 1);
+    }
+
+    #[test]
+    fn test_from_grid() {
+        let input = "...S...\n.......\n...^...\n.......\n..^.^..\n.......\n";
+        let mut grid = vec![vec!['.'; 7]; 6];
+        grid[0][3] = 'S';
+        grid[2][3] = '^';
+        grid[4][2] = '^';
+        grid[4][4] = '^';
+
+        let from_grid = TachyonMap::from_grid(&grid).unwrap();
+        let from_input = TachyonMap::from_input(input).unwrap();
+        assert_eq!(from_grid.splitters_hit(), from_input.splitters_hit());
+        assert_eq!(from_grid.splitters_hit().len(), 3);
+        assert_eq!(from_grid.count_timelines(), from_input.count_timelines());
+
+        grid[1].pop();
+        assert!(matches!(
+            TachyonMap::from_grid(&grid),
+            Err(Error::InvalidLineLength)
+        ));
+        grid[1].push('x');
+        assert!(matches!(
+            TachyonMap::from_grid(&grid),
+            Err(Error::InvalidCharacter('x'))
+        ));
+    }
+
+    #[test]
+    fn test_beam_fates() {
+        let map = TachyonMap::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        let fates = map.beam_fates();
+        let exiting = fates.iter().filter(|(_, exits)| *exits).count();
+        assert_eq!(exiting, 9);
+        // Every other beam ends at one of the splitters hit.
+        assert_eq!(fates.len() - exiting, map.splitters_hit().len());
+        for (beam, exits) in &fates {
+            assert_eq!(*exits, *beam.ys.end() == map.height);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::str::FromStr;
 use std::time::Instant;
 
 #[derive(Debug)]
@@ -7,19 +8,25 @@ enum Error {
     InvalidCoordinate(String),
     EmptyInput,
     NoSolutionFound,
+    NotEnoughCircuits,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+// Ordered by x, then y, then z, like `sort_boxes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 struct JunctionBox {
     x: i32,
     y: i32,
     z: i32,
 }
 
-impl JunctionBox {
-    fn from_input(line: &str) -> Result<JunctionBox, Error> {
+impl FromStr for JunctionBox {
+    type Err = Error;
+
+    // The coordinates can be separated by commas, whitespace or both.
+    fn from_str(line: &str) -> Result<JunctionBox, Error> {
         let coords: Vec<i32> = line
-            .split(',')
+            .split(|c| c == ',' || char::is_whitespace(c))
+            .filter(|s| !s.is_empty())
             .map(|s| {
                 s.parse::<i32>()
                     .map_err(|_| Error::InvalidCoordinate(line.to_string()))
@@ -34,7 +41,9 @@ impl JunctionBox {
             z: coords[2],
         })
     }
+}
 
+impl JunctionBox {
     // Calculate the euclidean distance between two junction boxes.
     fn distance(&self, other: &JunctionBox) -> f64 {
         let a = (self.x - other.x) as f64;
@@ -61,6 +70,18 @@ impl JunctionBox {
     }
 }
 
+// Orders connections by distance. Connections of equal distance are ordered by their boxes, so
+// which ones are made doesn't depend on the order of the input.
+fn compare_connections(
+    left: &(JunctionBox, JunctionBox, f64),
+    right: &(JunctionBox, JunctionBox, f64),
+) -> std::cmp::Ordering {
+    return left
+        .2
+        .total_cmp(&right.2)
+        .then_with(|| (left.0, left.1).cmp(&(right.0, right.1)));
+}
+
 fn add_pair_to_circuits(
     box1: JunctionBox,
     box2: JunctionBox,
@@ -112,6 +133,50 @@ fn circuit_size(
     num_connections: usize,
     num_circuits: usize,
 ) -> Result<usize, Error> {
+    // Get the sizes of the `num_circuits` largest circuits.
+    let mut sizes = circuit_sizes(boxes, num_connections)?;
+    sizes.truncate(num_circuits);
+    // Multiply them together.
+    let result = sizes.iter().product();
+    return Ok(result);
+}
+
+// Like `circuit_size`, but fails if there are fewer than `sizes_to_multiply` circuits instead of
+// multiplying whatever there is.
+#[allow(dead_code)]
+fn circuit_size_weighted(
+    boxes: &[JunctionBox],
+    num_connections: usize,
+    sizes_to_multiply: usize,
+) -> Result<usize, Error> {
+    let sizes = circuit_sizes(boxes, num_connections)?;
+    if sizes.len() < sizes_to_multiply {
+        return Err(Error::NotEnoughCircuits);
+    }
+    return Ok(sizes[..sizes_to_multiply].iter().product());
+}
+
+// The sizes of all circuits after making the `num_connections` shortest connections, largest
+// first. Boxes that aren't connected to anything are circuits of size 1.
+fn circuit_sizes(boxes: &[JunctionBox], num_connections: usize) -> Result<Vec<usize>, Error> {
+    // Add the connections to the circuits.
+    let mut circuits: Vec<HashSet<JunctionBox>> = Vec::new();
+    for (box1, box2) in shortest_connections(boxes, num_connections)? {
+        add_pair_to_circuits(box1, box2, &mut circuits);
+    }
+
+    let mut circuit_sizes = circuits.iter().map(|c| c.len()).collect::<Vec<usize>>();
+    let connected: usize = circuit_sizes.iter().sum();
+    circuit_sizes.extend(std::iter::repeat_n(1, boxes.len() - connected));
+    circuit_sizes.sort_by(|left, right| left.cmp(right).reverse());
+    return Ok(circuit_sizes);
+}
+
+// The `num_connections` shortest connections, each with its boxes in the order of `sort_boxes`.
+fn shortest_connections(
+    boxes: &[JunctionBox],
+    num_connections: usize,
+) -> Result<Vec<(JunctionBox, JunctionBox)>, Error> {
     if boxes.len() < 2 {
         return Err(Error::EmptyInput);
     }
@@ -129,67 +194,96 @@ fn circuit_size(
     }
 
     // Sort them by distance.
-    distances.sort_by(|left, right| left.2.total_cmp(&right.2));
+    distances.sort_by(compare_connections);
     // Truncate to the number of connections to make.
     distances.truncate(num_connections);
-
-    // Add the connections to the circuits.
-    let mut circuits: Vec<HashSet<JunctionBox>> = Vec::new();
-    for (box1, box2, _) in distances {
-        add_pair_to_circuits(box1, box2, &mut circuits);
-    }
-
-    // Get the sizes of the `num_circuits` largest circuits.
-    let mut circuit_sizes = circuits.iter().map(|c| c.len()).collect::<Vec<usize>>();
-    circuit_sizes.sort_by(|left, right| left.cmp(right).reverse());
-    circuit_sizes.truncate(num_circuits);
-    // Multiply them together.
-    let result = circuit_sizes.iter().product();
-    return Ok(result);
+    return Ok(distances
+        .into_iter()
+        .map(|(box1, box2, _)| (box1, box2))
+        .collect());
 }
 
-fn cable_length(boxes: &Vec<JunctionBox>) -> Result<i64, Error> {
+// The connections of the minimum spanning tree (Kruskal's algorithm), in the order they're made.
+fn mst_edges(boxes: &[JunctionBox]) -> Result<Vec<(JunctionBox, JunctionBox)>, Error> {
     if boxes.len() < 2 {
         return Err(Error::EmptyInput);
     }
 
     // Calculate all possible junction box distances.
-    let mut distances: Vec<(JunctionBox, JunctionBox, f64)> = Vec::new();
+    let mut distances: Vec<(usize, usize, f64)> = Vec::new();
     for start in 0..boxes.len() - 1 {
         for end in start + 1..boxes.len() {
-            let start_box = &boxes[start];
-            let end_box = &boxes[end];
-            let distance = start_box.distance(end_box);
-            let key = start_box.sort_boxes(end_box);
-            distances.push((key.0, key.1, distance));
+            distances.push((start, end, boxes[start].distance(&boxes[end])));
         }
     }
 
-    // Sort them by distance, reversed for `pop()`.
-    distances.sort_by(|left, right| left.2.total_cmp(&right.2).reverse());
+    // Sort them by distance, with the same order for ties as `shortest_connections`.
+    distances.sort_by(|left, right| {
+        let (left_box1, left_box2) = boxes[left.0].sort_boxes(&boxes[left.1]);
+        let (right_box1, right_box2) = boxes[right.0].sort_boxes(&boxes[right.1]);
+        compare_connections(
+            &(left_box1, left_box2, left.2),
+            &(right_box1, right_box2, right.2),
+        )
+    });
 
-    // Join them all until all junction boxes are connected and there is only one circuit.
-    let mut circuits: Vec<HashSet<JunctionBox>> = Vec::new();
-    let mut connected_boxes: HashSet<JunctionBox> = HashSet::new();
-    while let Some((box1, box2, _)) = distances.pop() {
-        add_pair_to_circuits(box1, box2, &mut circuits);
-        connected_boxes.insert(box1);
-        connected_boxes.insert(box2);
+    // Each box starts as its own circuit. `parents` leads to the box representing the circuit.
+    let mut parents: Vec<usize> = (0..boxes.len()).collect();
+    fn find(parents: &mut [usize], index: usize) -> usize {
+        let mut root = index;
+        while parents[root] != root {
+            root = parents[root];
+        }
+        parents[index] = root;
+        return root;
+    }
 
-        if circuits.len() == 1 && connected_boxes.len() == boxes.len() {
-            // All joined into one circuit!
-            return Ok(box1.x as i64 * box2.x as i64);
+    let mut edges = Vec::with_capacity(boxes.len() - 1);
+    for (start, end, _) in distances {
+        let root1 = find(&mut parents, start);
+        let root2 = find(&mut parents, end);
+        if root1 == root2 {
+            // Already in the same circuit.
+            continue;
+        }
+        parents[root2] = root1;
+        edges.push(boxes[start].sort_boxes(&boxes[end]));
+        if edges.len() == boxes.len() - 1 {
+            return Ok(edges);
         }
     }
 
     return Err(Error::NoSolutionFound);
 }
 
+// The connections of the minimum spanning tree as `x1,y1,z1 -> x2,y2,z2` lines.
+#[allow(dead_code)]
+fn mst_edges_text(boxes: &[JunctionBox]) -> Result<String, Error> {
+    let lines: Vec<String> = mst_edges(boxes)?
+        .iter()
+        .map(|(box1, box2)| {
+            format!(
+                "{},{},{} -> {},{},{}",
+                box1.x, box1.y, box1.z, box2.x, box2.y, box2.z
+            )
+        })
+        .collect();
+    return Ok(lines.join("\n"));
+}
+
+fn cable_length(boxes: &Vec<JunctionBox>) -> Result<i64, Error> {
+    // The last connection of the minimum spanning tree is the one that joins everything into a
+    // single circuit.
+    let edges = mst_edges(boxes)?;
+    let (box1, box2) = edges.last().ok_or(Error::NoSolutionFound)?;
+    return Ok(box1.x as i64 * box2.x as i64);
+}
+
 fn part1(input: &str) -> Result<(), Error> {
     let boxes = input
         .trim()
         .lines()
-        .map(|line| JunctionBox::from_input(line))
+        .map(|line| line.parse::<JunctionBox>())
         .collect::<Result<Vec<JunctionBox>, Error>>()?;
 
     let result = circuit_size(&boxes, 1000, 3)?;
@@ -201,7 +295,7 @@ fn part2(input: &str) -> Result<(), Error> {
     let boxes = input
         .trim()
         .lines()
-        .map(|line| JunctionBox::from_input(line))
+        .map(|line| line.parse::<JunctionBox>())
         .collect::<Result<Vec<JunctionBox>, Error>>()?;
 
     let result = cable_length(&boxes)?;
@@ -222,3 +316,117 @@ fn main() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_junction_box() {
+        let parsed = "162,817,812".parse::<JunctionBox>().unwrap();
+        assert_eq!(
+            parsed,
+            JunctionBox {
+                x: 162,
+                y: 817,
+                z: 812
+            }
+        );
+
+        assert!(matches!(
+            "162,817".parse::<JunctionBox>(),
+            Err(Error::InvalidCoordinate(_))
+        ));
+        assert!(matches!(
+            "162,a,812".parse::<JunctionBox>(),
+            Err(Error::InvalidCoordinate(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_separators() {
+        let expected = JunctionBox { x: 1, y: 2, z: 3 };
+        for line in ["1,2,3", "1 2 3", "1\t2\t3", "1, 2, 3", " 1  2\t 3 "] {
+            assert_eq!(line.parse::<JunctionBox>().unwrap(), expected, "{:?}", line);
+        }
+        assert_eq!(
+            "-162 817 812".parse::<JunctionBox>().unwrap(),
+            JunctionBox {
+                x: -162,
+                y: 817,
+                z: 812
+            }
+        );
+
+        assert!(matches!(
+            "1 2".parse::<JunctionBox>(),
+            Err(Error::InvalidCoordinate(_))
+        ));
+        assert!(matches!(
+            "1 2 3 4".parse::<JunctionBox>(),
+            Err(Error::InvalidCoordinate(_))
+        ));
+        assert!(matches!(
+            "1;2;3".parse::<JunctionBox>(),
+            Err(Error::InvalidCoordinate(_))
+        ));
+    }
+
+    fn parse_boxes(input: &str) -> Vec<JunctionBox> {
+        return input
+            .trim()
+            .lines()
+            .map(|line| line.parse::<JunctionBox>().unwrap())
+            .collect();
+    }
+
+    #[test]
+    fn test_mst_edges_text() {
+        let boxes = parse_boxes(include_str!("../rsc/sample1.txt"));
+        assert_eq!(circuit_size(&boxes, 10, 3).unwrap(), 40);
+        assert_eq!(cable_length(&boxes).unwrap(), 25272);
+
+        let text = mst_edges_text(&boxes).unwrap();
+        assert_eq!(text.lines().count(), boxes.len() - 1);
+        assert_eq!(text.lines().next(), Some("162,817,812 -> 425,690,689"));
+        assert_eq!(text.lines().last(), Some("117,168,530 -> 216,146,977"));
+    }
+
+    #[test]
+    fn test_circuit_size_weighted() {
+        let boxes = parse_boxes(include_str!("../rsc/sample1.txt"));
+        // After 10 connections there are circuits of 5, 4, 2 and 2 boxes, and 7 single boxes.
+        assert_eq!(circuit_size_weighted(&boxes, 10, 3).unwrap(), 40);
+        assert_eq!(circuit_size_weighted(&boxes, 10, 11).unwrap(), 80);
+        assert!(matches!(
+            circuit_size_weighted(&boxes, 10, 12),
+            Err(Error::NotEnoughCircuits)
+        ));
+        assert_eq!(circuit_size(&boxes, 10, 12).unwrap(), 80);
+    }
+
+    #[test]
+    fn test_equal_distances() {
+        // A-B, B-C and D-E are all 10 apart. The first two connections must be A-B and B-C, no
+        // matter in which order the boxes are given.
+        let input = "0,0,0\n10,0,0\n20,0,0\n100,0,0\n110,0,0";
+        let boxes = parse_boxes(input);
+        let expected = vec![(boxes[0], boxes[1]), (boxes[1], boxes[2])];
+        for order in [
+            [0, 1, 2, 3, 4],
+            [3, 4, 0, 1, 2],
+            [4, 3, 2, 1, 0],
+            [2, 4, 1, 3, 0],
+        ] {
+            let permuted: Vec<JunctionBox> = order.iter().map(|index| boxes[*index]).collect();
+            assert_eq!(shortest_connections(&permuted, 2).unwrap(), expected);
+            assert_eq!(circuit_sizes(&permuted, 2).unwrap(), vec![3, 1, 1]);
+            assert_eq!(
+                mst_edges(&permuted).unwrap()[..2],
+                expected[..],
+                "{:?}",
+                order
+            );
+        }
+    }
+}
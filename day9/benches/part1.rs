@@ -0,0 +1,28 @@
+use aoc::{Map, format_polygon, gen_polygon};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+// Run with `cargo bench`. That both give the same area is checked by
+// `test_max_area_simple_random`. The brute force stops at 10 000 points: at 100 000 it checks
+// five billion pairs, which takes far too long for criterion's samples.
+fn bench_part1(c: &mut Criterion) {
+    let mut group = c.benchmark_group("part1");
+    group.sample_size(10);
+    for n_vertices in [1000, 10_000, 100_000] {
+        let tiles = gen_polygon(n_vertices, 1_000_000_000, 42).unwrap();
+        let map = Map::from_input(&format_polygon(&tiles)).unwrap();
+        group.bench_with_input(BenchmarkId::new("frontiers", n_vertices), &map, |b, map| {
+            b.iter(|| map.max_area_simple().unwrap())
+        });
+        if n_vertices <= 10_000 {
+            group.bench_with_input(
+                BenchmarkId::new("brute force", n_vertices),
+                &map,
+                |b, map| b.iter(|| map.max_area_simple_brute_force().unwrap()),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_part1);
+criterion_main!(benches);
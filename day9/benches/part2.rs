@@ -0,0 +1,23 @@
+use aoc::{Map, format_polygon, gen_polygon};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+// Run with `cargo bench`. That both give the same rectangle is checked by
+// `test_parallel_matches_serial`.
+fn bench_part2(c: &mut Criterion) {
+    let mut group = c.benchmark_group("part2");
+    group.sample_size(10);
+    for n_vertices in [100, 1000, 5000] {
+        let tiles = gen_polygon(n_vertices, 100_000, 1).unwrap();
+        let map = Map::from_input(&format_polygon(&tiles)).unwrap();
+        group.bench_with_input(BenchmarkId::new("serial", n_vertices), &map, |b, map| {
+            b.iter(|| map.max_area_compressed(false).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("parallel", n_vertices), &map, |b, map| {
+            b.iter(|| map.max_area_compressed(true).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_part2);
+criterion_main!(benches);
@@ -0,0 +1,1980 @@
+use rayon::prelude::*;
+use rng::next_random;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidInput {
+        line: usize,
+        text: String,
+        reason: String,
+    },
+    NotEnoughTiles,
+    NoValidArea,
+    InvalidArgument(String),
+    NotRectilinear {
+        index: usize,
+        a: Point,
+        b: Point,
+    },
+    DegenerateSegment {
+        index: usize,
+    },
+    SelfIntersecting {
+        first: usize,
+        second: usize,
+    },
+    WriteFailed(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput { line, text, reason } => {
+                write!(f, "Invalid input in line {}: {} ('{}')", line, reason, text)
+            }
+            Error::NotEnoughTiles => write!(f, "Not enough tiles"),
+            Error::NoValidArea => write!(f, "No valid area"),
+            Error::InvalidArgument(arg) => write!(f, "Invalid argument: {}", arg),
+            Error::NotRectilinear { index, a, b } => write!(
+                f,
+                "Segment {} from {:?} to {:?} is not horizontal or vertical",
+                index, a, b
+            ),
+            Error::DegenerateSegment { index } => write!(f, "Segment {} has length 0", index),
+            Error::SelfIntersecting { first, second } => {
+                write!(f, "Segments {} and {} intersect", first, second)
+            }
+            Error::WriteFailed(message) => write!(f, "Write failed: {}", message),
+        }
+    }
+}
+
+// A snapshot of the progress of the part 2 search.
+#[derive(Debug, Clone)]
+pub struct ProgressUpdate {
+    // Candidate pairs checked so far, out of `total`.
+    pub done: usize,
+    pub total: usize,
+    pub best_area: i128,
+    pub elapsed: Duration,
+    // Extrapolated from the elapsed time, `None` until something has been done.
+    pub remaining: Option<Duration>,
+}
+
+// Receives the progress updates of the part 2 search. Might get called from several threads.
+pub trait ProgressReporter: Sync {
+    fn report(&self, update: &ProgressUpdate);
+}
+
+// Counts the checked pairs and passes updates to the reporter, at most once per `interval`
+// (except for the final one). Updates are serialized so that `done` never goes down and the
+// estimated remaining time never goes up, even though the workers finish in any order.
+struct ProgressTracker<'a> {
+    reporter: &'a dyn ProgressReporter,
+    total: usize,
+    interval: Duration,
+    start: Instant,
+    done: AtomicUsize,
+    // The last reported update, and when it was reported.
+    last: Mutex<Option<(Instant, ProgressUpdate)>>,
+}
+
+#[cfg(test)]
+#[derive(Debug, PartialEq)]
+enum HitResult {
+    Miss,
+    Hit,
+    OnLine,
+}
+
+pub type Point = (i64, i64);
+
+pub struct Map {
+    // The outer boundary.
+    tiles: Vec<Point>,
+    // Further loops inside the outer boundary whose insides are not part of the area.
+    holes: Vec<Vec<Point>>,
+}
+
+// How many vertices `Map::from_input_normalized` dropped from the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Normalization {
+    // Tiles that repeat the previous one, including a closing tile that repeats the first one.
+    pub duplicates: usize,
+    // Tiles in the middle of a straight edge.
+    pub collinear: usize,
+}
+
+// The largest rectangle found, spanned by the two tiles `p1` and `p2` (uncompressed).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BestRect {
+    pub p1: Point,
+    pub p2: Point,
+    // Large coordinates can overflow i64 when multiplied.
+    pub area: i128,
+}
+
+// Which parts of the compressed grid are inside the polygon, determined once via flood fill. The
+// grid has twice the resolution of the compressed space so the gaps between compressed
+// coordinates are represented as well, plus a border that's always outside: compressed coordinate
+// `c` has index `2 * c + 1`.
+struct InsideGrid {
+    width: usize,
+    inside: Vec<bool>,
+    // 2D prefix sum over `inside`, with an extra row and column of zeroes at the start. Allows
+    // checking whether a whole rectangle is inside in O(1).
+    sums: Vec<usize>,
+}
+
+struct CoordinateCompressor {
+    // Tiles of all loops in compressed space.
+    tiles: Vec<Point>,
+    // The loops (outer boundary first, then the holes) in compressed space.
+    loops: Vec<Vec<Point>>,
+    // Map of the tiles to uncompressed space.
+    compressed_points: HashMap<Point, Point>,
+    // Sorted unique coordinates in uncompressed space, indexed by compressed coordinate.
+    xs: Vec<i64>,
+    ys: Vec<i64>,
+}
+
+// Parses "x,y", ignoring whitespace around the numbers. Otherwise returns what's wrong with the
+// line, `Map::from_input` adds where it is.
+pub fn parse_line(line: &str) -> Result<Point, String> {
+    let parts: Vec<&str> = line.split(',').map(|part| part.trim()).collect();
+    let [x, y] = parts[..] else {
+        return Err(if parts.len() > 2 {
+            "more than two numbers".to_string()
+        } else {
+            "expected two numbers separated by a comma".to_string()
+        });
+    };
+
+    let parse = |part: &str| {
+        part.parse::<i64>()
+            .map_err(|_| format!("'{}' is not a number", part))
+    };
+    return Ok((parse(x)?, parse(y)?));
+}
+
+// Whether `b` lies strictly between `a` and `c` on a horizontal or vertical line, making it a
+// redundant vertex of the outline.
+fn is_collinear(a: Point, b: Point, c: Point) -> bool {
+    if a.0 == b.0 && b.0 == c.0 {
+        return (a.1 < b.1 && b.1 < c.1) || (a.1 > b.1 && b.1 > c.1);
+    }
+    if a.1 == b.1 && b.1 == c.1 {
+        return (a.0 < b.0 && b.0 < c.0) || (a.0 > b.0 && b.0 > c.0);
+    }
+    return false;
+}
+
+// Removes the vertices of a closed outline that just split a straight edge in two, so that ray
+// casting doesn't count the crossing at the shared vertex twice. The removed points are still
+// tiles, so this is only applied to the edge list, not the tiles themselves.
+fn merge_collinear(points: &[Point]) -> Vec<Point> {
+    let mut merged = points.to_vec();
+    loop {
+        let before = merged.len();
+        let mut index = 0;
+        while index < merged.len() && merged.len() >= 3 {
+            let count = merged.len();
+            let previous = merged[(index + count - 1) % count];
+            let next = merged[(index + 1) % count];
+            if is_collinear(previous, merged[index], next) {
+                merged.remove(index);
+                // The previous vertex might have become redundant now.
+                index = index.saturating_sub(1);
+            } else {
+                index += 1;
+            }
+        }
+        if merged.len() == before {
+            return merged;
+        }
+    }
+}
+
+// Generates a random closed rectilinear polygon with exactly `n_vertices` corners and all
+// coordinates in `0..=extent`, to get inputs of controlled size. It's made of adjacent columns
+// whose bottoms are in the lower half and whose tops are in the upper half of the extent, so
+// neighboring columns always overlap and the outline can't touch itself. Neighboring columns
+// differ in both top and bottom, giving four corners per column, except that the last two columns
+// share their bottom if `n_vertices` isn't a multiple of four.
+pub fn gen_polygon(n_vertices: usize, extent: i64, seed: u64) -> Result<Vec<Point>, Error> {
+    let columns = n_vertices.div_ceil(4);
+    if n_vertices < 4 || !n_vertices.is_multiple_of(2) || extent < 4 || (extent as usize) < columns
+    {
+        return Err(Error::InvalidArgument(format!(
+            "Can't generate {} vertices within {}",
+            n_vertices, extent
+        )));
+    }
+
+    let mut state = seed.max(1);
+    // Picks a value from `range` that differs from `previous`.
+    let mut pick = |range: std::ops::Range<i64>, previous: Option<i64>| {
+        let count = (range.end - range.start) as u64;
+        let Some(previous) = previous else {
+            return range.start + (next_random(&mut state) % count) as i64;
+        };
+        let value = range.start + (next_random(&mut state) % (count - 1)) as i64;
+        return if value >= previous { value + 1 } else { value };
+    };
+
+    let middle = extent / 2;
+    let mut lows: Vec<i64> = Vec::with_capacity(columns);
+    let mut highs: Vec<i64> = Vec::with_capacity(columns);
+    for column in 0..columns {
+        if column == columns - 1 && n_vertices % 4 == 2 {
+            lows.push(lows[column - 1]);
+        } else {
+            lows.push(pick(0..middle, lows.last().copied()));
+        }
+        highs.push(pick(middle + 1..extent + 1, highs.last().copied()));
+    }
+
+    // Distinct x coordinates, using Floyd's algorithm for sampling without replacement.
+    let count = extent as u64 + 1;
+    let mut xs = BTreeSet::new();
+    for j in (count - columns as u64 - 1)..count {
+        let x = (next_random(&mut state) % (j + 1)) as i64;
+        if !xs.insert(x) {
+            xs.insert(j as i64);
+        }
+    }
+    let xs: Vec<i64> = xs.into_iter().collect();
+
+    // Along the tops from left to right, then back along the bottoms. The shared bottom is merged
+    // into one edge afterwards, its middle corner shows up twice.
+    let mut tiles = Vec::with_capacity(4 * columns);
+    for column in 0..columns {
+        tiles.push((xs[column], highs[column]));
+        tiles.push((xs[column + 1], highs[column]));
+    }
+    for column in (0..columns).rev() {
+        tiles.push((xs[column + 1], lows[column]));
+        tiles.push((xs[column], lows[column]));
+    }
+    tiles.dedup();
+    return Ok(merge_collinear(&tiles));
+}
+
+// Formats the tiles like the puzzle input.
+pub fn format_polygon(tiles: &[Point]) -> String {
+    return tiles
+        .iter()
+        .map(|(x, y)| format!("{},{}\n", x, y))
+        .collect();
+}
+
+fn area(p1: Point, p2: Point) -> i128 {
+    (p1.0.max(p2.0) as i128 - p1.0.min(p2.0) as i128 + 1)
+        * (p1.1.max(p2.1) as i128 - p1.1.min(p2.1) as i128 + 1)
+}
+
+impl BestRect {
+    fn new(p1: Point, p2: Point) -> BestRect {
+        return BestRect {
+            p1,
+            p2,
+            area: area(p1, p2),
+        };
+    }
+
+    fn upper_left(&self) -> Point {
+        return (self.p1.0.min(self.p2.0), self.p1.1.min(self.p2.1));
+    }
+
+    fn lower_right(&self) -> Point {
+        return (self.p1.0.max(self.p2.0), self.p1.1.max(self.p2.1));
+    }
+
+    // Larger area wins. For equal areas, the smaller upper-left corner wins, then the smaller
+    // lower-right corner, so the result does not depend on the order in which pairs are checked.
+    fn is_better_than(&self, other: &Option<BestRect>) -> bool {
+        match other {
+            None => return true,
+            Some(other) => {
+                if self.area != other.area {
+                    return self.area > other.area;
+                }
+                return (self.upper_left(), self.lower_right())
+                    < (other.upper_left(), other.lower_right());
+            }
+        }
+    }
+}
+
+impl Map {
+    // The input consists of one or more loops separated by blank lines. The first one is the
+    // outer boundary, the others are holes.
+    pub fn from_input(input: &str) -> Result<Map, Error> {
+        return Map::from_input_normalized(input).map(|(map, _)| map);
+    }
+
+    // Like `from_input`, but also returns how many redundant vertices were removed.
+    pub fn from_input_normalized(input: &str) -> Result<(Map, Normalization), Error> {
+        let mut blocks: Vec<Vec<Point>> = vec![Vec::new()];
+        for (index, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                // Blank lines separate the loops. Several of them in a row, or at the end of the
+                // input, don't start further loops.
+                if blocks.last().is_some_and(|block| !block.is_empty()) {
+                    blocks.push(Vec::new());
+                }
+                continue;
+            }
+
+            let point = parse_line(line).map_err(|reason| Error::InvalidInput {
+                line: index + 1,
+                text: line.to_string(),
+                reason,
+            })?;
+            blocks.last_mut().unwrap().push(point);
+        }
+        if blocks.last().is_some_and(|block| block.is_empty()) {
+            blocks.pop();
+        }
+
+        let mut loops = Vec::new();
+        let mut normalization = Normalization::default();
+        for coords in blocks {
+            // Repeating the previous tile doesn't add anything but would result in an edge of
+            // length 0.
+            let mut tiles: Vec<Point> = Vec::with_capacity(coords.len());
+            for point in &coords {
+                if tiles.last() != Some(point) {
+                    tiles.push(*point);
+                }
+            }
+            while tiles.len() > 1 && tiles.first() == tiles.last() {
+                tiles.pop();
+            }
+
+            normalization.duplicates += coords.len() - tiles.len();
+            normalization.collinear += tiles.len() - merge_collinear(&tiles).len();
+            Map::validate_segments(&tiles)?;
+            loops.push(tiles);
+        }
+
+        let mut loops = loops.into_iter();
+        let tiles = loops.next().unwrap_or_default();
+        let map = Map {
+            tiles,
+            holes: loops.collect(),
+        };
+        return Ok((map, normalization));
+    }
+
+    // Adds a tile to the end of the outer boundary, so it's connected to the previous last tile
+    // and to the first one. Nothing is cached between queries, every query compresses the
+    // coordinates anew and sees the edit. The edges are not validated, since it can take several
+    // edits to get from one valid polygon to another; see `validate_loops`.
+    pub fn push_vertex(&mut self, p: Point) {
+        self.tiles.push(p);
+    }
+
+    // Removes the last tile of the outer boundary, like `push_vertex`.
+    pub fn pop_vertex(&mut self) -> Option<Point> {
+        return self.tiles.pop();
+    }
+
+    // The same checks as `from_input` does on each loop, for after editing.
+    pub fn validate_loops(&self) -> Result<(), Error> {
+        for tiles in self.loops() {
+            Map::validate_segments(tiles)?;
+        }
+        return Ok(());
+    }
+
+    // The outer boundary followed by the holes.
+    fn loops(&self) -> impl Iterator<Item = &Vec<Point>> {
+        return std::iter::once(&self.tiles).chain(self.holes.iter());
+    }
+
+    // The tiles of all loops.
+    fn all_tiles(&self) -> Vec<Point> {
+        return self.loops().flatten().copied().collect();
+    }
+
+    // Each pair of consecutive tiles (including last -> first) must be a horizontal or vertical
+    // line, otherwise the ray casting in `hits_line` doesn't work.
+    fn validate_segments(tiles: &[Point]) -> Result<(), Error> {
+        for index in 0..tiles.len() {
+            let a = tiles[index];
+            let b = tiles[(index + 1) % tiles.len()];
+            if a == b {
+                return Err(Error::DegenerateSegment { index });
+            }
+            if a.0 != b.0 && a.1 != b.1 {
+                return Err(Error::NotRectilinear { index, a, b });
+            }
+        }
+        return Ok(());
+    }
+
+    // Part 1 only needs two tiles, but for part 2 the outer boundary has to be an actual polygon.
+    // With just two distinct tiles, the edges would run back and forth between them. The error
+    // points at the outer boundary as a whole, which starts in the first line.
+    fn validate_enclosing(&self) -> Result<(), Error> {
+        let distinct: BTreeSet<&Point> = self.tiles.iter().collect();
+        if distinct.len() < 3 {
+            let tiles: Vec<String> = self
+                .tiles
+                .iter()
+                .map(|(x, y)| format!("{},{}", x, y))
+                .collect();
+            return Err(Error::InvalidInput {
+                line: 1,
+                text: tiles.join(" "),
+                reason: format!(
+                    "part 2 needs at least 3 distinct tiles, found {}",
+                    distinct.len()
+                ),
+            });
+        }
+        return Ok(());
+    }
+
+    // The total length of the outer boundary's edges, including the one from the last tile back
+    // to the first. The edges are horizontal or vertical, so each is a Manhattan distance.
+    pub fn perimeter(&self) -> i64 {
+        let mut perimeter = 0;
+        for (index, a) in self.tiles.iter().enumerate() {
+            let b = self.tiles[(index + 1) % self.tiles.len()];
+            perimeter += (a.0 - b.0).abs() + (a.1 - b.1).abs();
+        }
+        return perimeter;
+    }
+
+    // The number of tiles strictly inside the outer boundary, via Pick's theorem: the shoelace
+    // area A of a polygon with its corners on tiles, with B tiles on its boundary, encloses
+    // I = A - B/2 + 1 tiles. The edges are horizontal or vertical, so B is the perimeter. Holes
+    // are ignored, like in `perimeter`. Independent of the ray casting, to cross-check it.
+    pub fn interior_point_count(&self) -> i64 {
+        let mut doubled_area: i128 = 0;
+        for (index, a) in self.tiles.iter().enumerate() {
+            let b = self.tiles[(index + 1) % self.tiles.len()];
+            doubled_area += a.0 as i128 * b.1 as i128 - b.0 as i128 * a.1 as i128;
+        }
+        return ((doubled_area.abs() - self.perimeter() as i128 + 2) / 2) as i64;
+    }
+
+    // Checks that no two edges of the polygon (including its holes) touch each other, except for
+    // neighboring edges sharing their common vertex. Simple O(n²) check of all pairs. The
+    // segments are numbered across all loops.
+    pub fn validate_no_self_intersections(&self) -> Result<(), Error> {
+        // The segments, and the range of segments of the loop each one belongs to.
+        let mut segments = Vec::new();
+        let mut loop_ranges = Vec::new();
+        for tiles in self.loops() {
+            if tiles.len() < 3 {
+                continue;
+            }
+            let start = segments.len();
+            for index in 0..tiles.len() {
+                segments.push((tiles[index], tiles[(index + 1) % tiles.len()]));
+            }
+            for _ in 0..tiles.len() {
+                loop_ranges.push((start, segments.len() - 1));
+            }
+        }
+
+        let count = segments.len();
+        for first in 0..count.saturating_sub(1) {
+            for second in (first + 1)..count {
+                let (a1, a2) = segments[first];
+                let (b1, b2) = segments[second];
+                let (loop_first, loop_last) = loop_ranges[first];
+                let adjacent = loop_ranges[second] == loop_ranges[first]
+                    && (second == first + 1 || (first == loop_first && second == loop_last));
+                if adjacent {
+                    // Neighbors always share a vertex. They only overlap if they are parallel and
+                    // the second one turns back onto the first.
+                    let (shared, a_other, b_other) = if second == first + 1 {
+                        (a2, a1, b2)
+                    } else {
+                        (a1, a2, b1)
+                    };
+                    let a_direction = (
+                        (a_other.0 - shared.0).signum(),
+                        (a_other.1 - shared.1).signum(),
+                    );
+                    let b_direction = (
+                        (b_other.0 - shared.0).signum(),
+                        (b_other.1 - shared.1).signum(),
+                    );
+                    if a_direction == b_direction {
+                        return Err(Error::SelfIntersecting { first, second });
+                    }
+                } else if Map::segments_touch((a1, a2), (b1, b2)) {
+                    return Err(Error::SelfIntersecting { first, second });
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    // Axis-aligned segments are their own bounding boxes, so they touch iff the boxes overlap.
+    fn segments_touch(a: (Point, Point), b: (Point, Point)) -> bool {
+        let a_min = (a.0.0.min(a.1.0), a.0.1.min(a.1.1));
+        let a_max = (a.0.0.max(a.1.0), a.0.1.max(a.1.1));
+        let b_min = (b.0.0.min(b.1.0), b.0.1.min(b.1.1));
+        let b_max = (b.0.0.max(b.1.0), b.0.1.max(b.1.1));
+        return a_min.0 <= b_max.0
+            && b_min.0 <= a_max.0
+            && a_min.1 <= b_max.1
+            && b_min.1 <= a_max.1;
+    }
+
+    pub fn max_area_simple(&self) -> Result<BestRect, Error> {
+        // The corners of the best rectangle are opposite corners: either upper-left and
+        // lower-right, or upper-right and lower-left. For the first case, if some other tile is
+        // further up and left than the upper-left corner, the rectangle using that tile instead is
+        // strictly larger. So only the tiles that aren't "dominated" that way need to be
+        // considered, which form a staircase (the Pareto frontier). Same for the other corners.
+        // Finding the frontiers is O(n log n), then the pairs across them are checked.
+        let tiles = self.all_tiles();
+        if tiles.len() < 2 {
+            return Err(Error::NotEnoughTiles);
+        }
+
+        let upper_left = Map::frontier(&tiles, |p| (p.0, p.1), |y, extreme| y < extreme);
+        let lower_right = Map::frontier(&tiles, |p| (-p.0, -p.1), |y, extreme| y > extreme);
+        let upper_right = Map::frontier(&tiles, |p| (-p.0, p.1), |y, extreme| y < extreme);
+        let lower_left = Map::frontier(&tiles, |p| (p.0, -p.1), |y, extreme| y > extreme);
+
+        let mut best = None;
+        for (firsts, seconds) in [(&upper_left, &lower_right), (&upper_right, &lower_left)] {
+            for &first in firsts {
+                for &second in seconds {
+                    let (start, end) = (first.min(second), first.max(second));
+                    let candidate = BestRect::new(tiles[start], tiles[end]);
+                    if candidate.is_better_than(&best) {
+                        best = Some(candidate);
+                    }
+                }
+            }
+        }
+
+        return best.ok_or(Error::NotEnoughTiles);
+    }
+
+    // Indices of the tiles on one of the staircases used by `max_area_simple`. The tiles are
+    // visited in the order given by `key`, and a tile is on the frontier if its y is beyond the
+    // extreme y of all tiles visited before, according to `beyond`.
+    fn frontier(
+        tiles: &[Point],
+        key: impl Fn(&Point) -> (i64, i64),
+        beyond: impl Fn(i64, i64) -> bool,
+    ) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..tiles.len()).collect();
+        order.sort_by_key(|index| key(&tiles[*index]));
+
+        let mut frontier = Vec::new();
+        let mut extreme: Option<i64> = None;
+        for index in order {
+            let y = tiles[index].1;
+            if extreme.is_none_or(|extreme| beyond(y, extreme)) {
+                frontier.push(index);
+                extreme = Some(y);
+            }
+        }
+        return frontier;
+    }
+
+    // The original O(n²) version of `max_area_simple`, kept to cross-check and benchmark it.
+    pub fn max_area_simple_brute_force(&self) -> Result<BestRect, Error> {
+        let tiles = self.all_tiles();
+        if tiles.len() < 2 {
+            return Err(Error::NotEnoughTiles);
+        }
+
+        let mut best = None;
+        for start in 0..tiles.len() - 1 {
+            for end in (start + 1)..tiles.len() {
+                let candidate = BestRect::new(tiles[start], tiles[end]);
+                if candidate.is_better_than(&best) {
+                    best = Some(candidate);
+                }
+            }
+        }
+
+        return best.ok_or(Error::NotEnoughTiles);
+    }
+
+    pub fn max_area_complicated(&self) -> Result<BestRect, Error> {
+        return self.max_area_compressed(true);
+    }
+
+    pub fn max_area_compressed(&self, parallel: bool) -> Result<BestRect, Error> {
+        return self.max_area_reporting(parallel, None);
+    }
+
+    // Like `max_area_compressed`, optionally reporting the progress every `interval`.
+    pub fn max_area_reporting(
+        &self,
+        parallel: bool,
+        progress: Option<(&dyn ProgressReporter, Duration)>,
+    ) -> Result<BestRect, Error> {
+        // Originally this was ray casting every point along the sides of an area to check
+        // whether they're inside the polygon. Now the inside of the polygon is determined once
+        // with a flood fill, which makes checking an area a simple lookup (see `InsideGrid`).
+        //
+        // To optimize the checks, the coordinates are compressed: the input contains coordinates
+        // with large-ish components, which made the ray casting algorithm expensive. However,
+        // there are much less DISTINCT coordinates, and by mapping the large components to the
+        // smallest possible ones, the ray casting algorithm runs MUCH faster: This compression
+        // brought the runtime down to ~65ms from about 30 seconds!
+        //
+        // The pairs can be checked independently, so they're split up by their first tile and
+        // checked in parallel. The workers only read the precomputed grid, so there's no shared
+        // mutable cache. The best area found so far is shared between the workers only for
+        // pruning, the actual winner is determined at the end.
+
+        self.validate_enclosing()?;
+
+        let compressor = CoordinateCompressor::from_map(self);
+        let grid = InsideGrid::new(&compressor, &compressor.lines());
+
+        let count = compressor.tiles.len();
+        let tracker = progress.map(|(reporter, interval)| {
+            ProgressTracker::new(reporter, count * (count - 1) / 2, interval)
+        });
+
+        let is_valid = |p1, p2| grid.is_valid_area(p1, p2);
+        let best = Map::search_best_rect(&compressor, &is_valid, parallel, tracker.as_ref());
+        return best.ok_or(Error::NoValidArea);
+    }
+
+    // The largest valid rectangle that covers the given point (in uncompressed space), if any.
+    pub fn max_area_containing(&self, point: Point) -> Result<Option<BestRect>, Error> {
+        self.validate_enclosing()?;
+
+        let compressor = CoordinateCompressor::from_map(self);
+        let grid = InsideGrid::new(&compressor, &compressor.lines());
+
+        // No need to search if the point isn't inside the polygon at all.
+        let Some((x, y)) = compressor.grid_cell(point) else {
+            return Ok(None);
+        };
+        if !grid.inside[y * grid.width + x] {
+            return Ok(None);
+        }
+
+        let is_valid = |p1: Point, p2: Point| {
+            let span = |a: i64, b: i64| (a.min(b) as usize * 2 + 1)..=(a.max(b) as usize * 2 + 1);
+            return span(p1.0, p2.0).contains(&x)
+                && span(p1.1, p2.1).contains(&y)
+                && grid.is_valid_area(p1, p2);
+        };
+        return Ok(Map::search_best_rect(&compressor, &is_valid, true, None));
+    }
+
+    // Finds the best rectangle with two tiles as opposite corners, in compressed space, that
+    // passes the `is_valid` check.
+    fn search_best_rect(
+        compressor: &CoordinateCompressor,
+        is_valid: &(impl Fn(Point, Point) -> bool + Sync),
+        parallel: bool,
+        progress: Option<&ProgressTracker>,
+    ) -> Option<BestRect> {
+        let best_area = AtomicI64::new(0);
+        let search = |start| {
+            let best = Map::best_rect_from(start, compressor, is_valid, &best_area);
+            if let Some(progress) = progress {
+                let pairs = compressor.tiles.len() - start - 1;
+                progress.advance(pairs, best_area.load(Ordering::Relaxed) as i128);
+            }
+            return best;
+        };
+        let pick = |a: BestRect, b: BestRect| if a.is_better_than(&Some(b)) { a } else { b };
+        let starts = 0..compressor.tiles.len() - 1;
+        if parallel {
+            return starts.into_par_iter().filter_map(search).reduce_with(pick);
+        } else {
+            return starts.filter_map(search).reduce(pick);
+        }
+    }
+
+    // Finds the best valid rectangle that has the tile at index `start` as first corner and a
+    // tile with a larger index as the second corner.
+    fn best_rect_from(
+        start: usize,
+        compressor: &CoordinateCompressor,
+        is_valid: &impl Fn(Point, Point) -> bool,
+        best_area: &AtomicI64,
+    ) -> Option<BestRect> {
+        let mut best = None;
+        for end in (start + 1)..compressor.tiles.len() {
+            let p1 = compressor.tiles[start];
+            let p2 = compressor.tiles[end];
+
+            // Need to calculate the area in uncompressed space.
+            let candidate = BestRect::new(
+                compressor.decompress_tile(&p1),
+                compressor.decompress_tile(&p2),
+            );
+            // Areas equal to the global best must still be checked as they might win the
+            // tie-break. The shared best is saturated at i64::MAX, which only makes the pruning
+            // less effective for gigantic areas.
+            if candidate.area < best_area.load(Ordering::Relaxed) as i128
+                || !candidate.is_better_than(&best)
+            {
+                // Not worth investigating.
+                continue;
+            }
+
+            if !is_valid(p1, p2) {
+                continue;
+            }
+
+            let saturated = i64::try_from(candidate.area).unwrap_or(i64::MAX);
+            best_area.fetch_max(saturated, Ordering::Relaxed);
+            best = Some(candidate);
+        }
+        return best;
+    }
+
+    // Checks whether the area spanned by the two points is completely inside the polygon, in
+    // compressed space, without any precomputation. Superseded by `InsideGrid`, kept to cross-check
+    // it. Instead of checking every point, look at the polygon's edges: if none of them passes
+    // through the inside of the area, the area is either completely inside or completely outside
+    // the polygon, and checking one point decides which. Degenerate areas (a single row or column)
+    // have no inside, so their segment is checked piece by piece.
+    //
+    // The points that get checked all have at least one ".5" component so the rays can never
+    // pass through a vertex of the polygon. To keep integers, they're handled in doubled
+    // coordinates.
+    #[cfg(test)]
+    fn is_valid_area(p1: Point, p2: Point, lines: &Vec<(Point, Point)>) -> bool {
+        let min_x = p1.0.min(p2.0);
+        let max_x = p1.0.max(p2.0);
+        let min_y = p1.1.min(p2.1);
+        let max_y = p1.1.max(p2.1);
+
+        for (a, b) in lines {
+            let crosses = if a.0 == b.0 {
+                min_x < a.0 && a.0 < max_x && a.1.min(b.1) < max_y && a.1.max(b.1) > min_y
+            } else {
+                min_y < a.1 && a.1 < max_y && a.0.min(b.0) < max_x && a.0.max(b.0) > min_x
+            };
+            if crosses {
+                return false;
+            }
+        }
+
+        if min_x < max_x && min_y < max_y {
+            return Map::is_inside_doubled((min_x * 2 + 1, min_y * 2 + 1), lines);
+        }
+        if min_x < max_x {
+            return (min_x..max_x).all(|x| Map::is_inside_doubled((x * 2 + 1, min_y * 2), lines));
+        }
+        return (min_y..max_y).all(|y| Map::is_inside_doubled((min_x * 2, y * 2 + 1), lines));
+    }
+
+    // Point-in-polygon test for a point in doubled coordinates (see `is_valid_area`), of which at
+    // least one component must be odd. Points on an edge count as inside.
+    #[cfg(test)]
+    fn is_inside_doubled(point: Point, lines: &Vec<(Point, Point)>) -> bool {
+        let (x, y) = point;
+        let mut crossings = 0;
+        for (a, b) in lines {
+            let (a, b) = ((a.0 * 2, a.1 * 2), (b.0 * 2, b.1 * 2));
+            if y % 2 != 0 {
+                // Horizontal ray to the left, only vertical edges can be crossed. The point might
+                // be on one of them, though.
+                if a.0 == b.0 && a.1.min(b.1) < y && y < a.1.max(b.1) {
+                    if a.0 == x {
+                        return true;
+                    } else if a.0 < x {
+                        crossings += 1;
+                    }
+                }
+            } else if a.1 == b.1 && a.0.min(b.0) < x && x < a.0.max(b.0) {
+                // The point is on a row with horizontal edges. Is it on one of them? If not,
+                // use a vertical ray to the top instead.
+                if a.1 == y {
+                    return true;
+                } else if a.1 < y {
+                    crossings += 1;
+                }
+            }
+        }
+        return crossings % 2 == 1;
+    }
+
+    // The original check, walking every point along the sides of the area. Superseded by
+    // `is_valid_area`, kept to cross-check it.
+    #[cfg(test)]
+    fn is_valid_area_pointwise(p1: Point, p2: Point, lines: &Vec<(Point, Point)>) -> bool {
+        let upper_left = (p1.0.min(p2.0), p1.1.min(p2.1));
+        let lower_left = (p1.0.min(p2.0), p1.1.max(p2.1));
+        let upper_right = (p1.0.max(p2.0), p1.1.min(p2.1));
+        let lower_right = (p1.0.max(p2.0), p1.1.max(p2.1));
+
+        // Check the corners first.
+        if !Map::is_inside(upper_left, lines)
+            || !Map::is_inside(lower_left, lines)
+            || !Map::is_inside(upper_right, lines)
+            || !Map::is_inside(lower_right, lines)
+        {
+            return false;
+        }
+
+        // Then check the sides. No need to check the inner parts of the area.
+        for x in (upper_left.0 + 1)..(upper_right.0) {
+            if !Map::is_inside((x, upper_left.1), lines) {
+                return false;
+            }
+            if !Map::is_inside((x, lower_left.1), lines) {
+                return false;
+            }
+        }
+        for y in (upper_left.1 + 1)..(lower_left.1) {
+            if !Map::is_inside((upper_left.0, y), lines) {
+                return false;
+            }
+            if !Map::is_inside((upper_right.0, y), lines) {
+                return false;
+            }
+        }
+
+        return true;
+    }
+
+    // Ray casting point-in-polygon test used by `is_valid_area_pointwise`. Points on an edge count
+    // as inside.
+    #[cfg(test)]
+    fn is_inside(point: Point, lines: &Vec<(Point, Point)>) -> bool {
+        let mut hit_lines = 0;
+        for line in lines {
+            match Map::hits_line(point, line) {
+                HitResult::Hit => hit_lines += 1,
+                HitResult::OnLine => return true,
+                HitResult::Miss => {}
+            }
+        }
+
+        return hit_lines % 2 == 1;
+    }
+
+    // Point-in-polygon test using the winding number instead of the ray caster's parity, as an
+    // independent check of it. Like `is_inside`, points on the boundary are inside. Only the
+    // vertical lines contribute, counted as +1 or -1 depending on their direction if they cross
+    // the ray from the point to the right (with the same half-open rule for their ends as
+    // `hits_line`). A point is inside if the winding number is nonzero, so holes only count as
+    // outside if they run in the opposite direction of the outer boundary. Only used to check the
+    // ray caster, on polygons without holes.
+    #[cfg(test)]
+    fn is_inside_winding(point: Point, lines: &Vec<(Point, Point)>) -> bool {
+        let (x, y) = point;
+        let mut winding = 0;
+        for (a, b) in lines {
+            let (min_x, max_x) = (a.0.min(b.0), a.0.max(b.0));
+            let (min_y, max_y) = (a.1.min(b.1), a.1.max(b.1));
+            if min_x <= x && x <= max_x && min_y <= y && y <= max_y {
+                return true;
+            }
+            if a.0 != b.0 || x > a.0 {
+                continue;
+            }
+            if a.1 <= y && y < b.1 {
+                winding += 1;
+            } else if b.1 <= y && y < a.1 {
+                winding -= 1;
+            }
+        }
+        return winding != 0;
+    }
+
+    #[cfg(test)]
+    fn hits_line(point: Point, line: &(Point, Point)) -> HitResult {
+        // Assume a ray from (0, y) - (x, y). Check if there is an intersection with the line.
+        let x = point.0;
+        let y = point.1;
+
+        // Only have rectangles, so either the y coordindates or x coordinates are the same. This
+        // is ensured by `Map::validate_segments`.
+        let (p1, p2) = line;
+
+        if (x == p1.0 && y == p1.1) || (x == p2.0 && y == p2.1) {
+            // Has hit one of the edges.
+            return HitResult::OnLine;
+        }
+
+        if p1.1 == p2.1 {
+            // Special case: horizontal line hit?
+            if y == p1.1 && x > p1.0.min(p2.0) && x < p1.0.max(p2.0) {
+                // It's inside the line.
+                return HitResult::OnLine;
+            }
+
+            // Otherwise, a ray running along the line doesn't cross it. Whether the polygon's
+            // boundary is crossed there is decided by the vertical lines connected to it.
+            return HitResult::Miss;
+        }
+
+        let min_y = p1.1.min(p2.1);
+        let max_y = p1.1.max(p2.1);
+        if x == p1.0 && y > min_y && y < max_y {
+            // Direct hit.
+            return HitResult::OnLine;
+        }
+
+        // The ray passing through the lower end of the line does count, the upper end does not.
+        // This way, passing a vertex where the boundary turns back (both lines are above or below
+        // the ray) counts as 0 or 2 hits, while passing a vertex where it continues counts once.
+        // Originally both ends counted, which got such vertices wrong.
+        if y < min_y || y >= max_y {
+            return HitResult::Miss;
+        }
+
+        if x < p1.0 {
+            // Too short, misses.
+            return HitResult::Miss;
+        } else {
+            // Has crossed the line.
+            return HitResult::Hit;
+        }
+    }
+}
+
+impl<'a> ProgressTracker<'a> {
+    fn new(reporter: &'a dyn ProgressReporter, total: usize, interval: Duration) -> Self {
+        return ProgressTracker {
+            reporter,
+            total,
+            interval,
+            start: Instant::now(),
+            done: AtomicUsize::new(0),
+            last: Mutex::new(None),
+        };
+    }
+
+    fn advance(&self, pairs: usize, best_area: i128) {
+        let done = self.done.fetch_add(pairs, Ordering::Relaxed) + pairs;
+        let mut last = self.last.lock().unwrap();
+        if let Some((reported_at, _)) = &*last
+            && done < self.total
+            && reported_at.elapsed() < self.interval
+        {
+            return;
+        }
+
+        let previous = last.as_ref().map(|(_, update)| update);
+        let done = done.max(previous.map_or(0, |update| update.done));
+        let elapsed = self.start.elapsed();
+        let mut remaining = if done > 0 {
+            Some(elapsed.mul_f64((self.total - done) as f64 / done as f64))
+        } else {
+            None
+        };
+        if let Some(previous_remaining) = previous.and_then(|update| update.remaining) {
+            remaining = remaining.map(|remaining| remaining.min(previous_remaining));
+        }
+
+        let update = ProgressUpdate {
+            done,
+            total: self.total,
+            best_area: best_area.max(previous.map_or(0, |update| update.best_area)),
+            elapsed,
+            remaining,
+        };
+        self.reporter.report(&update);
+        *last = Some((Instant::now(), update));
+    }
+}
+
+impl InsideGrid {
+    fn new(compressor: &CoordinateCompressor, lines: &Vec<(Point, Point)>) -> InsideGrid {
+        let (compressed_width, compressed_height) = compressor.dimensions();
+        let width = compressed_width * 2 + 1;
+        let height = compressed_height * 2 + 1;
+
+        // Draw the polygon's edges.
+        let mut edges = vec![false; width * height];
+        for (a, b) in lines {
+            let (x1, x2) = (a.0.min(b.0) as usize, a.0.max(b.0) as usize);
+            let (y1, y2) = (a.1.min(b.1) as usize, a.1.max(b.1) as usize);
+            for y in (y1 * 2 + 1)..=(y2 * 2 + 1) {
+                for x in (x1 * 2 + 1)..=(x2 * 2 + 1) {
+                    edges[y * width + x] = true;
+                }
+            }
+        }
+
+        // Flood fill the regions separated by the edges. The one connected to the border is
+        // outside. Any other region is either inside the polygon or inside one of its holes,
+        // which is decided by counting the edges to its left (even-odd rule). The edges
+        // themselves are inside.
+        let mut inside = vec![true; width * height];
+        let mut visited = edges.clone();
+        for start in 0..width * height {
+            if visited[start] {
+                continue;
+            }
+
+            // The top-left-most cell of a region is below a horizontal edge or the end of a
+            // vertical edge, so it's in a row between compressed coordinates. Only vertical edges
+            // cross such a row.
+            let (start_x, start_y) = (start % width, start / width);
+            let crossings = (0..start_x).filter(|x| edges[start_y * width + x]).count();
+            let region_inside = start != 0 && crossings % 2 == 1;
+
+            let mut stack = vec![start];
+            visited[start] = true;
+            while let Some(index) = stack.pop() {
+                inside[index] = region_inside;
+                let (x, y) = (index % width, index / width);
+                let mut neighbors = Vec::with_capacity(4);
+                if x > 0 {
+                    neighbors.push(index - 1);
+                }
+                if x + 1 < width {
+                    neighbors.push(index + 1);
+                }
+                if y > 0 {
+                    neighbors.push(index - width);
+                }
+                if y + 1 < height {
+                    neighbors.push(index + width);
+                }
+                for neighbor in neighbors {
+                    if !visited[neighbor] {
+                        visited[neighbor] = true;
+                        stack.push(neighbor);
+                    }
+                }
+            }
+        }
+
+        let mut sums = vec![0; (width + 1) * (height + 1)];
+        for y in 0..height {
+            for x in 0..width {
+                sums[(y + 1) * (width + 1) + x + 1] = inside[y * width + x] as usize
+                    + sums[y * (width + 1) + x + 1]
+                    + sums[(y + 1) * (width + 1) + x]
+                    - sums[y * (width + 1) + x];
+            }
+        }
+
+        return InsideGrid {
+            width,
+            inside,
+            sums,
+        };
+    }
+
+    // Whether a point in compressed space is inside the polygon (or on its edge).
+    #[cfg(test)]
+    fn is_inside(&self, point: Point) -> bool {
+        return self.inside[(point.1 as usize * 2 + 1) * self.width + point.0 as usize * 2 + 1];
+    }
+
+    // Whether the whole rectangle spanned by the two points (in compressed space) is inside the
+    // polygon, including the gaps between the compressed coordinates.
+    fn is_valid_area(&self, p1: Point, p2: Point) -> bool {
+        let min_x = p1.0.min(p2.0) as usize * 2 + 1;
+        let max_x = p1.0.max(p2.0) as usize * 2 + 2;
+        let min_y = p1.1.min(p2.1) as usize * 2 + 1;
+        let max_y = p1.1.max(p2.1) as usize * 2 + 2;
+
+        let row = self.width + 1;
+        let count = self.sums[max_y * row + max_x] + self.sums[min_y * row + min_x]
+            - self.sums[min_y * row + max_x]
+            - self.sums[max_y * row + min_x];
+        return count == (max_x - min_x) * (max_y - min_y);
+    }
+}
+
+impl CoordinateCompressor {
+    fn from_map(map: &Map) -> CoordinateCompressor {
+        let mut compressed_x = HashMap::new();
+        let mut compressed_y = HashMap::new();
+        let mut compressed_points = HashMap::new();
+
+        let mut xs = BTreeSet::new();
+        let mut ys = BTreeSet::new();
+        for point in map.loops().flatten() {
+            xs.insert(point.0);
+            ys.insert(point.1);
+        }
+
+        for (i, x) in xs.iter().enumerate() {
+            compressed_x.insert(*x, i as i64);
+        }
+        for (i, y) in ys.iter().enumerate() {
+            compressed_y.insert(*y, i as i64);
+        }
+
+        let mut compressed_loops = Vec::new();
+        for tiles in map.loops() {
+            let mut compressed_tiles = Vec::new();
+            for point in tiles {
+                let mapped_x = compressed_x.get(&point.0).unwrap();
+                let mapped_y = compressed_y.get(&point.1).unwrap();
+                compressed_points.insert((*mapped_x, *mapped_y), *point);
+                compressed_tiles.push((*mapped_x, *mapped_y));
+            }
+            compressed_loops.push(compressed_tiles);
+        }
+
+        return CoordinateCompressor {
+            tiles: compressed_loops.concat(),
+            loops: compressed_loops,
+            compressed_points,
+            xs: xs.into_iter().collect(),
+            ys: ys.into_iter().collect(),
+        };
+    }
+
+    // The edges of all loops in compressed space. Consecutive collinear edges are merged.
+    fn lines(&self) -> Vec<(Point, Point)> {
+        let mut lines = Vec::new();
+        for tiles in &self.loops {
+            let mut closed = merge_collinear(tiles);
+            closed.push(closed[0]);
+            lines.extend(closed.windows(2).map(|p| (p[0], p[1])));
+        }
+        return lines;
+    }
+
+    // Size of the compressed grid.
+    fn dimensions(&self) -> (usize, usize) {
+        let width = self.tiles.iter().map(|p| p.0).max().map_or(0, |x| x + 1);
+        let height = self.tiles.iter().map(|p| p.1).max().map_or(0, |y| y + 1);
+        return (width as usize, height as usize);
+    }
+
+    // The cell of `InsideGrid` that contains the point in uncompressed space. Coordinates between
+    // the compressed ones map to the cells in between. `None` if the point is outside the
+    // bounding box of the tiles.
+    fn grid_cell(&self, point: Point) -> Option<(usize, usize)> {
+        let cell = |coordinates: &Vec<i64>, value: i64| match coordinates.binary_search(&value) {
+            Ok(index) => Some(index * 2 + 1),
+            Err(index) if index > 0 && index < coordinates.len() => Some(index * 2),
+            Err(_) => None,
+        };
+        return Some((cell(&self.xs, point.0)?, cell(&self.ys, point.1)?));
+    }
+
+    // Maps one of the tiles back to uncompressed space.
+    fn decompress_tile(&self, point: &Point) -> Point {
+        return *self.compressed_points.get(point).unwrap();
+    }
+
+    #[allow(dead_code)]
+    fn decompress_x(&self, cx: i64) -> Option<i64> {
+        return usize::try_from(cx)
+            .ok()
+            .and_then(|i| self.xs.get(i))
+            .copied();
+    }
+
+    #[allow(dead_code)]
+    fn decompress_y(&self, cy: i64) -> Option<i64> {
+        return usize::try_from(cy)
+            .ok()
+            .and_then(|i| self.ys.get(i))
+            .copied();
+    }
+
+    // Maps any point of the compressed lattice back to uncompressed space.
+    #[allow(dead_code)]
+    fn decompress(&self, point: &Point) -> Option<Point> {
+        return Some((self.decompress_x(point.0)?, self.decompress_y(point.1)?));
+    }
+
+    // Only succeeds for coordinates that are used by the polygon.
+    #[allow(dead_code)]
+    fn compress_x(&self, x: i64) -> Option<i64> {
+        return self.xs.binary_search(&x).ok().map(|i| i as i64);
+    }
+
+    #[allow(dead_code)]
+    fn compress_y(&self, y: i64) -> Option<i64> {
+        return self.ys.binary_search(&y).ok().map(|i| i as i64);
+    }
+}
+
+// Draws the polygon with the best rectangles of both parts on top. The tiles are drawn as unit
+// squares, so the outline goes through the centers of the red tiles while the rectangles cover
+// their tiles completely. Like in the puzzle, y grows downwards, which is what SVG does as well.
+pub fn render_svg(map: &Map, part1: &BestRect, part2: &BestRect) -> String {
+    let tiles = map.all_tiles();
+    let min_x = tiles.iter().map(|p| p.0).min().unwrap_or(0);
+    let max_x = tiles.iter().map(|p| p.0).max().unwrap_or(0) + 1;
+    let min_y = tiles.iter().map(|p| p.1).min().unwrap_or(0);
+    let max_y = tiles.iter().map(|p| p.1).max().unwrap_or(0) + 1;
+    let margin = ((max_x - min_x).max(max_y - min_y) / 20).max(1);
+
+    // One closed subpath per loop.
+    let mut path = String::new();
+    for tiles in map.loops() {
+        for (index, point) in tiles.iter().enumerate() {
+            let command = if index == 0 { 'M' } else { 'L' };
+            path += &format!(
+                "{} {} {} ",
+                command,
+                point.0 as f64 + 0.5,
+                point.1 as f64 + 0.5
+            );
+        }
+        path += "Z ";
+    }
+    let path = path.trim_end();
+
+    let rect = |best: &BestRect, color: &str| {
+        let (upper_left, lower_right) = (best.upper_left(), best.lower_right());
+        return format!(
+            "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" fill-opacity=\"0.4\"/>\n",
+            upper_left.0,
+            upper_left.1,
+            lower_right.0 - upper_left.0 + 1,
+            lower_right.1 - upper_left.1 + 1,
+            color
+        );
+    };
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+        min_x - margin,
+        min_y - margin,
+        max_x - min_x + 2 * margin,
+        max_y - min_y + 2 * margin
+    );
+    svg += &rect(part1, "blue");
+    svg += &rect(part2, "green");
+    svg += &format!(
+        "  <path d=\"{}\" fill=\"none\" stroke=\"red\" vector-effect=\"non-scaling-stroke\"/>\n",
+        path
+    );
+    svg += "</svg>\n";
+    return svg;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_validate_sample() {
+        let map = Map::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        assert!(map.validate_no_self_intersections().is_ok());
+    }
+
+    #[test]
+    fn test_best_rect_sample() {
+        let map = Map::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+
+        // (2,3)-(11,7) has the same area, but a larger upper-left corner.
+        let best = map.max_area_simple().unwrap();
+        assert_eq!(best.area, 50);
+        assert_eq!((best.upper_left(), best.lower_right()), ((2, 1), (11, 5)));
+        assert_eq!((best.p1, best.p2), ((11, 1), (2, 5)));
+
+        let best = map.max_area_complicated().unwrap();
+        assert_eq!(best.area, 24);
+        assert_eq!((best.p1, best.p2), ((9, 5), (2, 3)));
+    }
+
+    #[test]
+    fn test_area() {
+        // Both corner tiles are part of the rectangle.
+        assert_eq!(area((1, 1), (1, 1)), 1);
+        assert_eq!(area((2, 5), (11, 1)), 50);
+        assert_eq!(area((11, 1), (2, 5)), 50);
+        assert_eq!(area((-2, -1), (2, 1)), 15);
+    }
+
+    #[test]
+    fn test_hits_line() {
+        let vertical = ((5, 0), (5, 10));
+        let horizontal = ((0, 3), (8, 3));
+
+        // On a vertex or on an edge.
+        assert_eq!(Map::hits_line((5, 0), &vertical), HitResult::OnLine);
+        assert_eq!(Map::hits_line((5, 4), &vertical), HitResult::OnLine);
+        assert_eq!(Map::hits_line((4, 3), &horizontal), HitResult::OnLine);
+
+        // The ray from the left ends before the line.
+        assert_eq!(Map::hits_line((3, 5), &vertical), HitResult::Miss);
+        assert_eq!(Map::hits_line((7, 11), &vertical), HitResult::Miss);
+
+        // Crosses the line, including its lower end but not its upper one.
+        assert_eq!(Map::hits_line((7, 5), &vertical), HitResult::Hit);
+        assert_eq!(Map::hits_line((7, 0), &vertical), HitResult::Hit);
+        assert_eq!(Map::hits_line((7, 10), &vertical), HitResult::Miss);
+
+        // Running along a horizontal line doesn't cross it.
+        assert_eq!(Map::hits_line((10, 3), &horizontal), HitResult::Miss);
+    }
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        let map = Map::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        assert_eq!(
+            map.max_area_compressed(true).unwrap(),
+            map.max_area_compressed(false).unwrap()
+        );
+    }
+
+    // Generates a polygon made of adjacent columns of random height (like a histogram that can
+    // extend both up and down). All coordinates are even, so between two distinct coordinates
+    // there's always at least one more integer.
+    fn random_polygon(columns: usize, seed: u64) -> Vec<Point> {
+        let mut state = seed.max(1);
+        let mut xs = vec![0];
+        let mut lows: Vec<i64> = Vec::new();
+        let mut highs: Vec<i64> = Vec::new();
+        for _ in 0..columns {
+            xs.push(xs.last().unwrap() + 2 + 2 * (next_random(&mut state) % 3) as i64);
+            loop {
+                let low = 2 * (next_random(&mut state) % 6) as i64;
+                let high = 12 + 2 * (next_random(&mut state) % 6) as i64;
+                if lows.last() != Some(&low) && highs.last() != Some(&high) {
+                    lows.push(low);
+                    highs.push(high);
+                    break;
+                }
+            }
+        }
+
+        let mut tiles = vec![(xs[0], highs[0])];
+        for i in 0..columns {
+            tiles.push((xs[i + 1], highs[i]));
+            if i + 1 < columns {
+                tiles.push((xs[i + 1], highs[i + 1]));
+            }
+        }
+        for i in (0..columns).rev() {
+            tiles.push((xs[i + 1], lows[i]));
+            tiles.push((xs[i], lows[i]));
+        }
+        // The last tile is directly below the first one, don't repeat it.
+        tiles.dedup();
+        return tiles;
+    }
+
+    // Independent, slow check of whether an integer point is inside or on the polygon.
+    fn brute_force_inside(point: Point, tiles: &[Point]) -> bool {
+        let mut crossings = 0;
+        for i in 0..tiles.len() {
+            let (a, b) = (tiles[i], tiles[(i + 1) % tiles.len()]);
+            let (min_x, max_x) = (a.0.min(b.0), a.0.max(b.0));
+            let (min_y, max_y) = (a.1.min(b.1), a.1.max(b.1));
+            if min_x <= point.0 && point.0 <= max_x && min_y <= point.1 && point.1 <= max_y {
+                return true;
+            }
+            if a.0 == b.0 && a.0 < point.0 && min_y <= point.1 && point.1 < max_y {
+                crossings += 1;
+            }
+        }
+        return crossings % 2 == 1;
+    }
+
+    #[test]
+    fn test_is_valid_area_sample() {
+        let map = Map::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        let compressor = CoordinateCompressor::from_map(&map);
+        let lines = compressor.lines();
+
+        for &p1 in &compressor.tiles {
+            for &p2 in &compressor.tiles {
+                assert_eq!(
+                    Map::is_valid_area(p1, p2, &lines),
+                    Map::is_valid_area_pointwise(p1, p2, &lines)
+                );
+            }
+        }
+        assert_eq!(map.max_area_complicated().unwrap().area, 24);
+    }
+
+    #[test]
+    fn test_is_valid_area_random() {
+        // The pointwise check gets confused by rays passing through vertices, so compare against
+        // a brute force check of every point instead.
+        for seed in 1..=50 {
+            let map = Map {
+                tiles: random_polygon(6, seed),
+                holes: Vec::new(),
+            };
+            Map::validate_segments(&map.tiles).unwrap();
+            map.validate_no_self_intersections().unwrap();
+
+            let compressor = CoordinateCompressor::from_map(&map);
+            let lines = compressor.lines();
+            for (i, &p1) in compressor.tiles.iter().enumerate() {
+                for &p2 in &compressor.tiles[i + 1..] {
+                    let (u1, u2) = (
+                        compressor.decompress_tile(&p1),
+                        compressor.decompress_tile(&p2),
+                    );
+                    let expected = (u1.0.min(u2.0)..=u1.0.max(u2.0)).all(|x| {
+                        (u1.1.min(u2.1)..=u1.1.max(u2.1))
+                            .all(|y| brute_force_inside((x, y), &map.tiles))
+                    });
+                    assert_eq!(
+                        Map::is_valid_area(p1, p2, &lines),
+                        expected,
+                        "seed {}, {:?} {:?}",
+                        seed,
+                        u1,
+                        u2
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_inside_grid_sample() {
+        let map = Map::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        let compressor = CoordinateCompressor::from_map(&map);
+        let lines = compressor.lines();
+        let grid = InsideGrid::new(&compressor, &lines);
+        let (width, height) = compressor.dimensions();
+
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                assert_eq!(grid.is_inside((x, y)), Map::is_inside((x, y), &lines));
+            }
+        }
+        for &p1 in &compressor.tiles {
+            for &p2 in &compressor.tiles {
+                assert_eq!(
+                    grid.is_valid_area(p1, p2),
+                    Map::is_valid_area_pointwise(p1, p2, &lines)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_inside_grid_random() {
+        // Compare against the brute force check for the points, and against the ray casting of
+        // `is_inside_doubled` for the gaps in between.
+        for seed in 1..=50 {
+            let map = Map {
+                tiles: random_polygon(6, seed),
+                holes: Vec::new(),
+            };
+            let compressor = CoordinateCompressor::from_map(&map);
+            let lines = compressor.lines();
+            let grid = InsideGrid::new(&compressor, &lines);
+            let (width, height) = compressor.dimensions();
+
+            for y in 0..(height * 2 - 1) as i64 {
+                for x in 0..(width * 2 - 1) as i64 {
+                    let expected = if x % 2 == 0 && y % 2 == 0 {
+                        let point = compressor.decompress(&(x / 2, y / 2)).unwrap();
+                        brute_force_inside(point, &map.tiles)
+                    } else {
+                        Map::is_inside_doubled((x, y), &lines)
+                    };
+                    let actual = grid.inside[(y as usize + 1) * grid.width + x as usize + 1];
+                    assert_eq!(actual, expected, "seed {}, ({}, {})", seed, x, y);
+                }
+            }
+
+            for &p1 in &compressor.tiles {
+                for &p2 in &compressor.tiles {
+                    assert_eq!(
+                        grid.is_valid_area(p1, p2),
+                        Map::is_valid_area(p1, p2, &lines)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_not_rectilinear() {
+        let result = Map::from_input("0,0\n5,0\n7,3\n0,3");
+        assert!(matches!(
+            result,
+            Err(Error::NotRectilinear {
+                index: 1,
+                a: (5, 0),
+                b: (7, 3)
+            })
+        ));
+    }
+
+    #[test]
+    fn test_degenerate_segment() {
+        // Duplicates are removed, so this can only happen if there's just a single tile.
+        let result = Map::from_input("5,0\n5,0");
+        assert!(matches!(result, Err(Error::DegenerateSegment { index: 0 })));
+    }
+
+    #[test]
+    fn test_normalize() {
+        // (4,0) is repeated, and splits the edge from (0,0) to (8,0). The closing (0,0) repeats the
+        // first tile.
+        let (map, normalization) =
+            Map::from_input_normalized("0,0\n4,0\n4,0\n8,0\n8,6\n0,6\n0,0").unwrap();
+        assert_eq!(
+            normalization,
+            Normalization {
+                duplicates: 2,
+                collinear: 1
+            }
+        );
+        assert_eq!(map.tiles, vec![(0, 0), (4, 0), (8, 0), (8, 6), (0, 6)]);
+
+        let compressor = CoordinateCompressor::from_map(&map);
+        assert_eq!(compressor.lines().len(), 4);
+        assert_eq!(map.max_area_simple().unwrap().area, 63);
+        assert_eq!(map.max_area_complicated().unwrap().area, 63);
+
+        // Splits that only show up after merging others, and across the start of the list.
+        let map = Map::from_input("2,0\n4,0\n4,2\n4,4\n0,4\n0,0\n1,0").unwrap();
+        assert_eq!(
+            merge_collinear(&map.tiles),
+            vec![(4, 0), (4, 4), (0, 4), (0, 0)]
+        );
+        assert_eq!(map.max_area_complicated().unwrap().area, 25);
+
+        // Doubling back is not a split.
+        let spike = [(0, 0), (4, 0), (4, 2), (4, 1), (0, 1)];
+        assert_eq!(merge_collinear(&spike), spike.to_vec());
+    }
+
+    #[test]
+    fn test_self_intersection() {
+        // A figure eight: the edge from (4,0) to (4,4) crosses the edge from (0,2) to (6,2).
+        let map = Map::from_input("0,0\n4,0\n4,4\n6,4\n6,2\n0,2").unwrap();
+        assert!(matches!(
+            map.validate_no_self_intersections(),
+            Err(Error::SelfIntersecting {
+                first: 1,
+                second: 4
+            })
+        ));
+
+        // Doubling back onto the previous edge.
+        let map = Map::from_input("0,0\n6,0\n3,0\n3,3\n0,3").unwrap();
+        assert!(matches!(
+            map.validate_no_self_intersections(),
+            Err(Error::SelfIntersecting {
+                first: 0,
+                second: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_decompress() {
+        let map = Map::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        let compressor = CoordinateCompressor::from_map(&map);
+        let (width, height) = compressor.dimensions();
+
+        for cy in 0..height as i64 {
+            for cx in 0..width as i64 {
+                let (x, y) = compressor.decompress(&(cx, cy)).unwrap();
+                assert_eq!(compressor.compress_x(x), Some(cx));
+                assert_eq!(compressor.compress_y(y), Some(cy));
+            }
+        }
+        for (tile, original) in compressor.tiles.iter().zip(&map.tiles) {
+            assert_eq!(compressor.decompress(tile), Some(*original));
+            assert_eq!(compressor.decompress_tile(tile), *original);
+        }
+
+        assert_eq!(compressor.decompress_x(-1), None);
+        assert_eq!(compressor.decompress_x(width as i64), None);
+        assert_eq!(compressor.decompress_y(height as i64), None);
+        assert_eq!(compressor.decompress(&(0, -1)), None);
+        assert_eq!(compressor.compress_x(0), None);
+        assert_eq!(compressor.compress_y(4), None);
+    }
+
+    #[test]
+    fn test_large_coordinates() {
+        let map = Map::from_input(
+            "-3000000000,-3000000000\n\
+             3000000000,-3000000000\n\
+             3000000000,0\n\
+             0,0\n\
+             0,3000000000\n\
+             -3000000000,3000000000",
+        )
+        .unwrap();
+
+        // (6 * 10^9 + 1)², which doesn't fit into an i64.
+        let best = map.max_area_simple().unwrap();
+        assert!(best.area > i64::MAX as i128);
+        assert_eq!(best.area.to_string(), "36000000012000000001");
+
+        // The L-shape limits the area to one of its arms.
+        let best = map.max_area_complicated().unwrap();
+        assert_eq!(best.area.to_string(), "18000000009000000001");
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let map = Map::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        let part1 = map.max_area_simple().unwrap();
+        let part2 = map.max_area_complicated().unwrap();
+        let svg = render_svg(&map, &part1, &part2);
+        assert_eq!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"1 0 12 9\">\n\
+             \x20 <rect x=\"2\" y=\"1\" width=\"10\" height=\"5\" fill=\"blue\" fill-opacity=\"0.4\"/>\n\
+             \x20 <rect x=\"2\" y=\"3\" width=\"8\" height=\"3\" fill=\"green\" fill-opacity=\"0.4\"/>\n\
+             \x20 <path d=\"M 7.5 1.5 L 11.5 1.5 L 11.5 7.5 L 9.5 7.5 L 9.5 5.5 L 2.5 5.5 L 2.5 3.5 L 7.5 3.5 Z\" \
+             fill=\"none\" stroke=\"red\" vector-effect=\"non-scaling-stroke\"/>\n\
+             </svg>\n"
+        );
+    }
+
+    fn random_points(count: usize, range: u64, seed: u64) -> Vec<Point> {
+        let mut state = seed.max(1);
+        return (0..count)
+            .map(|_| {
+                let x = (next_random(&mut state) % range) as i64;
+                let y = (next_random(&mut state) % range) as i64;
+                (x, y)
+            })
+            .collect();
+    }
+
+    #[test]
+    fn test_max_area_simple_random() {
+        for seed in 1..=40 {
+            // Few distinct coordinates to provoke ties and duplicates.
+            let count = [2, 3, 10, 100, 2000][seed as usize % 5];
+            let range = if seed % 2 == 0 { 20 } else { 1_000_000 };
+            let map = Map {
+                tiles: random_points(count, range, seed),
+                holes: Vec::new(),
+            };
+            let fast = map.max_area_simple().unwrap();
+            let brute_force = map.max_area_simple_brute_force().unwrap();
+            assert_eq!(fast.area, brute_force.area, "seed {}", seed);
+            assert_eq!(
+                (fast.upper_left(), fast.lower_right()),
+                (brute_force.upper_left(), brute_force.lower_right()),
+                "seed {}",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn test_holes() {
+        let map = Map::from_input("0,0\n10,0\n10,10\n0,10\n\n3,3\n5,3\n5,7\n3,7").unwrap();
+        assert_eq!(map.tiles.len(), 4);
+        assert_eq!(map.holes, vec![vec![(3, 3), (5, 3), (5, 7), (3, 7)]]);
+        assert!(map.validate_no_self_intersections().is_ok());
+
+        // Part 1 doesn't care about the hole.
+        assert_eq!(map.max_area_simple().unwrap().area, 121);
+
+        // The rectangles right of the hole, from x = 5 to 10, are the largest ones touching the
+        // hole only at its edge: 6 * 8. The upper one wins the tie.
+        let best = map.max_area_complicated().unwrap();
+        assert_eq!(best.area, 48);
+        assert_eq!((best.p1, best.p2), ((10, 0), (5, 7)));
+
+        let compressor = CoordinateCompressor::from_map(&map);
+        let lines = compressor.lines();
+        assert_eq!(lines.len(), 8);
+        let grid = InsideGrid::new(&compressor, &lines);
+        // The hole's corner is on its edge, but the middle of the hole is not inside.
+        assert!(grid.is_inside((1, 1)));
+        assert!(!grid.inside[4 * grid.width + 4]);
+        assert!(!Map::is_inside_doubled((3, 3), &lines));
+        for &p1 in &compressor.tiles {
+            for &p2 in &compressor.tiles {
+                assert_eq!(
+                    grid.is_valid_area(p1, p2),
+                    Map::is_valid_area(p1, p2, &lines)
+                );
+            }
+        }
+
+        // The hole touches the outer boundary.
+        let map = Map::from_input("0,0\n10,0\n10,10\n0,10\n\n0,3\n5,3\n5,7\n0,7").unwrap();
+        assert!(matches!(
+            map.validate_no_self_intersections(),
+            Err(Error::SelfIntersecting {
+                first: 3,
+                second: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn test_max_area_containing() {
+        let map = Map::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+
+        // The widest part contains the best rectangle overall.
+        let best = map.max_area_containing((3, 4)).unwrap().unwrap();
+        assert_eq!((best.area, best.p1, best.p2), (24, (9, 5), (2, 3)));
+
+        // The right arm only fits narrow rectangles.
+        let best = map.max_area_containing((10, 6)).unwrap().unwrap();
+        assert_eq!(best.area, 21);
+        assert_eq!((best.upper_left(), best.lower_right()), ((9, 1), (11, 7)));
+
+        // On the edge.
+        let best = map.max_area_containing((11, 1)).unwrap().unwrap();
+        assert_eq!(best.area, 21);
+
+        // Outside of the polygon, and outside of its bounding box.
+        assert!(map.max_area_containing((4, 1)).unwrap().is_none());
+        assert!(map.max_area_containing((5, 6)).unwrap().is_none());
+        assert!(map.max_area_containing((0, 0)).unwrap().is_none());
+        assert!(map.max_area_containing((20, 4)).unwrap().is_none());
+
+        // Inside a hole.
+        let map = Map::from_input("0,0\n10,0\n10,10\n0,10\n\n3,3\n5,3\n5,7\n3,7").unwrap();
+        assert!(map.max_area_containing((4, 4)).unwrap().is_none());
+        assert_eq!(map.max_area_containing((1, 5)).unwrap().unwrap().area, 32);
+    }
+
+    proptest! {
+        #[test]
+        fn test_is_inside_winding(
+            columns in 1usize..8,
+            seed in 1u64..u64::MAX,
+            x in -1i64..40,
+            y in -1i64..25,
+        ) {
+            let tiles = random_polygon(columns, seed);
+            let mut closed = tiles.clone();
+            closed.push(closed[0]);
+            let lines: Vec<(Point, Point)> = closed.windows(2).map(|p| (p[0], p[1])).collect();
+
+            let expected = brute_force_inside((x, y), &tiles);
+            prop_assert_eq!(Map::is_inside_winding((x, y), &lines), expected);
+            prop_assert_eq!(Map::is_inside((x, y), &lines), expected);
+        }
+    }
+
+    // Collects all progress updates.
+    struct RecordingProgress {
+        updates: Mutex<Vec<ProgressUpdate>>,
+    }
+
+    impl ProgressReporter for RecordingProgress {
+        fn report(&self, update: &ProgressUpdate) {
+            self.updates.lock().unwrap().push(update.clone());
+        }
+    }
+
+    #[test]
+    fn test_progress() {
+        let map = Map {
+            tiles: random_polygon(40, 7),
+            holes: Vec::new(),
+        };
+        let recording = RecordingProgress {
+            updates: Mutex::new(Vec::new()),
+        };
+        let best = map
+            .max_area_reporting(true, Some((&recording, Duration::ZERO)))
+            .unwrap();
+        assert_eq!(best, map.max_area_complicated().unwrap());
+
+        let updates = recording.updates.into_inner().unwrap();
+        let count = map.tiles.len();
+        // With an interval of 0, every first tile reports.
+        assert_eq!(updates.len(), count - 1);
+        for pair in updates.windows(2) {
+            assert!(pair[0].done <= pair[1].done);
+            assert!(pair[0].best_area <= pair[1].best_area);
+            if let (Some(first), Some(second)) = (pair[0].remaining, pair[1].remaining) {
+                assert!(first >= second);
+            }
+        }
+        let last = updates.last().unwrap();
+        assert_eq!(
+            (last.done, last.total),
+            (count * (count - 1) / 2, count * (count - 1) / 2)
+        );
+        assert_eq!(last.remaining, Some(Duration::ZERO));
+        assert_eq!(last.best_area, best.area);
+    }
+
+    #[test]
+    fn test_gen_polygon() {
+        for (n_vertices, extent, seed) in [
+            (4, 4, 1),
+            (6, 4, 2),
+            (8, 10, 3),
+            (10, 100, 4),
+            (100, 1000, 5),
+            (1002, 1000, 6),
+            (1000, 1_000_000_000, 7),
+        ] {
+            let tiles = gen_polygon(n_vertices, extent, seed).unwrap();
+            assert_eq!(tiles.len(), n_vertices);
+            assert_eq!(gen_polygon(n_vertices, extent, seed).unwrap(), tiles);
+            assert!(
+                tiles
+                    .iter()
+                    .all(|p| (0..=extent).contains(&p.0) && (0..=extent).contains(&p.1))
+            );
+
+            // Closed and rectilinear, including the edge from the last back to the first tile,
+            // without redundant vertices.
+            assert_ne!(tiles.first(), tiles.last());
+            Map::validate_segments(&tiles).unwrap();
+            assert_eq!(merge_collinear(&tiles), tiles);
+
+            let map = Map::from_input(&format_polygon(&tiles)).unwrap();
+            assert_eq!(map.tiles, tiles);
+            map.validate_no_self_intersections().unwrap();
+        }
+
+        assert!(gen_polygon(2, 100, 1).is_err());
+        assert!(gen_polygon(7, 100, 1).is_err());
+        assert!(gen_polygon(4, 3, 1).is_err());
+        assert!(gen_polygon(100, 24, 1).is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let accepted = [("1,2", (1, 2)), (" 3 , 4 ", (3, 4)), ("-5,-6", (-5, -6))];
+        for (input, expected) in accepted {
+            assert_eq!(parse_line(input), Ok(expected), "{:?}", input);
+        }
+        let map = Map::from_input("1,2\n3,2\n 3, 4\n1,4\n\n").unwrap();
+        assert_eq!(map.tiles, vec![(1, 2), (3, 2), (3, 4), (1, 4)]);
+        assert!(map.holes.is_empty());
+
+        let rejected = [
+            (
+                "1",
+                "Invalid input in line 1: expected two numbers separated by a comma ('1')",
+            ),
+            (
+                "1,2\n1,2,3",
+                "Invalid input in line 2: more than two numbers ('1,2,3')",
+            ),
+            (
+                "1,2\n\n\na,b",
+                "Invalid input in line 4: 'a' is not a number ('a,b')",
+            ),
+        ];
+        for (input, expected) in rejected {
+            let error = Map::from_input(input).err().unwrap();
+            assert!(matches!(error, Error::InvalidInput { .. }));
+            assert_eq!(error.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_few_tiles() {
+        let map = Map::from_input("2,3\n9,3").unwrap();
+        assert_eq!(map.max_area_simple().unwrap().area, 8);
+        match map.max_area_complicated() {
+            Err(Error::InvalidInput { line, text, reason }) => {
+                assert_eq!((line, text.as_str()), (1, "2,3 9,3"));
+                assert!(reason.contains("found 2"), "{}", reason);
+            }
+            result => panic!("unexpected {:?}", result),
+        }
+        assert!(matches!(
+            map.max_area_containing((5, 3)),
+            Err(Error::InvalidInput { line: 1, .. })
+        ));
+
+        // Three tiles on a line. The polygon has no inside, but its boundary is still made of
+        // red and green tiles.
+        let map = Map::from_input("2,3\n5,3\n9,3").unwrap();
+        assert_eq!(map.max_area_simple().unwrap().area, 8);
+        assert_eq!(map.max_area_complicated().unwrap().area, 8);
+    }
+
+    #[test]
+    fn test_perimeter() {
+        let (width, height) = (10, 4);
+        let map = Map::from_input("2,3\n12,3\n12,7\n2,7").unwrap();
+        assert_eq!(map.perimeter(), 2 * (width + height));
+
+        // Same rectangle, counterclockwise and with an extra tile on an edge.
+        let map = Map::from_input("2,7\n12,7\n12,3\n5,3\n2,3").unwrap();
+        assert_eq!(map.perimeter(), 2 * (width + height));
+
+        let map = Map::from_input(include_str!("../rsc/sample1.txt")).unwrap();
+        assert_eq!(map.perimeter(), 30);
+    }
+
+    #[test]
+    fn test_interior_point_count() {
+        let map = Map::from_input("2,3\n12,3\n12,7\n2,7").unwrap();
+        assert_eq!(map.interior_point_count(), 9 * 3);
+        let map = Map::from_input("2,7\n12,7\n12,3\n5,3\n2,3").unwrap();
+        assert_eq!(map.interior_point_count(), 9 * 3);
+
+        // The ray caster counts the boundary as inside as well.
+        for input in [
+            include_str!("../rsc/sample1.txt"),
+            "0,0\n6,0\n6,6\n4,6\n4,2\n2,2\n2,6\n0,6",
+        ] {
+            let map = Map::from_input(input).unwrap();
+            let lines: Vec<(Point, Point)> = (0..map.tiles.len())
+                .map(|index| (map.tiles[index], map.tiles[(index + 1) % map.tiles.len()]))
+                .collect();
+            let (max_x, max_y) = map
+                .tiles
+                .iter()
+                .fold((0, 0), |(x, y), tile| (x.max(tile.0), y.max(tile.1)));
+            let mut inside = 0;
+            for y in 0..=max_y {
+                for x in 0..=max_x {
+                    if Map::is_inside((x, y), &lines) {
+                        inside += 1;
+                    }
+                }
+            }
+            assert_eq!(map.interior_point_count() + map.perimeter(), inside);
+        }
+    }
+
+    #[test]
+    fn test_push_pop_vertex() {
+        // An L, whose best rectangles are either of its legs.
+        let mut map = Map::from_input("0,0\n10,0\n10,4\n4,4\n4,10\n0,10").unwrap();
+        let before = map.max_area_complicated().unwrap();
+        assert_eq!(before.area, 11 * 5);
+
+        // Fill in the corner to get a square.
+        assert_eq!(map.pop_vertex(), Some((0, 10)));
+        assert_eq!(map.pop_vertex(), Some((4, 10)));
+        assert_eq!(map.pop_vertex(), Some((4, 4)));
+        map.push_vertex((10, 10));
+        assert!(matches!(
+            map.validate_loops(),
+            Err(Error::NotRectilinear { .. })
+        ));
+        map.push_vertex((0, 10));
+        assert!(map.validate_loops().is_ok());
+
+        let after = map.max_area_complicated().unwrap();
+        assert!(after.area > before.area);
+        assert_eq!(after.area, 11 * 11);
+        assert_eq!(
+            map.max_area_complicated().unwrap(),
+            Map::from_input("0,0\n10,0\n10,10\n0,10")
+                .unwrap()
+                .max_area_complicated()
+                .unwrap()
+        );
+
+        let mut map = Map::from_input("").unwrap();
+        assert_eq!(map.pop_vertex(), None);
+    }
+}
@@ -0,0 +1,10 @@
+// Random numbers for generating test and benchmark inputs, shared by the days.
+
+// Simple xorshift, good enough for generating test data. A `state` of zero stays zero, so seeds
+// should be at least one.
+pub fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    return *state;
+}